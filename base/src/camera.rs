@@ -134,6 +134,7 @@ pub struct FlightCameraBuilder {
     yaw  : f32,
     pitch: f32,
 
+    zoom: f32,
     near: f32,
     far : f32,
     screen_aspect: f32,
@@ -147,6 +148,7 @@ impl Default for FlightCameraBuilder {
             world_up : Vec3F::new(0.0, 1.0, 0.0),
             yaw      : -90.0,
             pitch    : 0.0,
+            zoom     : 45.0_f32.to_radians(),
             near     : 0.1,
             far      : 100.0,
             screen_aspect: 1.0,
@@ -173,7 +175,7 @@ impl FlightCameraBuilder {
             move_speed: 2.5,
             _mouse_sensitivity: 1.0,
             _wheel_sensitivity: 1.0,
-            zoom: 45.0_f32.to_radians(),
+            zoom: self.zoom,
 
             flip_vertically: true,
         };
@@ -198,6 +200,11 @@ impl FlightCameraBuilder {
         self.pitch = pitch; self
     }
 
+    /// Set the vertical field of view in radians.
+    pub fn field_of_view(mut self, fov: f32) -> FlightCameraBuilder {
+        self.zoom = fov; self
+    }
+
     pub fn view_distance(mut self, near: f32, far: f32) -> FlightCameraBuilder {
         self.near = near;
         self.far = far; self
@@ -150,3 +150,95 @@ impl VkObjectBindable for vk::Buffer {
     }
 }
 // ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Wrapper class for `vk::BufferMemoryBarrier`.
+///
+/// The default values are defined as follows:
+/// ``` ignore
+/// vk::BufferMemoryBarrier {
+///     s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+///     p_next: ptr::null(),
+///     src_access_mask: vk::AccessFlags::empty(),
+///     dst_access_mask: vk::AccessFlags::empty(),
+///     src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+///     dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+///     buffer: vk::Buffer::null(),
+///     offset: 0,
+///     size  : vk::WHOLE_SIZE,
+/// }
+/// ```
+///
+/// See [VkBufferMemoryBarrier](https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/VkBufferMemoryBarrier.html) for more detail.
+///
+#[derive(Debug, Clone)]
+pub struct BufferBarrierCI {
+    inner: vk::BufferMemoryBarrier,
+}
+
+impl VulkanCI<vk::BufferMemoryBarrier> for BufferBarrierCI {
+
+    fn default_ci() -> vk::BufferMemoryBarrier {
+
+        vk::BufferMemoryBarrier {
+            s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+            p_next: ptr::null(),
+            src_access_mask: vk::AccessFlags::empty(),
+            dst_access_mask: vk::AccessFlags::empty(),
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer: vk::Buffer::null(),
+            offset: 0,
+            size  : vk::WHOLE_SIZE,
+        }
+    }
+}
+
+impl AsRef<vk::BufferMemoryBarrier> for BufferBarrierCI {
+
+    fn as_ref(&self) -> &vk::BufferMemoryBarrier {
+        &self.inner
+    }
+}
+
+impl BufferBarrierCI {
+
+    /// Initialize `vk::BufferMemoryBarrier` with default value.
+    ///
+    /// `buffer` is the buffer affected by this barrier.
+    ///
+    /// `offset` and `size` specify the range of `buffer` affected by this barrier.
+    pub fn new(buffer: vk::Buffer, offset: vkbytes, size: vkbytes) -> BufferBarrierCI {
+
+        BufferBarrierCI {
+            inner: vk::BufferMemoryBarrier {
+                buffer, offset, size,
+                ..BufferBarrierCI::default_ci()
+            },
+        }
+    }
+
+    /// Set the `src_access_mask` and `dst_access_mask` members for `vk::BufferMemoryBarrier`.
+    #[inline(always)]
+    pub fn access_mask(mut self, from: vk::AccessFlags, to: vk::AccessFlags) -> Self {
+        self.inner.src_access_mask = from;
+        self.inner.dst_access_mask = to; self
+    }
+
+    /// Set the `src_queue_family_index` and `dst_queue_family_index` members for `vk::BufferMemoryBarrier`.
+    ///
+    /// It specifies the queue family ownership transfer for the buffer.
+    #[inline(always)]
+    pub fn queue_family_index(mut self, from: vkuint, to: vkuint) -> Self {
+        self.inner.src_queue_family_index = from;
+        self.inner.dst_queue_family_index = to; self
+    }
+}
+
+impl From<BufferBarrierCI> for vk::BufferMemoryBarrier {
+
+    fn from(v: BufferBarrierCI) -> vk::BufferMemoryBarrier {
+        v.inner
+    }
+}
+// ----------------------------------------------------------------------------------------------
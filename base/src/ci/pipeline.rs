@@ -384,6 +384,65 @@ impl VkObjectDiscardable for vk::Pipeline {
 }
 // ----------------------------------------------------------------------------------------------
 
+// ----------------------------------------------------------------------------------------------
+// Wrapper class for vk::ComputePipelineCreateInfo.
+#[derive(Debug, Clone)]
+pub struct ComputePipelineCI<'a> {
+
+    inner: vk::ComputePipelineCreateInfo,
+    cache: Option<vk::PipelineCache>,
+
+    phantom_type: ::std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> VkObjectBuildableCI for ComputePipelineCI<'a> {
+    type ObjectType = vk::Pipeline;
+
+    fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
+
+        let pipeline = unsafe {
+            device.logic.handle.create_compute_pipelines(self.cache.unwrap_or(device.pipeline_cache), &[self.inner], None)
+                .map_err(|_| VkError::create("Compute Pipeline"))?
+        }.remove(0);
+
+        Ok(pipeline)
+    }
+}
+
+impl<'a> ComputePipelineCI<'a> {
+
+    /// `shader` must outlive this struct, since its entry name is referenced by pointer.
+    pub fn new(pipeline_layout: vk::PipelineLayout, shader: &'a ShaderStageCI) -> ComputePipelineCI<'a> {
+
+        ComputePipelineCI {
+            inner: vk::ComputePipelineCreateInfo {
+                stage: shader.as_ref().clone(),
+                layout: pipeline_layout,
+                base_pipeline_index: -1,
+                ..Default::default()
+            },
+            cache: None,
+            phantom_type: ::std::marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_base_pipeline(&mut self, pipeline: vk::Pipeline) {
+        self.inner.base_pipeline_handle = pipeline;
+    }
+
+    #[inline(always)]
+    pub fn set_flags(&mut self, flags: vk::PipelineCreateFlags) {
+        self.inner.flags = flags;
+    }
+
+    #[inline(always)]
+    pub fn set_pipeline_cache(&mut self, cache: vk::PipelineCache) {
+        self.cache = Some(cache);
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
 // ----------------------------------------------------------------------------------------------
 // Wrapper class for vk::PipelineCacheCreateInfo.
 #[derive(Debug, Clone)]
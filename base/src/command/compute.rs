@@ -1,8 +1,10 @@
 
 use ash::vk;
+use ash::version::DeviceV1_0;
 
 use crate::command::VkCommandType;
 use crate::command::recorder::VkCmdRecorder;
+use crate::vkuint;
 
 pub struct ICompute;
 
@@ -11,9 +13,47 @@ impl VkCommandType for ICompute {
 }
 
 impl<'a> CmdComputeApi for VkCmdRecorder<'a, ICompute> {
-    // Not implement yet...
+
+    fn push_constants(&self, layout: vk::PipelineLayout, offset: vkuint, data: &[u8]) -> &Self {
+        unsafe {
+            self.device.handle.cmd_push_constants(self.command, layout, vk::ShaderStageFlags::COMPUTE, offset, data);
+        } self
+    }
+
+    fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &Self {
+        unsafe {
+            self.device.handle.cmd_bind_pipeline(self.command, ICompute::BIND_POINT, pipeline);
+        } self
+    }
+
+    fn bind_descriptor_sets(&self, layout: vk::PipelineLayout, first_set: vkuint, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[vkuint]) -> &Self {
+        unsafe {
+            self.device.handle.cmd_bind_descriptor_sets(self.command, ICompute::BIND_POINT, layout, first_set, descriptor_sets, dynamic_offsets);
+        } self
+    }
+
+    fn dispatch(&self, group_count_x: vkuint, group_count_y: vkuint, group_count_z: vkuint) -> &Self {
+        unsafe {
+            self.device.handle.cmd_dispatch(self.command, group_count_x, group_count_y, group_count_z);
+        } self
+    }
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &Self {
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, src_stage, dst_stage, dependencies, &[], &[], image_barriers);
+        } self
+    }
 }
 
 pub trait CmdComputeApi {
-    // Not implement yet...
+
+    fn push_constants(&self, layout: vk::PipelineLayout, offset: vkuint, data: &[u8]) -> &Self;
+
+    fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &Self;
+
+    fn bind_descriptor_sets(&self, layout: vk::PipelineLayout, first_set: vkuint, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[vkuint]) -> &Self;
+
+    fn dispatch(&self, group_count_x: vkuint, group_count_y: vkuint, group_count_z: vkuint) -> &Self;
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &Self;
 }
@@ -132,6 +132,20 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
         } self
     }
 
+    /// Draw with the `vk::DrawIndirectCommand`s in buffer. `draw_count` greater than 1 requires `multi_draw_indirect` feature.
+    fn draw_indirect(&self, buffer: vk::Buffer, offset: vkbytes, draw_count: vkuint, stride: vkuint) -> &VkCmdRecorder<'a, IGraphics> {
+        unsafe {
+            self.device.handle.cmd_draw_indirect(self.command, buffer, offset, draw_count, stride);
+        } self
+    }
+
+    /// Draw with the `vk::DrawIndexedIndirectCommand`s in buffer. `draw_count` greater than 1 requires `multi_draw_indirect` feature.
+    fn draw_indexed_indirect(&self, buffer: vk::Buffer, offset: vkbytes, draw_count: vkuint, stride: vkuint) -> &VkCmdRecorder<'a, IGraphics> {
+        unsafe {
+            self.device.handle.cmd_draw_indexed_indirect(self.command, buffer, offset, draw_count, stride);
+        } self
+    }
+
     fn end_render_pass(&self) -> &VkCmdRecorder<'a, IGraphics> {
         // Ending the render pass will add an implicit barrier transitioning the frame buffer color attachment vk::IMAGE_LAYOUT_PRESENT_SRC_KHR for presenting it to the windowing system.
         unsafe {
@@ -176,5 +190,9 @@ pub trait CmdGraphicsApi {
 
     fn draw_indexed(&self, index_count: vkuint, instance_count: vkuint, first_index: vkuint, vertex_offset: vksint, first_instance: vkuint) -> &Self;
 
+    fn draw_indirect(&self, buffer: vk::Buffer, offset: vkbytes, draw_count: vkuint, stride: vkuint) -> &Self;
+
+    fn draw_indexed_indirect(&self, buffer: vk::Buffer, offset: vkbytes, draw_count: vkuint, stride: vkuint) -> &Self;
+
     fn end_render_pass(&self) -> &Self;
 }
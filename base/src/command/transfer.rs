@@ -72,6 +72,13 @@ impl<'a> CmdTransferApi for VkCmdRecorder<'a, ITransfer> {
         } self
     }
 
+    fn buffer_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, buffer_barriers: &[vk::BufferMemoryBarrier]) -> &Self {
+
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, src_stage, dst_stage, dependencies, &[], buffer_barriers, &[]);
+        } self
+    }
+
     fn blit_image(&self, src_handle: vk::Image, src_layout: vk::ImageLayout, dst_handle: vk::Image, dst_layout: vk::ImageLayout, regions: &[vk::ImageBlit], filter: vk::Filter) -> &Self {
         unsafe {
             self.device.handle.cmd_blit_image(self.command, src_handle, src_layout, dst_handle, dst_layout, regions, filter);
@@ -91,5 +98,7 @@ pub trait CmdTransferApi {
 
     fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &Self;
 
+    fn buffer_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, buffer_barriers: &[vk::BufferMemoryBarrier]) -> &Self;
+
     fn blit_image(&self, src_handle: vk::Image, src_layout: vk::ImageLayout, dst_handle: vk::Image, dst_layout: vk::ImageLayout, regions: &[vk::ImageBlit], filter: vk::Filter) -> &Self;
 }
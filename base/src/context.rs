@@ -8,6 +8,8 @@ pub use self::swapchain::{VkSwapchain, SwapchainSyncError};
 pub use self::instance::InstanceConfig;
 pub use self::debug::ValidationConfig;
 pub use self::device::{LogicDevConfig, PhysicalDevConfig};
+pub use self::device::{UploadTicket, ImageUploadInfo, BufferUploadInfo};
+pub use self::device::{MipmapMethod, MipmapGenerateInfo, mip_level_count};
 pub use self::swapchain::SwapchainConfig;
 
 mod instance;
@@ -68,16 +70,18 @@ impl VulkanContext {
         Ok(())
     }
 
-    pub(super) fn discard(self) {
+    pub(super) fn discard(self) -> VkResult<()> {
 
         self.swapchain.discard(&self.device);
         drop(self.swapchain);
 
-        self.device.drop_self();
+        let device_result = self.device.drop_self();
 
         drop(self.surface);
         drop(self.debugger);
         drop(self.instance);
+
+        device_result
     }
 }
 
@@ -116,9 +120,10 @@ impl<'a> VulkanContextBuilder<'a> {
         let surface = surface::VkSurface::new(&instance, &self.window.handle)?;
 
         let phy_device = device::VkPhysicalDevice::new(&instance, self.config.dev_phy)?;
+        let staging_ring_size = self.config.dev_logic.staging_ring_size;
         let logic_device = device::VkLogicalDevice::new(&instance, &phy_device, self.config.dev_logic)?;
         let vma = VulkanContextBuilder::build_vma(&instance, &phy_device, &logic_device)?;
-        let device = device::VkDevice::new(logic_device, phy_device, vma)?;
+        let device = device::VkDevice::new(logic_device, phy_device, vma, staging_ring_size)?;
 
         let dimension = self.window.dimension()?;
         let swapchain = swapchain::VkSwapchain::new(&instance, &device, &surface, self.config.swapchain, dimension)?;
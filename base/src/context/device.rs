@@ -2,9 +2,16 @@
 mod physical;
 mod logical;
 mod queue;
+mod upload;
+mod mipmap;
 
 pub use self::logical::{VkLogicalDevice, VkQueue, LogicDevConfig};
 pub use self::physical::{VkPhysicalDevice, PhysicalDevConfig};
+pub use self::upload::{UploadTicket, ImageUploadInfo, BufferUploadInfo};
+pub use self::mipmap::{MipmapMethod, MipmapGenerateInfo, mip_level_count};
+
+use self::upload::UploadManager;
+use self::mipmap::MipmapGenerator;
 
 use ash::vk;
 use ash::version::DeviceV1_0;
@@ -29,14 +36,24 @@ pub struct VkDevice {
     /// An internal command pool that used to allocate command buffers for data transfer operations.
     transfer_cmd_pool: vk::CommandPool,
     transfer_command : vk::CommandBuffer,
+
+    /// Batches the data uploads through a staging ring buffer.
+    upload: Option<UploadManager>,
+    /// Compute pipelines used to generate mipmaps for the formats that can not be blitted.
+    mipmap: Option<MipmapGenerator>,
 }
 
 impl VkDevice {
 
-    pub(super) fn new(logic: VkLogicalDevice, phy: VkPhysicalDevice, vma: vma::Allocator) -> VkResult<VkDevice> {
+    pub(super) fn new(logic: VkLogicalDevice, phy: VkPhysicalDevice, mut vma: vma::Allocator, staging_ring_size: vkbytes) -> VkResult<VkDevice> {
+
+        let copy_alignment = phy.limits.optimal_buffer_copy_offset_alignment;
+        let upload = UploadManager::new(&logic, &mut vma, staging_ring_size, copy_alignment)?;
 
         let mut device = VkDevice {
             logic, phy, vma,
+            upload: Some(upload),
+            mipmap: Some(MipmapGenerator::new()),
             pipeline_cache   : vk::PipelineCache::null(),
             transfer_cmd_pool: vk::CommandPool::null(),
             transfer_command : vk::CommandBuffer::null(),
@@ -75,12 +92,101 @@ impl VkDevice {
         }
     }
 
-    pub(super) fn drop_self(self) {
+    /// Write `data` to staging memory and record its copy to a range of device local buffer.
+    ///
+    /// The copy is not executed until `submit_uploads` is called.
+    pub fn upload_buffer(&mut self, data: &[u8], info: BufferUploadInfo) -> VkResult<()> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.upload_buffer(&self.logic, &mut self.vma, data, info)
+    }
+
+    /// Write `data` to staging memory and record its copy to a device local image.
+    ///
+    /// The copy is not executed until `submit_uploads` is called.
+    pub fn upload_image(&mut self, data: &[u8], info: ImageUploadInfo) -> VkResult<()> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.upload_image(&self.logic, &mut self.vma, data, info)
+    }
+
+    /// Submit all the recorded uploads to transfer queue without waiting for their completion.
+    ///
+    /// `signal` will be signaled once the uploaded resources are ready to be used by graphics queue.
+    ///
+    /// Return None if there is no upload recorded since last submission.
+    pub fn submit_uploads(&mut self, signal: Option<vk::Semaphore>) -> VkResult<Option<UploadTicket>> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.submit(&self.logic, signal)
+    }
+
+    /// Check if the uploads of `ticket` have finished without blocking.
+    pub fn is_upload_complete(&mut self, ticket: UploadTicket) -> VkResult<bool> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.is_complete(&self.logic, &mut self.vma, ticket)
+    }
+
+    /// Block until the uploads of `ticket` have finished.
+    pub fn wait_upload(&mut self, ticket: UploadTicket) -> VkResult<()> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.wait(&self.logic, &mut self.vma, ticket)
+    }
+
+    /// Submit all the recorded uploads and block until every upload has finished.
+    pub fn wait_uploads(&mut self) -> VkResult<()> {
+        let upload = self.upload.as_mut().unwrap();
+        upload.wait_all(&self.logic, &mut self.vma)
+    }
+
+    /// Return the method to generate mipmaps for `format`, or None if mipmaps can not be generated on device for it.
+    pub fn mipmap_method(&self, format: vk::Format) -> Option<MipmapMethod> {
+        mipmap::query_mipmap_method(&self.phy, format)
+    }
+
+    /// Record the generation of the mip chain of an image, after the upload of its base level.
+    ///
+    /// The generation is executed on graphics queue on the next `submit_uploads`.
+    pub fn generate_mipmaps(&mut self, info: MipmapGenerateInfo) -> VkResult<()> {
+
+        if info.mip_levels <= 1 {
+            return Ok(())
+        }
+
+        let command = self.upload.as_mut().unwrap().graphics_command(&self.logic)?;
+
+        match info.method {
+            | MipmapMethod::Blit => {
+                mipmap::record_blit(&self.logic, command, &info);
+            },
+            | MipmapMethod::Compute => {
+                let mut generator = self.mipmap.take().unwrap();
+                let transient = generator.record_compute(self, command, &info);
+                self.mipmap = Some(generator);
+
+                let transient = transient?;
+                self.upload.as_mut().unwrap().retain_transient(transient.views, transient.descriptor_pool);
+            },
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn drop_self(mut self) -> VkResult<()> {
+
+        // release the other resources even if the pending uploads fail, and report the failure afterwards.
+        let upload_result = match self.upload.take() {
+            | Some(upload) => upload.discard(&self.logic, &mut self.vma),
+            | None => Ok(()),
+        };
+
+        if let Some(generator) = self.mipmap.take() {
+            generator.discard(&self);
+        }
 
         self.discard(self.transfer_cmd_pool);
         self.discard(self.pipeline_cache);
         // destroy vma manually, so that vma will be destroyed before logic device.
         drop(self.vma);
+
+        upload_result
     }
 }
 
@@ -5,8 +5,9 @@ use ash::version::{DeviceV1_0, InstanceV1_0};
 use crate::context::instance::VkInstance;
 use crate::context::device::physical::VkPhysicalDevice;
 use crate::context::device::queue::{QueueRequester, QueueRequestStrategy};
+use crate::context::device::upload::DEFAULT_STAGING_RING_SIZE;
 use crate::error::{VkResult, VkError};
-use crate::vkuint;
+use crate::{vkuint, vkbytes};
 
 use std::ptr;
 
@@ -15,6 +16,8 @@ use std::ptr;
 pub struct LogicDevConfig {
 
     pub request_queues: vk::QueueFlags,
+    /// the size of the staging ring buffer used by asynchronous uploads.
+    pub staging_ring_size: vkbytes,
 }
 
 impl Default for LogicDevConfig {
@@ -23,6 +26,7 @@ impl Default for LogicDevConfig {
 
         LogicDevConfig {
             request_queues: vk::QueueFlags::GRAPHICS | vk::QueueFlags::TRANSFER,
+            staging_ring_size: DEFAULT_STAGING_RING_SIZE,
         }
     }
 }
@@ -0,0 +1,30 @@
+#version 450
+
+// `IMAGE_FORMAT` is replaced with the GLSL format qualifier of the image before compiling.
+
+layout (local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout (set = 0, binding = 0, IMAGE_FORMAT) uniform readonly  image2DArray src_level;
+layout (set = 0, binding = 1, IMAGE_FORMAT) uniform writeonly image2DArray dst_level;
+
+void main() {
+
+    ivec3 dst_coord = ivec3(gl_GlobalInvocationID);
+    ivec2 dst_size  = imageSize(dst_level).xy;
+
+    if (dst_coord.x >= dst_size.x || dst_coord.y >= dst_size.y) {
+        return;
+    }
+
+    // average the 2x2 texels of the previous level, clamping at the border for odd dimensions.
+    ivec2 src_max  = imageSize(src_level).xy - ivec2(1);
+    ivec2 src_base = dst_coord.xy * 2;
+    int layer = dst_coord.z;
+
+    vec4 color = imageLoad(src_level, ivec3(min(src_base + ivec2(0, 0), src_max), layer))
+               + imageLoad(src_level, ivec3(min(src_base + ivec2(1, 0), src_max), layer))
+               + imageLoad(src_level, ivec3(min(src_base + ivec2(0, 1), src_max), layer))
+               + imageLoad(src_level, ivec3(min(src_base + ivec2(1, 1), src_max), layer));
+
+    imageStore(dst_level, dst_coord, color * 0.25);
+}
@@ -0,0 +1,377 @@
+
+use ash::vk;
+
+use crate::ci::image::{ImageBarrierCI, ImageViewCI};
+use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+use crate::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+use crate::ci::pipeline::{PipelineLayoutCI, ComputePipelineCI};
+use crate::ci::shader::{ShaderModuleCI, ShaderStageCI};
+use crate::ci::VkObjectBuildableCI;
+
+use crate::context::{VkDevice, VkLogicalDevice, VkPhysicalDevice};
+use crate::command::{VkCmdRecorder, ITransfer, ICompute, CmdTransferApi, CmdComputeApi};
+use crate::{VkResult, VkError};
+use crate::{vkuint, vksint};
+
+/// the local workgroup size of mipmap compute shader in x and y dimension.
+const MIPMAP_GROUP_SIZE: vkuint = 8;
+
+// ----------------------------------------------------------------------------------------------
+/// The way to generate the mip chain of an image.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MipmapMethod {
+    /// downsample each level with `vkCmdBlitImage` and linear filter.
+    Blit,
+    /// downsample each level with a box filter in compute shader, for the formats that do not support linear blit.
+    Compute,
+}
+
+impl MipmapMethod {
+
+    /// The image usages required by this method, besides the usages of the image itself.
+    pub fn image_usage(&self) -> vk::ImageUsageFlags {
+        match self {
+            | MipmapMethod::Blit    => vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST,
+            | MipmapMethod::Compute => vk::ImageUsageFlags::STORAGE,
+        }
+    }
+
+    /// The layout and access type of the base level that this method expects after the base level has been uploaded.
+    pub fn base_level_state(&self) -> (vk::ImageLayout, vk::AccessFlags) {
+        match self {
+            | MipmapMethod::Blit    => (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::AccessFlags::TRANSFER_READ),
+            | MipmapMethod::Compute => (vk::ImageLayout::GENERAL, vk::AccessFlags::SHADER_READ),
+        }
+    }
+}
+
+/// The parameters to generate the mip chain of an image from its base level.
+#[derive(Debug, Clone)]
+pub struct MipmapGenerateInfo {
+    /// the image whose base level has been uploaded and is in the layout of `MipmapMethod::base_level_state`.
+    pub image: vk::Image,
+    pub format: vk::Format,
+    /// the dimension of the base level.
+    pub extent: vk::Extent2D,
+    /// the total count of mip levels, including the base level.
+    pub mip_levels: vkuint,
+    pub layer_count: vkuint,
+    pub method: MipmapMethod,
+    /// the layout of all levels after the mip chain has been generated.
+    pub final_layout: vk::ImageLayout,
+    /// the access type of the first usage of image after the generation.
+    pub dst_access: vk::AccessFlags,
+}
+
+/// Return the count of levels in the full mip chain of an image with `extent`.
+pub fn mip_level_count(extent: vk::Extent2D) -> vkuint {
+    let max_dimension = extent.width.max(extent.height).max(1);
+    32 - max_dimension.leading_zeros()
+}
+
+/// Find the method to generate mipmaps for `format`, or return None if the format supports neither of them.
+pub(crate) fn query_mipmap_method(phy: &VkPhysicalDevice, format: vk::Format) -> Option<MipmapMethod> {
+
+    let features = phy.format_properties(format).optimal_tiling_features;
+
+    let blit_features = vk::FormatFeatureFlags::BLIT_SRC | vk::FormatFeatureFlags::BLIT_DST | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+
+    if features.contains(blit_features) {
+        Some(MipmapMethod::Blit)
+    } else if features.contains(vk::FormatFeatureFlags::STORAGE_IMAGE) && storage_format_qualifier(format).is_some() {
+        Some(MipmapMethod::Compute)
+    } else {
+        None
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Record the commands that generate mipmaps by blitting each level from its previous level.
+pub(crate) fn record_blit(logic: &VkLogicalDevice, command: vk::CommandBuffer, info: &MipmapGenerateInfo) {
+
+    let recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(logic, command);
+
+    let level_range = |level: vkuint| vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: level,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: info.layer_count,
+    };
+
+    for level in 1..info.mip_levels {
+
+        let blit = vk::ImageBlit {
+            src_subresource: level_layers(level - 1, info.layer_count),
+            src_offsets: [vk::Offset3D { x: 0, y: 0, z: 0 }, level_offset(info.extent, level - 1)],
+            dst_subresource: level_layers(level, info.layer_count),
+            dst_offsets: [vk::Offset3D { x: 0, y: 0, z: 0 }, level_offset(info.extent, level)],
+        };
+
+        // the current level will be used as the destination of blit.
+        let dst_barrier = ImageBarrierCI::new(info.image, level_range(level))
+            .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
+            .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        // and then as the source of the next level.
+        let src_barrier = ImageBarrierCI::new(info.image, level_range(level))
+            .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::TRANSFER_READ)
+            .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+
+        recorder
+            .image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[dst_barrier.into()])
+            .blit_image(info.image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, info.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[blit], vk::Filter::LINEAR)
+            .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[src_barrier.into()]);
+    }
+
+    // all levels are in transfer source layout now.
+    let final_barrier = ImageBarrierCI::new(info.image, chain_range(info))
+        .access_mask(vk::AccessFlags::TRANSFER_READ, info.dst_access)
+        .layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL, info.final_layout);
+    recorder.image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[final_barrier.into()]);
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// The compute pipelines to generate mipmaps for the formats that can not be blitted.
+///
+/// Pipelines are created lazily for each format on its first usage.
+pub(crate) struct MipmapGenerator {
+
+    set_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    pipelines: Vec<(vk::Format, vk::Pipeline)>,
+}
+
+/// The resources used by the recorded compute commands, which must be kept until the commands have completed.
+pub(crate) struct MipmapTransient {
+    pub views: Vec<vk::ImageView>,
+    pub descriptor_pool: vk::DescriptorPool,
+}
+
+impl MipmapGenerator {
+
+    pub fn new() -> MipmapGenerator {
+
+        MipmapGenerator {
+            set_layout: vk::DescriptorSetLayout::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            pipelines: Vec::new(),
+        }
+    }
+
+    /// Record the commands that generate mipmaps by downsampling each level in compute shader.
+    pub fn record_compute(&mut self, device: &VkDevice, command: vk::CommandBuffer, info: &MipmapGenerateInfo) -> VkResult<MipmapTransient> {
+
+        let pipeline = self.pipeline(device, info.format)?;
+        let generation_count = info.mip_levels - 1;
+
+        // create an image view for each mip level.
+        let mut views = Vec::with_capacity(info.mip_levels as usize);
+        for level in 0..info.mip_levels {
+            let view = ImageViewCI::new(info.image, vk::ImageViewType::TYPE_2D_ARRAY, info.format)
+                .sub_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: level,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: info.layer_count,
+                })
+                .build(device)?;
+            views.push(view);
+        }
+
+        // each generated level uses a descriptor set to bind its previous level and itself.
+        let descriptor_pool = DescriptorPoolCI::new(generation_count)
+            .add_descriptor(vk::DescriptorType::STORAGE_IMAGE, generation_count * 2)
+            .build(device)?;
+
+        let mut set_ai = DescriptorSetAI::new(descriptor_pool);
+        for _ in 0..generation_count {
+            set_ai = set_ai.add_set_layout(self.set_layout);
+        }
+        let descriptor_sets = set_ai.build(device)?;
+
+        for (i, &set) in descriptor_sets.iter().enumerate() {
+
+            let src_write = DescriptorImageSetWI::new(set, 0, vk::DescriptorType::STORAGE_IMAGE)
+                .add_image(vk::DescriptorImageInfo {
+                    sampler: vk::Sampler::null(),
+                    image_view: views[i],
+                    image_layout: vk::ImageLayout::GENERAL,
+                });
+            let dst_write = DescriptorImageSetWI::new(set, 1, vk::DescriptorType::STORAGE_IMAGE)
+                .add_image(vk::DescriptorImageInfo {
+                    sampler: vk::Sampler::null(),
+                    image_view: views[i + 1],
+                    image_layout: vk::ImageLayout::GENERAL,
+                });
+
+            DescriptorSetsUpdateCI::new()
+                .add_write(&src_write)
+                .add_write(&dst_write)
+                .update(device);
+        }
+
+        let recorder: VkCmdRecorder<ICompute> = VkCmdRecorder::new(&device.logic, command);
+
+        // transition all generated levels to general layout.
+        let generated_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 1,
+            level_count: generation_count,
+            base_array_layer: 0,
+            layer_count: info.layer_count,
+        };
+        let general_barrier = ImageBarrierCI::new(info.image, generated_range)
+            .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::SHADER_WRITE)
+            .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL);
+
+        recorder
+            .image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::COMPUTE_SHADER, vk::DependencyFlags::empty(), &[general_barrier.into()])
+            .bind_pipeline(pipeline);
+
+        for level in 1..info.mip_levels {
+
+            let level_extent = level_offset(info.extent, level);
+            let group_x = (level_extent.x as vkuint + MIPMAP_GROUP_SIZE - 1) / MIPMAP_GROUP_SIZE;
+            let group_y = (level_extent.y as vkuint + MIPMAP_GROUP_SIZE - 1) / MIPMAP_GROUP_SIZE;
+
+            // make the current level visible to the generation of next level.
+            let level_barrier = ImageBarrierCI::new(info.image, vk::ImageSubresourceRange { base_mip_level: level, level_count: 1, ..generated_range })
+                .access_mask(vk::AccessFlags::SHADER_WRITE, vk::AccessFlags::SHADER_READ)
+                .layout(vk::ImageLayout::GENERAL, vk::ImageLayout::GENERAL);
+
+            recorder
+                .bind_descriptor_sets(self.pipeline_layout, 0, &[descriptor_sets[(level - 1) as usize]], &[])
+                .dispatch(group_x, group_y, info.layer_count)
+                .image_pipeline_barrier(vk::PipelineStageFlags::COMPUTE_SHADER, vk::PipelineStageFlags::COMPUTE_SHADER, vk::DependencyFlags::empty(), &[level_barrier.into()]);
+        }
+
+        let final_barrier = ImageBarrierCI::new(info.image, chain_range(info))
+            .access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE, info.dst_access)
+            .layout(vk::ImageLayout::GENERAL, info.final_layout);
+        recorder.image_pipeline_barrier(vk::PipelineStageFlags::COMPUTE_SHADER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[final_barrier.into()]);
+
+        let transient = MipmapTransient { views, descriptor_pool };
+        Ok(transient)
+    }
+
+    fn pipeline(&mut self, device: &VkDevice, format: vk::Format) -> VkResult<vk::Pipeline> {
+
+        if let Some(&(_, pipeline)) = self.pipelines.iter().find(|(f, _)| *f == format) {
+            return Ok(pipeline)
+        }
+
+        if self.pipeline_layout == vk::PipelineLayout::null() {
+
+            let storage_binding = |binding: vkuint| vk::DescriptorSetLayoutBinding {
+                binding,
+                descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::COMPUTE,
+                p_immutable_samplers: ::std::ptr::null(),
+            };
+
+            self.set_layout = DescriptorSetLayoutCI::new()
+                .add_binding(storage_binding(0))
+                .add_binding(storage_binding(1))
+                .build(device)?;
+            self.pipeline_layout = PipelineLayoutCI::new()
+                .add_set_layout(self.set_layout)
+                .build(device)?;
+        }
+
+        let format_qualifier = storage_format_qualifier(format)
+            .ok_or(VkError::unsupported("Mipmap generation in compute shader for this format"))?;
+        let source_text = include_str!("mipmap.comp.glsl")
+            .replace("IMAGE_FORMAT", format_qualifier);
+
+        let mut shader_compiler = crate::utils::shaderc::VkShaderCompiler::new()?;
+        let comp_codes = shader_compiler.compile_from_str(
+            &source_text,
+            shaderc::ShaderKind::Compute,
+            "[Mipmap Compute Shader]",
+            "main")?;
+
+        let comp_module = ShaderModuleCI::new(comp_codes).build(device)?;
+        let shader = ShaderStageCI::new(vk::ShaderStageFlags::COMPUTE, comp_module);
+
+        let pipeline = ComputePipelineCI::new(self.pipeline_layout, &shader).build(device)?;
+        device.discard(comp_module);
+
+        self.pipelines.push((format, pipeline));
+        Ok(pipeline)
+    }
+
+    pub fn discard(&self, device: &VkDevice) {
+
+        for &(_, pipeline) in self.pipelines.iter() {
+            device.discard(pipeline);
+        }
+
+        if self.pipeline_layout != vk::PipelineLayout::null() {
+            device.discard(self.pipeline_layout);
+            device.discard(self.set_layout);
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+/// The GLSL image format qualifier of `format`, for the formats that can be loaded and stored as float vectors.
+fn storage_format_qualifier(format: vk::Format) -> Option<&'static str> {
+
+    let qualifier = match format {
+        | vk::Format::R8_UNORM                 => "r8",
+        | vk::Format::R8G8_UNORM               => "rg8",
+        | vk::Format::R8G8B8A8_UNORM           => "rgba8",
+        | vk::Format::R8_SNORM                 => "r8_snorm",
+        | vk::Format::R8G8_SNORM               => "rg8_snorm",
+        | vk::Format::R8G8B8A8_SNORM           => "rgba8_snorm",
+        | vk::Format::R16_UNORM                => "r16",
+        | vk::Format::R16G16_UNORM             => "rg16",
+        | vk::Format::R16G16B16A16_UNORM       => "rgba16",
+        | vk::Format::R16_SFLOAT               => "r16f",
+        | vk::Format::R16G16_SFLOAT            => "rg16f",
+        | vk::Format::R16G16B16A16_SFLOAT      => "rgba16f",
+        | vk::Format::R32_SFLOAT               => "r32f",
+        | vk::Format::R32G32_SFLOAT            => "rg32f",
+        | vk::Format::R32G32B32A32_SFLOAT      => "rgba32f",
+        | vk::Format::A2B10G10R10_UNORM_PACK32 => "rgb10_a2",
+        | vk::Format::B10G11R11_UFLOAT_PACK32  => "r11f_g11f_b10f",
+        | _ => return None,
+    };
+
+    Some(qualifier)
+}
+
+fn level_layers(level: vkuint, layer_count: vkuint) -> vk::ImageSubresourceLayers {
+
+    vk::ImageSubresourceLayers {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        mip_level: level,
+        base_array_layer: 0,
+        layer_count,
+    }
+}
+
+/// Return the dimension of mip `level` as the far corner of a blit region.
+fn level_offset(extent: vk::Extent2D, level: vkuint) -> vk::Offset3D {
+
+    vk::Offset3D {
+        x: (extent.width  >> level).max(1) as vksint,
+        y: (extent.height >> level).max(1) as vksint,
+        z: 1,
+    }
+}
+
+fn chain_range(info: &MipmapGenerateInfo) -> vk::ImageSubresourceRange {
+
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: info.mip_levels,
+        base_array_layer: 0,
+        layer_count: info.layer_count,
+    }
+}
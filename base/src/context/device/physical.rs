@@ -52,6 +52,9 @@ pub struct VkPhysicalDevice {
 
     features_enable: vk::PhysicalDeviceFeatures,
     config: PhysicalDevConfig,
+
+    /// a copy of the instance function table, used to query the properties of this device.
+    instance: ash::Instance,
 }
 
 impl VkPhysicalDevice {
@@ -91,6 +94,7 @@ impl VkPhysicalDevice {
                 handle: phy_device.handle,
                 limits: phy_device.property.limits,
                 features_enable: enable_feature_if_support(&phy_device, &config),
+                instance: instance.handle.clone(),
                 config, memories, depth_format,
             };
 
@@ -137,6 +141,14 @@ impl VkPhysicalDevice {
     pub fn enable_extensions(&self) -> &Vec<CString> {
         &self.config.request_extensions
     }
+
+    /// Query the features supported by `format` on this device.
+    #[inline]
+    pub fn format_properties(&self, format: vk::Format) -> vk::FormatProperties {
+        unsafe {
+            self.instance.get_physical_device_format_properties(self.handle, format)
+        }
+    }
 }
 
 struct PhyDeviceTmp {
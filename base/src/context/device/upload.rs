@@ -0,0 +1,749 @@
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use crate::ci::buffer::{BufferCI, BufferBarrierCI};
+use crate::ci::image::ImageBarrierCI;
+use crate::ci::command::{CommandPoolCI, CommandBufferAI};
+use crate::ci::sync::{FenceCI, SemaphoreCI};
+use crate::ci::device::SubmitCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+
+use crate::context::VkLogicalDevice;
+use crate::command::{VkCmdRecorder, ITransfer, CmdTransferApi};
+use crate::utils::memory::IntegerAlignable;
+use crate::utils::time::VkTimeDuration;
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::{vkbytes, vkuint};
+
+use std::collections::VecDeque;
+use std::ptr;
+
+/// The default size of the persistently mapped staging ring.
+pub const DEFAULT_STAGING_RING_SIZE: vkbytes = 64 * 1024 * 1024;
+/// The minimum alignment of every staging allocation(must be a multiple of 4 and of any texel block size).
+const STAGING_MIN_ALIGNMENT: vkbytes = 16;
+
+// ----------------------------------------------------------------------------------------------
+/// An identifier of a submitted upload batch.
+///
+/// Tickets are increasing, so a ticket is complete once any later ticket is complete.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct UploadTicket(u64);
+
+/// The parameters to upload data into a device local image.
+#[derive(Debug, Clone)]
+pub struct ImageUploadInfo {
+    /// the destination image.
+    pub image: vk::Image,
+    /// the layout of `image` before the upload, where `vk::ImageLayout::UNDEFINED` discards its previous content.
+    ///
+    /// An image with content to keep is expected to be owned by graphics queue family, and it is updated on graphics queue,
+    /// so that the update is ordered after the submitted commands reading it.
+    pub initial_layout: vk::ImageLayout,
+    /// the copy regions, with `buffer_offset` relative to the beginning of the uploaded data.
+    pub regions: Vec<vk::BufferImageCopy>,
+    /// the levels and layers of `image` that are written by this upload.
+    pub sub_range: vk::ImageSubresourceRange,
+    /// the layout of `image` after the upload has finished.
+    pub final_layout: vk::ImageLayout,
+    /// the access type of the first usage of `image` after the upload.
+    pub dst_access: vk::AccessFlags,
+}
+
+/// The parameters to upload data into a device local buffer.
+#[derive(Debug, Clone)]
+pub struct BufferUploadInfo {
+    /// the destination buffer.
+    pub buffer: vk::Buffer,
+    /// the starting offset in `buffer` to write.
+    pub offset: vkbytes,
+    /// the access type of the first usage of `buffer` after the upload.
+    pub dst_access: vk::AccessFlags,
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// A persistently mapped host visible buffer which is sub-allocated in a circular way.
+struct StagingRing {
+
+    buffer: VmaBuffer,
+    data_ptr: *mut u8,
+
+    capacity: vkbytes,
+    /// the starting offset of the next allocation.
+    head: vkbytes,
+    /// the starting offset of the oldest allocation still in use.
+    tail: vkbytes,
+    /// the number of bytes currently reserved(including the padding and the wasted space at the end of ring).
+    reserved: vkbytes,
+}
+
+impl StagingRing {
+
+    fn new(vma: &mut vma::Allocator, capacity: vkbytes) -> VkResult<StagingRing> {
+
+        let ring_ci = BufferCI::new(capacity)
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let ring_allocation = vma.create_buffer(ring_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        let buffer = VmaBuffer::from(ring_allocation);
+        // keep the memory mapping during the whole lifetime of the ring.
+        let data_ptr = buffer.info.get_mapped_data();
+        debug_assert_ne!(data_ptr, ptr::null_mut());
+
+        let ring = StagingRing { buffer, data_ptr, capacity, head: 0, tail: 0, reserved: 0 };
+        Ok(ring)
+    }
+
+    /// Reserve `size` bytes from the ring, and return the offset of the reserved range and the number of bytes it costs.
+    fn allocate(&mut self, size: vkbytes, alignment: vkbytes) -> Option<(vkbytes, vkbytes)> {
+
+        if self.reserved == 0 {
+            // restart from the beginning while the ring is empty to reduce fragmentation.
+            self.head = 0;
+            self.tail = 0;
+        }
+
+        let start = self.head.align_to(alignment);
+        let is_wrapped = self.head < self.tail || (self.head == self.tail && self.reserved > 0);
+
+        let (offset, cost) = if is_wrapped {
+            // the free space is [head, tail).
+            if start + size <= self.tail {
+                (start, start + size - self.head)
+            } else {
+                return None
+            }
+        } else if start + size <= self.capacity {
+            // the free space is [head, capacity) and [0, tail).
+            (start, start + size - self.head)
+        } else if size <= self.tail {
+            // skip the remaining space at the end of the ring and start from the beginning.
+            (0, self.capacity - self.head + size)
+        } else {
+            return None
+        };
+
+        self.head = offset + size;
+        self.reserved += cost;
+
+        Some((offset, cost))
+    }
+
+    /// Free the oldest allocations, whose ranges end at `end` and cost `cost` bytes in total.
+    fn release(&mut self, end: vkbytes, cost: vkbytes) {
+
+        debug_assert!(self.reserved >= cost);
+
+        // batches without ring allocation(e.g. only dedicated staging buffers) must not move the tail.
+        if cost > 0 {
+            self.tail = end;
+            self.reserved -= cost;
+        }
+    }
+
+    fn write(&self, offset: vkbytes, data: &[u8]) {
+
+        debug_assert!(offset + (data.len() as vkbytes) <= self.capacity);
+
+        unsafe {
+            self.data_ptr.offset(offset as isize)
+                .copy_from_nonoverlapping(data.as_ptr(), data.len());
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// The command buffers and sync objects of a group of copy operations that are submitted together.
+struct UploadBatch {
+
+    /// the command buffer submitted to transfer queue.
+    transfer_command: vk::CommandBuffer,
+    /// the command buffer submitted to graphics queue, used to acquire the ownership of uploaded resources and to update the images in use.
+    graphics_command: vk::CommandBuffer,
+    /// signaled after all the commands of this batch have finished.
+    fence: vk::Fence,
+    /// signaled by transfer queue and waited by graphics queue.
+    semaphore: vk::Semaphore,
+
+    is_graphics_recording: bool,
+    /// the end of the ring range used by this batch.
+    ring_end: vkbytes,
+    /// the number of bytes of the ring reserved by this batch.
+    ring_cost: vkbytes,
+    /// the staging buffers allocated for the data that is too large to fit in the ring.
+    dedicated: Vec<VmaBuffer>,
+    /// the image views and descriptor pools used by the commands of this batch(e.g. mipmap generation).
+    transient_views: Vec<vk::ImageView>,
+    transient_pools: Vec<vk::DescriptorPool>,
+
+    ticket: u64,
+}
+
+/// The queue families involved in a queue family ownership transfer.
+#[derive(Debug, Clone, Copy)]
+struct OwnershipTransfer {
+    transfer_family: vkuint,
+    graphics_family: vkuint,
+}
+
+/// `UploadManager` batches the data uploads to device local resources.
+///
+/// The data is written into a persistently mapped staging ring, and many copies are recorded in the same command buffer,
+/// which is submitted to transfer queue without waiting for the completion.
+///
+/// The uploaded resources are not available until the ticket returned by `VkDevice::submit_uploads` is complete,
+/// or until the semaphore passed to it is signaled.
+pub struct UploadManager {
+
+    ring: StagingRing,
+    alignment: vkbytes,
+
+    transfer_pool: vk::CommandPool,
+    graphics_pool: vk::CommandPool,
+    ownership: Option<OwnershipTransfer>,
+    /// whether graphics queue is not the transfer queue, even if they are in the same queue family.
+    is_graphics_separate: bool,
+
+    recording: Option<UploadBatch>,
+    in_flight: VecDeque<UploadBatch>,
+    idle: Vec<UploadBatch>,
+
+    next_ticket: u64,
+    completed_ticket: u64,
+}
+
+impl UploadManager {
+
+    pub(crate) fn new(logic: &VkLogicalDevice, vma: &mut vma::Allocator, ring_size: vkbytes, copy_alignment: vkbytes) -> VkResult<UploadManager> {
+
+        let ring = StagingRing::new(vma, ring_size)?;
+
+        let transfer_family = logic.queues.transfer.family_index;
+        let graphics_family = logic.queues.graphics.family_index;
+
+        // the ownership of exclusive resources must be transferred if they are written and read by different queue families.
+        let ownership = if logic.queues.graphics.handle != vk::Queue::null() && transfer_family != graphics_family {
+            Some(OwnershipTransfer { transfer_family, graphics_family })
+        } else {
+            None
+        };
+
+        let is_graphics_separate = logic.queues.graphics.handle != vk::Queue::null() && logic.queues.graphics.handle != logic.queues.transfer.handle;
+
+        let pool_flags = vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER | vk::CommandPoolCreateFlags::TRANSIENT;
+        let transfer_pool = unsafe {
+            logic.handle.create_command_pool(CommandPoolCI::new(transfer_family).flags(pool_flags).as_ref(), None)
+                .map_err(|_| VkError::create("Command Pool"))?
+        };
+        let graphics_pool = if is_graphics_separate {
+            unsafe {
+                logic.handle.create_command_pool(CommandPoolCI::new(graphics_family).flags(pool_flags).as_ref(), None)
+                    .map_err(|_| VkError::create("Command Pool"))?
+            }
+        } else {
+            vk::CommandPool::null()
+        };
+
+        let manager = UploadManager {
+            ring, transfer_pool, graphics_pool, ownership, is_graphics_separate,
+            alignment: copy_alignment.max(STAGING_MIN_ALIGNMENT),
+            recording: None,
+            in_flight: VecDeque::new(),
+            idle: Vec::new(),
+            next_ticket: 1,
+            completed_ticket: 0,
+        };
+        Ok(manager)
+    }
+
+    /// Record the copy from `data` to a range of buffer.
+    pub(crate) fn upload_buffer(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, data: &[u8], info: BufferUploadInfo) -> VkResult<()> {
+
+        let (staging_buffer, staging_offset) = self.stage(logic, vma, data)?;
+        let ownership = self.ownership;
+        let batch = self.recording.as_mut().unwrap();
+
+        let copy_region = vk::BufferCopy {
+            src_offset: staging_offset,
+            dst_offset: info.offset,
+            size      : data.len() as vkbytes,
+        };
+
+        let transfer_recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(logic, batch.transfer_command);
+        transfer_recorder.copy_buf2buf(staging_buffer, info.buffer, &[copy_region]);
+
+        let barrier = BufferBarrierCI::new(info.buffer, info.offset, copy_region.size);
+
+        if let Some(ownership) = ownership {
+
+            // release the ownership from transfer queue family.
+            let release_barrier = barrier.clone()
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::empty())
+                .queue_family_index(ownership.transfer_family, ownership.graphics_family);
+            transfer_recorder.buffer_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[release_barrier.into()]);
+
+            // and acquire it in graphics queue family.
+            let acquire_barrier = barrier
+                .access_mask(vk::AccessFlags::empty(), info.dst_access)
+                .queue_family_index(ownership.transfer_family, ownership.graphics_family);
+            let graphics_recorder = batch.graphics_recorder(logic)?;
+            graphics_recorder.buffer_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[acquire_barrier.into()]);
+        } else {
+
+            let visible_barrier = barrier
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, info.dst_access);
+            transfer_recorder.buffer_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[visible_barrier.into()]);
+        }
+
+        Ok(())
+    }
+
+    /// Record the copy from `data` to an image, and the layout transitions of the image.
+    pub(crate) fn upload_image(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, data: &[u8], info: ImageUploadInfo) -> VkResult<()> {
+
+        let (staging_buffer, staging_offset) = self.stage(logic, vma, data)?;
+        let ownership = self.ownership;
+        let is_graphics_separate = self.is_graphics_separate;
+        let batch = self.recording.as_mut().unwrap();
+
+        let copy_regions: Vec<vk::BufferImageCopy> = info.regions.iter().map(|region| {
+            vk::BufferImageCopy {
+                buffer_offset: region.buffer_offset + staging_offset,
+                ..*region
+            }
+        }).collect();
+
+        let is_content_kept = info.initial_layout != vk::ImageLayout::UNDEFINED;
+        // the previous usages of image must finish before it is overwritten, unless its content is discarded.
+        let wait_stage = if is_content_kept { vk::PipelineStageFlags::ALL_COMMANDS } else { vk::PipelineStageFlags::TOP_OF_PIPE };
+
+        // the image will be used as the destination of copy.
+        let copy_dst_barrier = ImageBarrierCI::new(info.image, info.sub_range)
+            .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
+            .layout(info.initial_layout, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+
+        // the layout transition must be the same in both release and acquire barriers.
+        let final_barrier = ImageBarrierCI::new(info.image, info.sub_range)
+            .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, info.final_layout);
+
+        if is_content_kept && is_graphics_separate {
+
+            // the image may still be read by the commands submitted to graphics queue, which the barriers on another queue do not wait for.
+            // so it is copied on graphics queue, which also keeps its content without ownership transfer.
+            let visible_barrier = final_barrier
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, info.dst_access);
+            let graphics_recorder = batch.graphics_recorder(logic)?;
+            graphics_recorder
+                .image_pipeline_barrier(wait_stage, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[copy_dst_barrier.into()])
+                .copy_buf2img(staging_buffer, info.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &copy_regions)
+                .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[visible_barrier.into()]);
+
+            return Ok(())
+        }
+
+        let transfer_recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(logic, batch.transfer_command);
+        transfer_recorder
+            .image_pipeline_barrier(wait_stage, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[copy_dst_barrier.into()])
+            .copy_buf2img(staging_buffer, info.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &copy_regions);
+
+        if let Some(ownership) = ownership {
+
+            let release_barrier = final_barrier.clone()
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::empty())
+                .queue_family_index(ownership.transfer_family, ownership.graphics_family);
+            transfer_recorder.image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[release_barrier.into()]);
+
+            let acquire_barrier = final_barrier
+                .access_mask(vk::AccessFlags::empty(), info.dst_access)
+                .queue_family_index(ownership.transfer_family, ownership.graphics_family);
+            let graphics_recorder = batch.graphics_recorder(logic)?;
+            graphics_recorder.image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[acquire_barrier.into()]);
+        } else {
+
+            let visible_barrier = final_barrier
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, info.dst_access);
+            transfer_recorder.image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[visible_barrier.into()]);
+        }
+
+        Ok(())
+    }
+
+    /// Return the command buffer of recording batch that is executed on graphics queue after all the recorded copies,
+    /// which is used to record the commands that require graphics capability(e.g. image blit).
+    pub(crate) fn graphics_command(&mut self, logic: &VkLogicalDevice) -> VkResult<vk::CommandBuffer> {
+
+        if logic.queues.graphics.handle == vk::Queue::null() {
+            return Err(VkError::unsupported("Recording graphics commands without graphics queue"))
+        }
+
+        let is_ownership_transfer = self.ownership.is_some();
+        let batch = self.recording.as_mut()
+            .ok_or(VkError::custom("There is no upload recording to append graphics commands to."))?;
+
+        if is_ownership_transfer {
+            batch.graphics_recorder(logic)?;
+            Ok(batch.graphics_command)
+        } else {
+            // transfer queue and graphics queue are in the same queue family.
+            Ok(batch.transfer_command)
+        }
+    }
+
+    /// Keep the image views and descriptor pool alive until the recording batch has completed, and then destroy them.
+    pub(crate) fn retain_transient(&mut self, views: Vec<vk::ImageView>, descriptor_pool: vk::DescriptorPool) {
+
+        if let Some(ref mut batch) = self.recording {
+            batch.transient_views.extend(views);
+            batch.transient_pools.push(descriptor_pool);
+        }
+    }
+
+    /// Submit all the recorded copies without waiting.
+    ///
+    /// `signal` is an optional semaphore to be signaled after the uploaded resources are available to graphics queue.
+    ///
+    /// Return None if there is nothing to submit.
+    pub(crate) fn submit(&mut self, logic: &VkLogicalDevice, signal: Option<vk::Semaphore>) -> VkResult<Option<UploadTicket>> {
+
+        let batch = match self.recording.take() {
+            | Some(batch) => batch,
+            | None => return Ok(None),
+        };
+
+        unsafe {
+            logic.handle.end_command_buffer(batch.transfer_command)
+                .map_err(|_| VkError::device("End Command Buffer."))?;
+        }
+
+        if batch.is_graphics_recording {
+
+            unsafe {
+                logic.handle.end_command_buffer(batch.graphics_command)
+                    .map_err(|_| VkError::device("End Command Buffer."))?;
+            }
+
+            let transfer_ci = SubmitCI::new()
+                .add_command(batch.transfer_command)
+                .add_signal(batch.semaphore);
+            let mut graphics_ci = SubmitCI::new()
+                .add_wait(vk::PipelineStageFlags::ALL_COMMANDS, batch.semaphore)
+                .add_command(batch.graphics_command);
+            if let Some(signal) = signal {
+                graphics_ci = graphics_ci.add_signal(signal);
+            }
+
+            unsafe {
+                logic.handle.queue_submit(logic.queues.transfer.handle, &[*transfer_ci.as_ref()], vk::Fence::null())
+                    .map_err(|_| VkError::device("Queue Submit"))?;
+                logic.handle.queue_submit(logic.queues.graphics.handle, &[*graphics_ci.as_ref()], batch.fence)
+                    .map_err(|_| VkError::device("Queue Submit"))?;
+            }
+        } else {
+
+            let mut transfer_ci = SubmitCI::new()
+                .add_command(batch.transfer_command);
+            if let Some(signal) = signal {
+                transfer_ci = transfer_ci.add_signal(signal);
+            }
+
+            unsafe {
+                logic.handle.queue_submit(logic.queues.transfer.handle, &[*transfer_ci.as_ref()], batch.fence)
+                    .map_err(|_| VkError::device("Queue Submit"))?;
+            }
+        }
+
+        let ticket = UploadTicket(batch.ticket);
+        self.in_flight.push_back(batch);
+
+        Ok(Some(ticket))
+    }
+
+    /// Check if the uploads of `ticket` have finished, without blocking.
+    pub(crate) fn is_complete(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, ticket: UploadTicket) -> VkResult<bool> {
+
+        self.poll(logic, vma)?;
+        Ok(ticket.0 <= self.completed_ticket)
+    }
+
+    /// Block until the uploads of `ticket` have finished.
+    pub(crate) fn wait(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, ticket: UploadTicket) -> VkResult<()> {
+
+        while self.completed_ticket < ticket.0 {
+
+            if self.in_flight.is_empty() {
+                // the ticket has not been submitted yet.
+                return Err(VkError::custom("Waiting for an upload ticket that has not been submitted."))
+            }
+
+            self.wait_oldest(logic, vma)?;
+        }
+
+        Ok(())
+    }
+
+    /// Submit all the recorded copies, and block until all the uploads have finished.
+    pub(crate) fn wait_all(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator) -> VkResult<()> {
+
+        self.submit(logic, None)?;
+
+        while !self.in_flight.is_empty() {
+            self.wait_oldest(logic, vma)?;
+        }
+
+        Ok(())
+    }
+
+    /// Recycle the batches that have finished.
+    fn poll(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator) -> VkResult<()> {
+
+        while let Some(fence) = self.in_flight.front().map(|batch| batch.fence) {
+
+            match unsafe { logic.handle.get_fence_status(fence) } {
+                | Ok(_) => {
+                    let batch = self.in_flight.pop_front().unwrap();
+                    self.retire(logic, vma, batch)?;
+                },
+                | Err(vk::Result::NOT_READY) => break,
+                | Err(_) => return Err(VkError::device("Get Fence Status")),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn wait_oldest(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator) -> VkResult<()> {
+
+        if let Some(batch) = self.in_flight.pop_front() {
+
+            unsafe {
+                logic.handle.wait_for_fences(&[batch.fence], true, VkTimeDuration::Infinite.into())
+                    .map_err(|_| VkError::device("Wait for fences"))?;
+            }
+
+            self.retire(logic, vma, batch)?;
+        }
+
+        Ok(())
+    }
+
+    fn retire(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, mut batch: UploadBatch) -> VkResult<()> {
+
+        self.ring.release(batch.ring_end, batch.ring_cost);
+        self.completed_ticket = batch.ticket;
+
+        for dedicated in batch.dedicated.drain(..) {
+            vma.destroy_buffer(dedicated.handle, &dedicated.allocation)
+                .map_err(VkErrorKind::Vma)?;
+        }
+
+        unsafe {
+            for view in batch.transient_views.drain(..) {
+                logic.handle.destroy_image_view(view, None);
+            }
+            for pool in batch.transient_pools.drain(..) {
+                logic.handle.destroy_descriptor_pool(pool, None);
+            }
+
+            logic.handle.reset_fences(&[batch.fence])
+                .map_err(|_| VkError::device("Fence Resetting"))?;
+            logic.handle.reset_command_buffer(batch.transfer_command, vk::CommandBufferResetFlags::empty())
+                .map_err(|_| VkError::device("Reset Command Buffer"))?;
+            if batch.graphics_command != vk::CommandBuffer::null() {
+                logic.handle.reset_command_buffer(batch.graphics_command, vk::CommandBufferResetFlags::empty())
+                    .map_err(|_| VkError::device("Reset Command Buffer"))?;
+            }
+        }
+
+        batch.is_graphics_recording = false;
+        batch.ring_cost = 0;
+        self.idle.push(batch);
+
+        Ok(())
+    }
+
+    /// Copy `data` to staging memory, and return the staging buffer and the offset of data in it.
+    ///
+    /// A batch is guaranteed to be recording after this call.
+    fn stage(&mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator, data: &[u8]) -> VkResult<(vk::Buffer, vkbytes)> {
+
+        let size = data.len() as vkbytes;
+        debug_assert!(size > 0, "The size of uploaded data must be greater than 0!");
+
+        if size > self.ring.capacity {
+            // the data can never fit in the ring, so use a dedicated staging buffer for it.
+            let dedicated = create_dedicated_staging(vma, data)?;
+            let handle = dedicated.handle;
+
+            self.begin_batch(logic)?;
+            self.recording.as_mut().unwrap().dedicated.push(dedicated);
+
+            return Ok((handle, 0))
+        }
+
+        loop {
+
+            if let Some((offset, cost)) = self.ring.allocate(size, self.alignment) {
+
+                self.ring.write(offset, data);
+                self.begin_batch(logic)?;
+
+                let batch = self.recording.as_mut().unwrap();
+                batch.ring_end = self.ring.head;
+                batch.ring_cost += cost;
+
+                return Ok((self.ring.buffer.handle, offset))
+            }
+
+            // the ring is full, try to free the space used by the batches that have finished.
+            let last_completed = self.completed_ticket;
+            self.poll(logic, vma)?;
+
+            if self.completed_ticket != last_completed {
+                continue
+            }
+
+            if self.in_flight.is_empty() {
+                // all the space is used by the recording batch, so submit it first.
+                self.submit(logic, None)?;
+            }
+
+            self.wait_oldest(logic, vma)?;
+        }
+    }
+
+    /// Make sure there is a batch in recording state.
+    fn begin_batch(&mut self, logic: &VkLogicalDevice) -> VkResult<()> {
+
+        if self.recording.is_some() {
+            return Ok(())
+        }
+
+        let mut batch = match self.idle.pop() {
+            | Some(batch) => batch,
+            | None => self.create_batch(logic)?,
+        };
+
+        batch.ticket = self.next_ticket;
+        batch.ring_end = self.ring.head;
+        self.next_ticket += 1;
+
+        let recorder: VkCmdRecorder<ITransfer> = upload_recorder(logic, batch.transfer_command);
+        recorder.begin_record()?;
+
+        self.recording = Some(batch);
+        Ok(())
+    }
+
+    fn create_batch(&self, logic: &VkLogicalDevice) -> VkResult<UploadBatch> {
+
+        let transfer_command = unsafe {
+            logic.handle.allocate_command_buffers(CommandBufferAI::new(self.transfer_pool, 1).as_ref())
+                .map_err(|_| VkError::create("Command Buffers"))?.remove(0)
+        };
+
+        let graphics_command = if self.graphics_pool != vk::CommandPool::null() {
+            unsafe {
+                logic.handle.allocate_command_buffers(CommandBufferAI::new(self.graphics_pool, 1).as_ref())
+                    .map_err(|_| VkError::create("Command Buffers"))?.remove(0)
+            }
+        } else {
+            vk::CommandBuffer::null()
+        };
+
+        let (fence, semaphore) = unsafe {
+            let fence = logic.handle.create_fence(FenceCI::new(false).as_ref(), None)
+                .map_err(|_| VkError::create("Fence"))?;
+            let semaphore = logic.handle.create_semaphore(SemaphoreCI::new().as_ref(), None)
+                .map_err(|_| VkError::create("Semaphore"))?;
+            (fence, semaphore)
+        };
+
+        let batch = UploadBatch {
+            transfer_command, graphics_command, fence, semaphore,
+            is_graphics_recording: false,
+            ring_end: 0,
+            ring_cost: 0,
+            dedicated: Vec::new(),
+            transient_views: Vec::new(),
+            transient_pools: Vec::new(),
+            ticket: 0,
+        };
+        Ok(batch)
+    }
+
+    pub(crate) fn discard(mut self, logic: &VkLogicalDevice, vma: &mut vma::Allocator) -> VkResult<()> {
+
+        self.wait_all(logic, vma)?;
+
+        for batch in self.idle.drain(..) {
+            unsafe {
+                logic.handle.destroy_fence(batch.fence, None);
+                logic.handle.destroy_semaphore(batch.semaphore, None);
+            }
+        }
+
+        unsafe {
+            // command buffers are freed together with their pools.
+            logic.handle.destroy_command_pool(self.transfer_pool, None);
+            if self.graphics_pool != vk::CommandPool::null() {
+                logic.handle.destroy_command_pool(self.graphics_pool, None);
+            }
+        }
+
+        vma.destroy_buffer(self.ring.buffer.handle, &self.ring.buffer.allocation)
+            .map_err(VkErrorKind::Vma)?;
+
+        Ok(())
+    }
+}
+
+impl UploadBatch {
+
+    /// Return the recorder of the command buffer submitted to graphics queue, and begin the recording if need.
+    fn graphics_recorder<'a>(&mut self, logic: &'a VkLogicalDevice) -> VkResult<VkCmdRecorder<'a, ITransfer>> {
+
+        let recorder = upload_recorder(logic, self.graphics_command);
+
+        if !self.is_graphics_recording {
+            recorder.begin_record()?;
+            self.is_graphics_recording = true;
+        }
+
+        Ok(recorder)
+    }
+}
+
+fn upload_recorder(logic: &VkLogicalDevice, command: vk::CommandBuffer) -> VkCmdRecorder<ITransfer> {
+
+    let mut recorder = VkCmdRecorder::new(logic, command);
+    recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    recorder
+}
+
+fn create_dedicated_staging(vma: &mut vma::Allocator, data: &[u8]) -> VkResult<VmaBuffer> {
+
+    let staging_ci = BufferCI::new(data.len() as vkbytes)
+        .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+    let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+        .flags(vma::AllocationCreateFlags::MAPPED);
+    let staging_allocation = vma.create_buffer(staging_ci.as_ref(), allocation_ci.as_ref())
+        .map_err(VkErrorKind::Vma)?;
+
+    let staging_buffer = VmaBuffer::from(staging_allocation);
+
+    unsafe {
+        let data_ptr = staging_buffer.info.get_mapped_data();
+        debug_assert_ne!(data_ptr, ptr::null_mut());
+        data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
+    }
+
+    Ok(staging_buffer)
+}
+// ----------------------------------------------------------------------------------------------
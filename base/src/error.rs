@@ -63,6 +63,35 @@ impl VkError {
         VkError::from(VkErrorKind::Serialize(error))
     }
 
+    pub fn gltf_resource(uri: impl AsRef<str>, description: impl AsRef<str>) -> VkError {
+        VkError::from(VkErrorKind::GltfResource {
+            uri: uri.as_ref().to_string(),
+            description: description.as_ref().to_string(),
+        })
+    }
+
+    pub fn gltf_accessor(accessor: usize, description: impl AsRef<str>) -> VkError {
+        VkError::from(VkErrorKind::GltfAccessor {
+            accessor,
+            description: description.as_ref().to_string(),
+        })
+    }
+
+    pub fn parse_obj(file: impl AsRef<str>, line: usize, description: impl AsRef<str>) -> VkError {
+        VkError::from(VkErrorKind::ParseObj {
+            file: file.as_ref().to_string(),
+            line,
+            description: description.as_ref().to_string(),
+        })
+    }
+
+    pub fn model_cache(path: impl AsRef<Path>, description: impl AsRef<str>) -> VkError {
+        VkError::from(VkErrorKind::ModelCache {
+            path: path.as_ref().to_path_buf(),
+            description: description.as_ref().to_string(),
+        })
+    }
+
     pub fn custom(description: impl AsRef<str>) -> VkError {
         VkError::from(VkErrorKind::Custom {
             description: description.as_ref().to_string()
@@ -114,6 +143,18 @@ pub enum VkErrorKind {
     Shaderc { compile_message: String },
     #[fail(display = "glTF parse error: {}", _0)]
     ParseGltf(#[cause] gltf::Error),
+    /// An error occurred while loading the buffer or image referenced by glTF file.
+    #[fail(display = "Failed to load glTF resource '{}': {}", uri, description)]
+    GltfResource { uri: String, description: String },
+    /// An error caused by a glTF accessor that reads out of its buffer.
+    #[fail(display = "Invalid glTF accessor {}: {}", accessor, description)]
+    GltfAccessor { accessor: usize, description: String },
+    /// An error occurred while parsing Wavefront OBJ or MTL file.
+    #[fail(display = "Failed to parse '{}' at line {}: {}", file, line, description)]
+    ParseObj { file: String, line: usize, description: String },
+    /// An error occurred while writing the cooked glTF model.
+    #[fail(display = "Failed to write glTF model cache at {:?}: {}", path, description)]
+    ModelCache { path: PathBuf, description: String },
     /// An error occurred while communicate with Window.
     #[fail(display = "Failed to interact with Window: {}.", description)]
     Window { description: String },
@@ -123,6 +164,12 @@ pub enum VkErrorKind {
     /// An error occurred while loading texture by gli crate.
     #[fail(display = "gli error: {}.", _0)]
     Gli(#[cause] gli::Error),
+    /// An error occurred while decoding image file(PNG, JPEG, TGA, HDR...) by image crate.
+    #[fail(display = "Image decode error: {}.", _0)]
+    Image(#[cause] image::ImageError),
+    /// An error occurred while parsing DDS file.
+    #[fail(display = "DDS error: {}.", _0)]
+    Dds(#[cause] ddsfile::Error),
     /// An error that occurred while working with a file path.
     #[fail(display = "Failed to locate file at: {:?}", path)]
     Path { path: PathBuf },
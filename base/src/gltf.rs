@@ -1,16 +1,38 @@
 
-pub use self::loader::GltfModelInfo;
+pub use self::loader::{GltfModelInfo, SceneSelection};
 pub use self::loader::load_gltf;
-pub use self::asset::{VkglTFModel, ModelRenderParams};
+pub use self::obj::{ObjModelInfo, load_obj};
+pub use self::source::{GltfSource, UriResolver, FileResolver, EmbeddedResolver};
+pub use self::asset::{VkglTFModel, ModelRenderParams, MaterialPipelines};
+pub use self::material::{AlphaMode, MaterialMode};
+pub use self::material::{PBR_VERTEX_SHADER_SOURCE, PBR_FRAGMENT_SHADER_SOURCE, PBR_SKINNED_VERTEX_SHADER_SOURCE, PBR_MORPHED_VERTEX_SHADER_SOURCE, PBR_INSTANCED_VERTEX_SHADER_SOURCE};
+pub use self::material::pbr_shader_source;
+pub use self::animations::AnimationPlayer;
+pub use self::nodes::{SceneGraph, NodePose};
+pub use self::cameras::{GltfCamera, CameraProjection};
+pub use self::lights::{GltfLight, LightKind, SceneLight};
+pub use self::bounds::{BoundingBox, Frustum, CullingStats};
+pub use self::instancing::InstanceResource;
 
-pub use self::meshes::AttributeFlags;
+pub use self::meshes::{AttributeFlags, AttributeFormat, VertexLayout};
+pub use self::meshes::{TopologyGroup, PrimitiveRange, RenderParams};
+pub use self::meshes::{MeshResource, MeshOptimizeFlags, OptimizationReport};
 pub use self::nodes::NodeAttachmentFlags;
 
 mod loader;
+mod source;
+mod cache;
+mod obj;
 
 mod scene;
+mod cameras;
+mod lights;
+mod bounds;
 mod material;
+mod textures;
 
 mod asset;
-mod meshes;
+mod instancing;
+pub(crate) mod meshes;
 mod nodes;
+mod animations;
@@ -0,0 +1,239 @@
+
+use ash::vk;
+
+use crate::gltf::asset::{GltfDocument, AssetAbstract, ReferenceIndex};
+use crate::gltf::animations::skin::Skin;
+use crate::gltf::animations::channel::Animation;
+use crate::gltf::nodes::SceneGraph;
+use crate::gltf::scene::Scene;
+
+use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+use crate::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+use crate::ci::vma::VmaBuffer;
+use crate::ci::VkObjectBuildableCI;
+use crate::context::VkDevice;
+use crate::error::{VkResult, VkErrorKind};
+use crate::utils::memory::IntegerAlignable;
+use crate::{vkuint, vkbytes, vkptr, Mat4F};
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::ptr;
+
+const JOINT_MATRIX_SIZE: vkbytes = ::std::mem::size_of::<Mat4F>() as vkbytes;
+
+// --------------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize)]
+pub struct AnimationAsset {
+
+    skins: Vec<Skin>,
+    animations: Vec<Animation>,
+
+    transform: Option<Mat4F>,
+}
+
+impl AnimationAsset {
+
+    pub fn new() -> AnimationAsset {
+
+        AnimationAsset {
+            skins: Vec::new(),
+            animations: Vec::new(),
+            transform: None,
+        }
+    }
+
+    /// Allocate a host visible storage buffer for the joint matrices of each skin, which contains a copy of joint matrices for each of `frame_in_flight` frames.
+    ///
+    /// Each copy is written to its own descriptor set, so that the joint matrices read by a frame in flight are never overwritten.
+    pub fn allocate(self, device: &mut VkDevice, graph: &SceneGraph, frame_in_flight: usize) -> VkResult<AnimationResource> {
+
+        use crate::ci::buffer::BufferCI;
+        use crate::ci::vma::VmaAllocationCI;
+
+        let frame_count = frame_in_flight.max(1);
+
+        let mut joint_buffers = Vec::with_capacity(self.skins.len());
+        let mut frame_sizes = Vec::with_capacity(self.skins.len());
+        for skin in self.skins.iter() {
+
+            // a skin without joints still requires a buffer with non-zero size.
+            let frame_size = (JOINT_MATRIX_SIZE * (skin.joint_count().max(1) as vkbytes))
+                .align_to(device.phy.limits.min_storage_buffer_offset_alignment);
+
+            let buffer_ci = BufferCI::new(frame_size * (frame_count as vkbytes))
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let buffer_allocation = device.vma.create_buffer(buffer_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            joint_buffers.push(VmaBuffer::from(buffer_allocation));
+            frame_sizes.push(frame_size);
+        }
+
+        // the descriptor pool requires at least one set.
+        let set_count = ((self.skins.len() * frame_count) as vkuint).max(1);
+        let descriptor_pool = DescriptorPoolCI::new(set_count)
+            .add_descriptor(vk::DescriptorType::STORAGE_BUFFER, set_count)
+            .build(device)?;
+
+        // Binding 0: the joint matrices of skin.
+        let set_layout = DescriptorSetLayoutCI::new()
+            .add_binding(vk::DescriptorSetLayoutBinding {
+                binding: 0,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                p_immutable_samplers: ptr::null(),
+            })
+            .build(device)?;
+
+        let mut descriptor_sets = Vec::with_capacity(joint_buffers.len());
+        for (joint_buffer, frame_size) in joint_buffers.iter().zip(frame_sizes.iter().cloned()) {
+
+            // each frame reads its own copy of joint matrices.
+            let skin_sets = (0..frame_count)
+                .fold(DescriptorSetAI::new(descriptor_pool), |set_ai, _| set_ai.add_set_layout(set_layout))
+                .build(device)?;
+
+            let joints_writes: Vec<DescriptorBufferSetWI> = skin_sets.iter().enumerate()
+                .map(|(frame_index, descriptor_set)| {
+                    DescriptorBufferSetWI::new(*descriptor_set, 0, vk::DescriptorType::STORAGE_BUFFER)
+                        .add_buffer(vk::DescriptorBufferInfo {
+                            buffer: joint_buffer.handle,
+                            offset: frame_size * (frame_index as vkbytes),
+                            range : frame_size,
+                        })
+                }).collect();
+
+            joints_writes.iter()
+                .fold(DescriptorSetsUpdateCI::new(), |update_ci, write| update_ci.add_write(write))
+                .update(device);
+
+            descriptor_sets.push(skin_sets);
+        }
+
+        let result = AnimationResource {
+            skins: self.skins,
+            animations: self.animations,
+            transform: self.transform,
+            joint_buffers, frame_count, frame_sizes, descriptor_pool, set_layout, descriptor_sets,
+        };
+
+        // write the joint matrices of rest pose to every frame, so that skinned meshes can be rendered without playing any animation.
+        for frame_index in 0..frame_count {
+            result.update_joints(graph, frame_index);
+        }
+
+        Ok(result)
+    }
+}
+
+impl AssetAbstract for AnimationAsset {
+    const ASSET_NAME: &'static str = "Animations";
+
+    fn read_doc(&mut self, source: &GltfDocument, _scene: &Scene) -> VkResult<()> {
+
+        self.skins = source.doc.skins()
+            .map(|doc_skin| Skin::from_doc(doc_skin, source))
+            .collect();
+
+        self.animations = source.doc.animations()
+            .map(|doc_animation| Animation::from_doc(doc_animation, source))
+            .collect();
+
+        self.transform = source.transform;
+
+        Ok(())
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+pub struct AnimationResource {
+
+    skins: Vec<Skin>,
+    animations: Vec<Animation>,
+
+    transform: Option<Mat4F>,
+
+    /// a persistently mapped storage buffer for each skin.
+    joint_buffers: Vec<VmaBuffer>,
+    /// the count of joint matrices copies in each buffer, one for each frame in flight.
+    frame_count: usize,
+    /// the byte size of joint matrices of each skin in each frame(including the padding for alignment).
+    frame_sizes: Vec<vkbytes>,
+
+    descriptor_pool: vk::DescriptorPool,
+    set_layout: vk::DescriptorSetLayout,
+    /// the descriptor set of each frame for each skin.
+    descriptor_sets: Vec<Vec<vk::DescriptorSet>>,
+}
+
+impl AnimationResource {
+
+    pub fn animation_count(&self) -> usize {
+        self.animations.len()
+    }
+
+    pub fn animation_name(&self, animation_index: usize) -> Option<&str> {
+        self.animations.get(animation_index)
+            .and_then(|animation| animation.name.as_ref().map(String::as_str))
+    }
+
+    /// Return the duration of animation in seconds.
+    pub fn animation_duration(&self, animation_index: usize) -> Option<f32> {
+        self.animations.get(animation_index)
+            .map(|animation| animation.duration)
+    }
+
+    /// The layout of skin descriptor set, which should be included in the pipeline layout at the set index of `ModelRenderParams::skin_set`.
+    ///
+    /// The joint matrices are bound to binding 0 as a storage buffer of mat4 array.
+    pub fn set_layout(&self) -> vk::DescriptorSetLayout {
+        self.set_layout
+    }
+
+    /// Return the descriptor set containing the joint matrices of skin written for `frame_index`.
+    ///
+    /// `frame_index` wraps around the frame count specified in allocation.
+    pub fn get_descriptor_set(&self, skin_index: ReferenceIndex, frame_index: usize) -> vk::DescriptorSet {
+        self.descriptor_sets[skin_index][frame_index % self.frame_count]
+    }
+
+    pub(super) fn animation(&self, animation_index: usize) -> Option<&Animation> {
+        self.animations.get(animation_index)
+    }
+
+    /// Calculate the joint matrices of all skins from the world transforms of nodes, and write them to the joint buffers of `frame_index`.
+    ///
+    /// This method must be called when GPU is not reading the joint matrices of `frame_index`.
+    pub(crate) fn update_joints(&self, graph: &SceneGraph, frame_index: usize) {
+
+        let frame_slot = frame_index % self.frame_count;
+
+        for ((skin, joint_buffer), frame_size) in self.skins.iter().zip(self.joint_buffers.iter()).zip(self.frame_sizes.iter().cloned()) {
+
+            let joint_matrices = skin.joint_matrices(graph, &self.transform);
+
+            unsafe {
+                let data_ptr = (joint_buffer.info.get_mapped_data() as vkptr<u8>).offset((frame_size as isize) * (frame_slot as isize)) as vkptr<Mat4F>;
+                data_ptr.copy_from_nonoverlapping(joint_matrices.as_ptr(), joint_matrices.len());
+            }
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.set_layout);
+        // descriptor sets are freed with the pool.
+        device.discard(self.descriptor_pool);
+
+        for joint_buffer in self.joint_buffers {
+            device.vma_discard(joint_buffer)?;
+        }
+        Ok(())
+    }
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,246 @@
+
+use crate::gltf::asset::{GltfDocument, ReferenceIndex};
+use crate::gltf::nodes::SceneGraph;
+use crate::gltf::meshes::MorphResource;
+use crate::{Vec3F, Vec4F};
+
+use serde_derive::{Serialize, Deserialize};
+
+// --------------------------------------------------------------------------------------
+/// A wrapper class for animation level in glTF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Animation {
+
+    /// the name property of animation.
+    pub name: Option<String>,
+    /// the time of last keyframe in seconds.
+    pub duration: f32,
+
+    channels: Vec<Channel>,
+}
+
+impl Animation {
+
+    pub fn from_doc(doc_animation: gltf::Animation, source: &GltfDocument) -> Animation {
+
+        let name = doc_animation.name().map(String::from);
+        let channels: Vec<Channel> = doc_animation.channels()
+            .filter_map(|doc_channel| Channel::from_doc(doc_channel, source))
+            .collect();
+
+        let duration = channels.iter()
+            .filter_map(|channel| channel.inputs.last().cloned())
+            .fold(0.0, f32::max);
+
+        Animation { name, duration, channels }
+    }
+
+    /// Overwrite the local transforms of nodes and the morph weights of meshes targeted by the channels of this animation at `time`.
+    pub fn apply(&self, graph: &mut SceneGraph, morph: &mut MorphResource, time: f32) {
+
+        for channel in self.channels.iter() {
+
+            let value = channel.sample(time);
+            let node_index = channel.target_node;
+
+            match channel.path {
+                | ChannelPath::Translation => graph.set_translation(node_index, Vec3F::new(value[0], value[1], value[2])),
+                | ChannelPath::Rotation    => graph.set_rotation(node_index, to_quaternion(&value)),
+                | ChannelPath::Scale       => graph.set_scale(node_index, Vec3F::new(value[0], value[1], value[2])),
+                | ChannelPath::Weights { mesh } => morph.set_weights(mesh, &value),
+            }
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum ChannelPath {
+    Translation,
+    Rotation,
+    Scale,
+    /// the morph weights of the mesh instantiated by target node, where `mesh` is the json index of mesh.
+    Weights { mesh: ReferenceIndex },
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum Interpolation {
+    Linear,
+    Step,
+    CubicSpline,
+}
+
+/// An animation channel combined with its sampler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Channel {
+
+    /// the json index of node targeted by this channel.
+    target_node: ReferenceIndex,
+    path: ChannelPath,
+    interpolation: Interpolation,
+
+    /// the time of each keyframe in seconds.
+    inputs: Vec<f32>,
+    /// the output values flattened to scalars, where each value contains `components` scalars.
+    /// For cubic spline interpolation, each keyframe contains an in-tangent, a value and an out-tangent.
+    outputs: Vec<f32>,
+    /// the count of scalars in each value, which is 3 for translations and scales, 4 for rotations, and the target count for morph weights.
+    components: usize,
+}
+
+impl Channel {
+
+    /// Read the animation channel, or return None if the channel has no keyframes.
+    fn from_doc(doc_channel: gltf::animation::Channel, source: &GltfDocument) -> Option<Channel> {
+
+        use gltf::animation::util::ReadOutputs;
+
+        let interpolation = match doc_channel.sampler().interpolation() {
+            | gltf::animation::Interpolation::Step        => Interpolation::Step,
+            | gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+            // the interpolations not defined in glTF 2.0 specification fall back to linear.
+            | _ => Interpolation::Linear,
+        };
+
+        let reader = doc_channel.reader(|b| Some(&source.buffers[b.index()]));
+        let inputs: Vec<f32> = reader.read_inputs()?.collect();
+
+        let values_per_key = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        if inputs.is_empty() {
+            return None
+        }
+
+        let (path, outputs, components): (ChannelPath, Vec<f32>, usize) = match reader.read_outputs()? {
+            | ReadOutputs::Translations(translations) => {
+                (ChannelPath::Translation, translations.flat_map(|t| t.to_vec()).collect(), 3)
+            },
+            | ReadOutputs::Rotations(rotations) => {
+                (ChannelPath::Rotation, rotations.into_f32().flat_map(|r| r.to_vec()).collect(), 4)
+            },
+            | ReadOutputs::Scales(scales) => {
+                (ChannelPath::Scale, scales.flat_map(|s| s.to_vec()).collect(), 3)
+            },
+            | ReadOutputs::MorphTargetWeights(weights) => {
+                let mesh = doc_channel.target().node().mesh()?.index();
+                let outputs: Vec<f32> = weights.into_f32().collect();
+                // the weights of all morph targets are packed into the output of each keyframe.
+                let components = outputs.len() / (inputs.len() * values_per_key);
+                (ChannelPath::Weights { mesh }, outputs, components)
+            },
+        };
+
+        if components == 0 || outputs.len() < inputs.len() * values_per_key * components {
+            return None
+        }
+
+        let channel = Channel {
+            target_node: doc_channel.target().node().index(),
+            path, interpolation, inputs, outputs, components,
+        };
+        Some(channel)
+    }
+
+    fn sample(&self, time: f32) -> Vec<f32> {
+
+        let last = self.inputs.len() - 1;
+
+        // clamp the time to the range of keyframes.
+        if time <= self.inputs[0] {
+            return self.value_at(0).to_vec()
+        }
+        if time >= self.inputs[last] {
+            return self.value_at(last).to_vec()
+        }
+
+        // find the keyframe pair that contains `time`.
+        let next = self.inputs.iter().position(|input| *input > time).unwrap_or(last);
+        let prev = next - 1;
+
+        let delta = self.inputs[next] - self.inputs[prev];
+        let t = (time - self.inputs[prev]) / delta;
+
+        match self.interpolation {
+            | Interpolation::Step => self.value_at(prev).to_vec(),
+            | Interpolation::Linear => {
+                let (from, to) = (self.value_at(prev), self.value_at(next));
+                if self.path == ChannelPath::Rotation {
+                    let rotation = slerp(to_quaternion(from), to_quaternion(to), t);
+                    vec![rotation.x, rotation.y, rotation.z, rotation.w]
+                } else {
+                    from.iter().zip(to.iter())
+                        .map(|(from, to)| from + (to - from) * t)
+                        .collect()
+                }
+            },
+            | Interpolation::CubicSpline => {
+                let t2 = t * t;
+                let t3 = t2 * t;
+
+                let p0 = self.value_at(prev);
+                let m0 = self.key_slice(prev * 3 + 2);
+                let p1 = self.value_at(next);
+                let m1 = self.key_slice(next * 3);
+
+                let value: Vec<f32> = (0..self.components).map(|i| {
+                    p0[i] * (2.0 * t3 - 3.0 * t2 + 1.0)
+                        + m0[i] * delta * (t3 - 2.0 * t2 + t)
+                        + p1[i] * (-2.0 * t3 + 3.0 * t2)
+                        + m1[i] * delta * (t3 - t2)
+                }).collect();
+
+                if self.path == ChannelPath::Rotation {
+                    let rotation = to_quaternion(&value).normalized();
+                    vec![rotation.x, rotation.y, rotation.z, rotation.w]
+                } else {
+                    value
+                }
+            },
+        }
+    }
+
+    /// Return the value of keyframe at `key_index`, skipping the tangents of cubic spline.
+    fn value_at(&self, key_index: usize) -> &[f32] {
+
+        if self.interpolation == Interpolation::CubicSpline {
+            self.key_slice(key_index * 3 + 1)
+        } else {
+            self.key_slice(key_index)
+        }
+    }
+
+    /// Return the scalars of the `element_index`-th element in outputs.
+    fn key_slice(&self, element_index: usize) -> &[f32] {
+        let start = element_index * self.components;
+        &self.outputs[start..(start + self.components)]
+    }
+}
+// --------------------------------------------------------------------------------------
+/// Pack the scalars of a rotation value into a quaternion in (x, y, z, w) order.
+fn to_quaternion(value: &[f32]) -> Vec4F {
+    Vec4F::new(value[0], value[1], value[2], value[3])
+}
+
+/// Spherical linear interpolation between two unit quaternions.
+fn slerp(from: Vec4F, to: Vec4F, t: f32) -> Vec4F {
+
+    let mut cos_theta = from.dot(to);
+    // take the shortest path.
+    let to = if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        -to
+    } else {
+        to
+    };
+
+    if cos_theta > 0.9995 {
+        // the quaternions are too close, use normalized linear interpolation instead.
+        return (from + (to - from) * t).normalized()
+    }
+
+    let theta = cos_theta.acos();
+    let sin_theta = theta.sin();
+
+    (from * ((1.0 - t) * theta).sin() + to * (t * theta).sin()) / sin_theta
+}
+// --------------------------------------------------------------------------------------
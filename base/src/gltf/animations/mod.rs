@@ -0,0 +1,8 @@
+
+pub use self::asset::{AnimationAsset, AnimationResource};
+pub use self::player::AnimationPlayer;
+
+mod asset;
+mod channel;
+mod player;
+mod skin;
@@ -0,0 +1,74 @@
+
+use crate::gltf::asset::VkglTFModel;
+use crate::error::{VkResult, VkError};
+
+// --------------------------------------------------------------------------------------
+/// Play an animation of glTF model by driving the local transforms of its nodes and the morph weights of its meshes.
+///
+/// ```ignore
+/// let mut player = AnimationPlayer::new(&model, 0)?;
+///
+/// // in each frame, after the fence of this frame has been waited.
+/// player.update(&mut model, delta_time);
+/// model.update(frame_index);
+/// ```
+pub struct AnimationPlayer {
+
+    /// the index of animation in glTF file.
+    animation: usize,
+    /// the current time of animation in seconds.
+    time: f32,
+
+    /// the playback rate of animation. Default is 1.0.
+    pub speed: f32,
+    /// whether to restart the animation after it reaches the end. Default is true.
+    pub is_loop: bool,
+}
+
+impl AnimationPlayer {
+
+    pub fn new(model: &VkglTFModel, animation_index: usize) -> VkResult<AnimationPlayer> {
+
+        if model.animations.animation_count() <= animation_index {
+            return Err(VkError::custom(format!("The glTF model does not contain animation {}.", animation_index)))
+        }
+
+        let player = AnimationPlayer {
+            animation: animation_index,
+            time: 0.0,
+            speed: 1.0,
+            is_loop: true,
+        };
+        Ok(player)
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jump to `time` in seconds without updating the nodes.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.0);
+    }
+
+    /// Advance the animation by `delta_time` seconds, and set the local transforms of animated nodes in the scene graph of `model`.
+    ///
+    /// The changes are written to GPU by `VkglTFModel::update`.
+    pub fn update(&mut self, model: &mut VkglTFModel, delta_time: f32) {
+
+        // the player is always created with a valid animation index.
+        let animation = model.animations.animation(self.animation).unwrap();
+
+        self.time += delta_time * self.speed;
+        if animation.duration > 0.0 {
+            self.time = if self.is_loop {
+                self.time.rem_euclid(animation.duration)
+            } else {
+                self.time.min(animation.duration).max(0.0)
+            };
+        }
+
+        animation.apply(model.nodes.graph_mut(), &mut model.meshes.morph, self.time);
+    }
+}
+// --------------------------------------------------------------------------------------
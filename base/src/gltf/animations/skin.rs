@@ -0,0 +1,68 @@
+
+use crate::gltf::asset::{GltfDocument, ReferenceIndex};
+use crate::gltf::nodes::SceneGraph;
+use crate::Mat4F;
+
+use serde_derive::{Serialize, Deserialize};
+
+// --------------------------------------------------------------------------------------
+/// A wrapper class for skin level in glTF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skin {
+
+    /// the json index of nodes used as joints.
+    joints: Vec<ReferenceIndex>,
+    /// the inverse bind matrix of each joint.
+    inverse_bind_matrices: Vec<Mat4F>,
+    /// the json index of the node which references this skin, whose transform is cancelled from joint matrices.
+    skinned_node: Option<ReferenceIndex>,
+}
+
+impl Skin {
+
+    pub fn from_doc(doc_skin: gltf::Skin, source: &GltfDocument) -> Skin {
+
+        let joints: Vec<ReferenceIndex> = doc_skin.joints()
+            .map(|doc_joint| doc_joint.index())
+            .collect();
+
+        let reader = doc_skin.reader(|b| Some(&source.buffers[b.index()]));
+        let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+            | Some(matrices_iter) => matrices_iter.map(Mat4F::from_col_arrays).collect(),
+            // the inverse bind matrices are assumed to be identity matrices if they are missing.
+            | None => vec![Mat4F::identity(); joints.len()],
+        };
+
+        let skinned_node = source.doc.nodes()
+            .find(|doc_node| doc_node.skin().map(|s| s.index()) == Some(doc_skin.index()))
+            .map(|doc_node| doc_node.index());
+
+        Skin { joints, inverse_bind_matrices, skinned_node }
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// Calculate the joint matrices of this skin.
+    ///
+    /// `source_transform` is the transform applied to vertices when the model was loaded.
+    pub fn joint_matrices(&self, graph: &SceneGraph, source_transform: &Option<Mat4F>) -> Vec<Mat4F> {
+
+        let inverse_node_transform = self.skinned_node
+            .map(|node_index| graph.world_transform(node_index).inverted())
+            .unwrap_or(Mat4F::identity());
+
+        self.joints.iter().zip(self.inverse_bind_matrices.iter()).map(|(joint_index, inverse_bind_matrix)| {
+
+            let joint_matrix = inverse_node_transform * graph.world_transform(*joint_index) * (*inverse_bind_matrix);
+
+            // the positions have been transformed by `source_transform`, so the joint matrix must be applied in the same space.
+            match source_transform {
+                | Some(transform) => (*transform) * joint_matrix * transform.inverted(),
+                | None => joint_matrix,
+            }
+        }).collect()
+    }
+}
+// --------------------------------------------------------------------------------------
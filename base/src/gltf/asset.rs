@@ -1,15 +1,23 @@
 
 use ash::vk;
 
-use crate::gltf::meshes::{MeshAsset, MeshResource, AttributeFlags};
+use crate::gltf::meshes::{MeshAsset, MeshResource, VertexLayout, MeshOptimizeFlags, AttributeGeneration};
 use crate::gltf::nodes::{NodeAsset, NodeResource, NodeAttachmentFlags};
-use crate::gltf::material::{MaterialAsset, MaterialResource};
+use crate::gltf::material::{MaterialAsset, MaterialResource, MaterialMode, AlphaMode};
+use crate::gltf::animations::{AnimationAsset, AnimationResource};
 use crate::gltf::scene::Scene;
-
-use crate::command::{VkCmdRecorder, IGraphics};
-use crate::context::{VkDevice, VmaResourceDiscardable};
+use crate::gltf::cameras::GltfCamera;
+use crate::gltf::lights::SceneLight;
+use crate::gltf::bounds::{BoundingBox, Frustum, CullingStats};
+use crate::gltf::instancing::{InstanceAsset, InstanceResource};
+
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::context::VkDevice;
+use crate::camera::FlightCameraBuilder;
 use crate::error::VkResult;
-use crate::Mat4F;
+use crate::{vkuint, Mat4F, Vec3F};
+
+use serde_derive::{Serialize, Deserialize};
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -23,6 +31,8 @@ pub struct GltfDocument {
     pub doc: gltf::Document,
     pub buffers: Vec<gltf::buffer::Data>,
     pub images : Vec<gltf::image::Data>,
+    /// the raw json of glTF file, used to read the extensions unknown by glTF crate.
+    pub json: serde_json::Value,
 
     pub transform: Option<Mat4F>,
 }
@@ -35,6 +45,7 @@ pub trait AssetAbstract: Sized {
     fn read_doc(&mut self, source: &GltfDocument, scene: &Scene) -> VkResult<()>;
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct AssetElementList<T> {
 
     list: Vec<T>,
@@ -72,34 +83,47 @@ impl<T> AssetElementList<T> {
 // --------------------------------------------------------------------------------------
 
 // --------------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize)]
 pub struct AssetRepository {
     pub nodes : NodeAsset,
     pub meshes: MeshAsset,
     pub materials: MaterialAsset,
+    pub animations: AnimationAsset,
+    pub instances: InstanceAsset,
 }
 
 impl AssetRepository {
 
-    pub fn new(attr_flag: AttributeFlags, attachment_flag: NodeAttachmentFlags) -> VkResult<AssetRepository> {
+    pub fn new(vertex_layout: VertexLayout, is_unroll_strips: bool, optimization: MeshOptimizeFlags, generation: AttributeGeneration, attachment_flag: NodeAttachmentFlags) -> VkResult<AssetRepository> {
 
         let repository = AssetRepository {
             nodes : NodeAsset::try_from(attachment_flag)?,
-            meshes: MeshAsset::try_from(attr_flag)?,
+            meshes: MeshAsset::new(vertex_layout, is_unroll_strips, optimization, generation)?,
             materials: MaterialAsset::new()?,
+            animations: AnimationAsset::new(),
+            instances: InstanceAsset::new(),
         };
         Ok(repository)
     }
 
-    pub fn allocate(self, device: &mut VkDevice, scene: Scene) -> VkResult<VkglTFModel> {
+    pub fn allocate(self, device: &mut VkDevice, mut scene: Scene, frame_in_flight: usize) -> VkResult<VkglTFModel> {
+
+        let nodes_allocated  = self.nodes.allocate(device, &scene, device.phy.limits.min_uniform_buffer_offset_alignment, frame_in_flight)?;
+        let meshes_allocated = self.meshes.allocate(device, frame_in_flight)?;
+        let materials_allocated = self.materials.allocate(device)?;
+        let animations_allocated = self.animations.allocate(device, nodes_allocated.graph(), frame_in_flight)?;
 
-        let nodes_allocated  = self.nodes.allocate(device, device.phy.limits.min_uniform_buffer_offset_alignment)?;
-        let meshes_allocated = self.meshes.allocate(device)?;
+        // collect all primitives in scene to decide their draw order.
+        scene.read_draws(&nodes_allocated.list, &meshes_allocated.list, &materials_allocated);
+        let instances_allocated = self.instances.allocate(device, &scene, &nodes_allocated.list, &meshes_allocated.list, nodes_allocated.graph(), frame_in_flight)?;
 
         let result = VkglTFModel {
             scene,
             meshes: meshes_allocated,
             nodes : nodes_allocated,
-            materials: self.materials,
+            materials: materials_allocated,
+            animations: animations_allocated,
+            instances: instances_allocated,
         };
 
         Ok(result)
@@ -114,6 +138,8 @@ pub struct VkglTFModel {
     pub meshes: MeshResource,
     pub nodes : NodeResource,
     pub materials: MaterialResource,
+    pub animations: AnimationResource,
+    pub instances: InstanceResource,
 
     scene: Scene,
 }
@@ -123,23 +149,161 @@ pub struct ModelRenderParams {
     pub descriptor_set : vk::DescriptorSet,
     pub pipeline_layout: vk::PipelineLayout,
     pub material_stage : Option<vk::ShaderStageFlags>,
+    /// the set index to bind the material descriptor set of each primitive, or None to skip binding material textures.
+    pub material_set   : Option<vkuint>,
+    /// the pipelines to switch between according to the alpha mode and double-sided property of material, or None to use the pipeline bound by caller.
+    pub material_pipelines: Option<MaterialPipelines>,
+    /// the camera position used to sort blended primitives back-to-front, or None to draw them in scene order.
+    pub view_position: Option<Vec3F>,
+    /// the frame to read node attachments, joint matrices and morph weights from, which should match the `frame_index` passed to `VkglTFModel::update`.
+    pub frame_index: usize,
+    /// the set index to bind the joint matrices of skin for skinned nodes, or None to skip binding joint matrices.
+    pub skin_set: Option<vkuint>,
+    /// the set index to bind the morph targets and weights of meshes, or None to skip binding morph targets.
+    ///
+    /// The morph index of each primitive is also pushed, so the pipeline layout must include `MorphResource::push_constant_range`.
+    pub morph_set: Option<vkuint>,
+    /// the topology of primitives to draw, which should match the topology of bound pipelines, or None to draw all primitives.
+    pub topology: Option<vk::PrimitiveTopology>,
+    /// the set index to bind the instance data for `VkglTFModel::record_command_instanced`, or None to skip binding instance data.
+    pub instance_set: Option<vkuint>,
+}
+
+/// The pipelines for the combination of material properties.
+///
+/// Opaque and mask materials share the same pipelines, since alpha mask is applied by discarding fragments in shader.
+/// Blend pipelines are expected to enable color blending and disable depth writing.
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialPipelines {
+    /// the pipeline with back-face culling for opaque and mask materials.
+    pub opaque: vk::Pipeline,
+    /// the pipeline without culling for double-sided opaque and mask materials.
+    pub opaque_double_sided: vk::Pipeline,
+    /// the pipeline with back-face culling for blend materials.
+    pub blend: vk::Pipeline,
+    /// the pipeline without culling for double-sided blend materials.
+    pub blend_double_sided: vk::Pipeline,
+}
+
+impl MaterialPipelines {
+
+    pub fn select(&self, mode: MaterialMode) -> vk::Pipeline {
+        match (mode.alpha_mode, mode.double_sided) {
+            | (AlphaMode::Blend, false) => self.blend,
+            | (AlphaMode::Blend, true)  => self.blend_double_sided,
+            | (_, false) => self.opaque,
+            | (_, true)  => self.opaque_double_sided,
+        }
+    }
 }
 
 impl VkglTFModel {
 
+    /// Return the count of scenes loaded from glTF file.
+    pub fn scene_count(&self) -> usize {
+        self.scene.scene_count()
+    }
+
+    pub fn scene_name(&self, scene_index: usize) -> Option<&str> {
+        self.scene.scene_name(scene_index)
+    }
+
+    pub fn active_scene(&self) -> usize {
+        self.scene.active_scene()
+    }
+
+    /// Set the scene drawn by `record_command`. The command buffers recorded before must be recorded again.
+    pub fn set_active_scene(&mut self, scene_index: usize) -> VkResult<()> {
+        self.scene.set_active_scene(scene_index)
+    }
+
+    /// Return the cameras referenced by the nodes of loaded scenes.
+    pub fn cameras(&self) -> &[GltfCamera] {
+        self.scene.cameras()
+    }
+
+    /// Create a `FlightCamera` preset from the current transform of camera node, or None if the camera is orthographic.
+    pub fn camera_preset(&self, camera_index: usize) -> Option<FlightCameraBuilder> {
+        self.scene.cameras().get(camera_index)
+            .and_then(|camera| camera.flight_camera(self.nodes.graph(), self.scene.transform()))
+    }
+
+    /// Return the `KHR_lights_punctual` lights of loaded scenes, placed by the current transforms of their nodes.
+    pub fn lights(&self) -> Vec<SceneLight> {
+        self.scene.lights().iter()
+            .map(|light| light.place(self.nodes.graph(), self.scene.transform()))
+            .collect()
+    }
+
     pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams) {
+        self.record_scene(recorder, params, None);
+    }
+
+    /// Record the draw commands of the primitives intersecting with `frustum`, and return the count of drawn and culled primitives.
+    ///
+    /// The frustum must be in the same space as node transforms(see `Frustum::from_matrix`).
+    /// Skinned and morphed primitives are never culled.
+    pub fn record_command_culled(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams, frustum: &Frustum) -> CullingStats {
+        self.record_scene(recorder, params, Some(frustum))
+    }
+
+    fn record_scene(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams, frustum: Option<&Frustum>) -> CullingStats {
 
         self.meshes.record_command(recorder);
-        self.scene.record_command(recorder, self, params);
+
+        if let Some(morph_set) = params.morph_set {
+            // the morph targets of all meshes are in a single descriptor set, so it only needs to be bound once.
+            recorder.bind_descriptor_sets(params.pipeline_layout, morph_set, &[self.meshes.morph.descriptor_set(params.frame_index)], &[]);
+        }
+
+        self.scene.record_command(recorder, self, params, frustum)
+    }
+
+    /// Record the draw commands of active scene, where the primitives of the same mesh are merged into instanced draws.
+    ///
+    /// The instance transforms are read from the set of `params.instance_set` instead of node attachments,
+    /// and `EXT_mesh_gpu_instancing` instances are only drawn by this method.
+    /// The blended primitives are not sorted by `params.view_position`.
+    pub fn record_command_instanced(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams) {
+
+        self.meshes.record_command(recorder);
+
+        if let Some(morph_set) = params.morph_set {
+            recorder.bind_descriptor_sets(params.pipeline_layout, morph_set, &[self.meshes.morph.descriptor_set(params.frame_index)], &[]);
+        }
+
+        // the node attachment in set 0 is unused by instanced shaders, but its dynamic offset must be provided.
+        recorder.bind_descriptor_sets(params.pipeline_layout, 0, &[params.descriptor_set], &[0]);
+
+        self.instances.record_command(recorder, self, params);
     }
-}
 
-impl VmaResourceDiscardable for VkglTFModel {
+    /// Return the bounding box of node's mesh in the space of node transforms, using the world transform written in last `update`.
+    ///
+    /// Return None if the node does not reference any mesh.
+    pub fn node_bounds(&self, node_index: ReferenceIndex) -> Option<BoundingBox> {
+
+        let mesh_index = self.nodes.list.get(node_index).mesh()?;
+        let transform = self.nodes.attached_transform(node_index)?;
+        Some(self.meshes.list.get(mesh_index).bounds().transform(&transform))
+    }
+
+    /// Recalculate the world transforms of nodes changed in scene graph, and write the node attachments, joint matrices, morph weights and instance data of `frame_index`.
+    pub fn update(&mut self, frame_index: usize) {
+
+        self.nodes.update(frame_index);
+        self.animations.update_joints(self.nodes.graph(), frame_index);
+        self.meshes.morph.update(frame_index);
+        self.instances.update(frame_index, self.nodes.graph());
+    }
 
-    fn discard_by(self, vma: &mut vma::Allocator) -> VkResult<()> {
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-        self.meshes.discard_by(vma)?;
-        self.nodes.discard_by(vma)
+        self.materials.discard_by(device)?;
+        self.animations.discard_by(device)?;
+        self.meshes.discard_by(device)?;
+        self.instances.discard_by(device)?;
+        device.vma_discard(self.nodes)
     }
 }
 // --------------------------------------------------------------------------------------
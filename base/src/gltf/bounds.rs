@@ -0,0 +1,145 @@
+
+use crate::{Mat4F, Vec3F, Vec4F};
+
+use serde_derive::{Serialize, Deserialize};
+
+// --------------------------------------------------------------------------------------
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BoundingBox {
+
+    pub min: Vec3F,
+    pub max: Vec3F,
+}
+
+impl BoundingBox {
+
+    pub fn new(min: Vec3F, max: Vec3F) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// A bounding box that contains nothing, which is the identity of `union`.
+    pub fn empty() -> BoundingBox {
+        BoundingBox {
+            min: Vec3F::broadcast(::std::f32::INFINITY),
+            max: Vec3F::broadcast(::std::f32::NEG_INFINITY),
+        }
+    }
+
+    /// Return the smallest bounding box containing all the points, or an empty box if there is no point.
+    pub fn from_points(points: impl Iterator<Item=Vec3F>) -> BoundingBox {
+        points.fold(BoundingBox::empty(), |bounds, point| bounds.union(&BoundingBox::new(point, point)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Vec3F {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Return the smallest bounding box containing both boxes.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: Vec3F::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y), self.min.z.min(other.min.z)),
+            max: Vec3F::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y), self.max.z.max(other.max.z)),
+        }
+    }
+
+    /// Return the bounding box containing this box after `transform`.
+    pub fn transform(&self, transform: &Mat4F) -> BoundingBox {
+
+        if self.is_empty() {
+            return *self
+        }
+
+        let mut result = BoundingBox::empty();
+        for corner_index in 0..8 {
+            let corner = Vec3F::new(
+                if corner_index & 0b001 == 0 { self.min.x } else { self.max.x },
+                if corner_index & 0b010 == 0 { self.min.y } else { self.max.y },
+                if corner_index & 0b100 == 0 { self.min.z } else { self.max.z },
+            );
+            let point = transform.mul_point(corner);
+            result = result.union(&BoundingBox::new(point, point));
+        }
+        result
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The six clip planes of a view volume, used to cull the primitives outside the screen.
+///
+/// Each plane is stored as (normal, distance), with the normal pointing into the volume.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+
+    planes: [Vec4F; 6],
+}
+
+impl Frustum {
+
+    /// Extract the frustum from a combined projection-view matrix, whose clip space depth ranges in [0, 1] as Vulkan does.
+    ///
+    /// If the model is rendered with a model matrix, pass `proj * view * model` to get the frustum in model space.
+    pub fn from_matrix(matrix: &Mat4F) -> Frustum {
+
+        let columns = matrix.into_col_arrays();
+        let row = |i: usize| Vec4F::new(columns[0][i], columns[1][i], columns[2][i], columns[3][i]);
+        let (row0, row1, row2, row3) = (row(0), row(1), row(2), row(3));
+
+        let normalize = |plane: Vec4F| {
+            let length = Vec3F::new(plane.x, plane.y, plane.z).magnitude();
+            if length > 0.0 { plane / length } else { plane }
+        };
+
+        Frustum {
+            planes: [
+                normalize(row3 + row0), // left
+                normalize(row3 - row0), // right
+                normalize(row3 + row1), // bottom
+                normalize(row3 - row1), // top
+                normalize(row2),        // near
+                normalize(row3 - row2), // far
+            ],
+        }
+    }
+
+    /// Extract the frustum from the view and projection matrix, such as `FlightCamera::view_matrix` and `FlightCamera::proj_matrix`.
+    pub fn from_view_proj(view: &Mat4F, proj: &Mat4F) -> Frustum {
+        Frustum::from_matrix(&((*proj) * (*view)))
+    }
+
+    /// Test whether the bounding box is inside or intersects with the frustum.
+    ///
+    /// The test is conservative, so some boxes near the corners of frustum are reported as visible.
+    pub fn intersects(&self, bounds: &BoundingBox) -> bool {
+
+        if bounds.is_empty() {
+            return false
+        }
+
+        self.planes.iter().all(|plane| {
+            // the corner of box that is farthest along the plane normal.
+            let positive_corner = Vec3F::new(
+                if plane.x >= 0.0 { bounds.max.x } else { bounds.min.x },
+                if plane.y >= 0.0 { bounds.max.y } else { bounds.min.y },
+                if plane.z >= 0.0 { bounds.max.z } else { bounds.min.z },
+            );
+            plane.x * positive_corner.x + plane.y * positive_corner.y + plane.z * positive_corner.z + plane.w >= 0.0
+        })
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The count of primitives drawn and skipped by `VkglTFModel::record_command_culled`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CullingStats {
+
+    pub visible: usize,
+    pub culled : usize,
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,272 @@
+
+use crate::gltf::asset::AssetRepository;
+use crate::gltf::scene::Scene;
+use crate::gltf::source::GltfSource;
+use crate::gltf::loader::{GltfModelInfo, SceneSelection};
+use crate::gltf::meshes::{VertexLayout, MeshOptimizeFlags};
+use crate::gltf::nodes::NodeAttachmentFlags;
+use crate::error::{VkResult, VkError};
+use crate::Mat4F;
+
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Write, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// The bytes at the beginning of cooked model file.
+const CACHE_MAGIC: [u8; 8] = *b"VKGLTFCK";
+/// The version of cooked data layout, which must be increased each time the serialized assets change.
+const CACHE_VERSION: u32 = 3;
+
+// --------------------------------------------------------------------------------------
+/// The state of a file when the model was cooked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    length: u64,
+    /// the modification time in seconds and nanoseconds since unix epoch, if supported by platform.
+    modified: Option<(u64, u32)>,
+}
+
+impl FileStamp {
+
+    fn read(path: &Path) -> Option<FileStamp> {
+
+        let metadata = ::std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| (duration.as_secs(), duration.subsec_nanos()));
+
+        let stamp = FileStamp {
+            path: path.to_path_buf(),
+            length: metadata.len(),
+            modified,
+        };
+        Some(stamp)
+    }
+}
+
+/// Identify the glTF source that a cooked model was imported from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum SourceStamp {
+    /// the glTF file and its external buffers and images, with the glTF file at first.
+    Files(Vec<FileStamp>),
+    /// the glTF data in memory. The external resources loaded by its resolver are not tracked.
+    Memory { length: u64, hash: u64 },
+}
+
+impl SourceStamp {
+
+    /// Stamp the source and the external resources referenced by its `json`.
+    fn cook(source: &GltfSource, json: &Value) -> VkResult<SourceStamp> {
+
+        let stamp = match source {
+            | GltfSource::File(path) => {
+
+                let base = path.parent().unwrap_or(Path::new(""));
+                let external_uris = ["buffers", "images"].iter()
+                    .filter_map(|property| json.get(property).and_then(Value::as_array))
+                    .flat_map(|elements| elements.iter())
+                    .filter_map(|element| element.get("uri").and_then(Value::as_str))
+                    .filter(|uri| !uri.starts_with("data:"));
+
+                let mut stamps = vec![FileStamp::read(path).ok_or(VkError::path(path))?];
+                for uri in external_uris {
+                    let resource_path = base.join(uri);
+                    stamps.push(FileStamp::read(&resource_path).ok_or(VkError::path(resource_path))?);
+                }
+
+                SourceStamp::Files(stamps)
+            },
+            | GltfSource::Slice { data, .. } => {
+                SourceStamp::Memory { length: data.len() as u64, hash: fnv1a_hash(data) }
+            },
+        };
+        Ok(stamp)
+    }
+
+    /// Whether `source` is the same source and has not changed since it was stamped.
+    fn is_valid(&self, source: &GltfSource) -> bool {
+
+        match (self, source) {
+            | (SourceStamp::Files(stamps), GltfSource::File(path)) => {
+                let is_same_file = stamps.first().map_or(false, |stamp| stamp.path.as_path() == *path);
+                is_same_file && stamps.iter().all(|stamp| FileStamp::read(&stamp.path).as_ref() == Some(stamp))
+            },
+            | (SourceStamp::Memory { length, hash }, GltfSource::Slice { data, .. }) => {
+                *length == data.len() as u64 && *hash == fnv1a_hash(data)
+            },
+            | _ => false,
+        }
+    }
+}
+
+/// The load options that affect the cooked assets, which must match exactly to reuse a cache.
+#[derive(Serialize)]
+struct CookOptions<'a> {
+    scene: &'a SceneSelection<'a>,
+    attribute: &'a VertexLayout,
+    unroll_strips: bool,
+    generate_normals: bool,
+    generate_tangents: bool,
+    optimization: MeshOptimizeFlags,
+    node: NodeAttachmentFlags,
+    transform: &'a Option<Mat4F>,
+}
+
+impl<'a> CookOptions<'a> {
+
+    fn fingerprint(info: &'a GltfModelInfo) -> VkResult<Vec<u8>> {
+
+        let options = CookOptions {
+            scene: &info.scene,
+            attribute: &info.attribute,
+            unroll_strips: info.unroll_strips,
+            generate_normals: info.generate_normals,
+            generate_tangents: info.generate_tangents,
+            optimization: info.optimization,
+            node: info.node,
+            transform: &info.transform,
+        };
+
+        bincode::serialize(&options)
+            .map_err(VkError::serialize)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    /// the serialized `CookOptions`.
+    options: Vec<u8>,
+    source: SourceStamp,
+}
+
+#[derive(Serialize)]
+struct CookedModelRef<'a> {
+    scene: &'a Scene,
+    repository: &'a AssetRepository,
+}
+
+/// The CPU-side data of model, which has the same serialized layout as `CookedModelRef`.
+#[derive(Deserialize)]
+struct CookedModel {
+    scene: Scene,
+    repository: AssetRepository,
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Read the scene and assets cooked at `cache_path`.
+///
+/// Return None if the cache is missing, corrupted, written by another version, or cooked from different source or options,
+/// so that the model should be imported from its source again.
+pub fn read_cache(cache_path: &Path, info: &GltfModelInfo) -> Option<(Scene, AssetRepository)> {
+
+    let file = File::open(cache_path).ok()?;
+    // the cache file must not be modified by other processes while it is mapped.
+    let mapped = unsafe { memmap::Mmap::map(&file) }.ok()?;
+
+    let mut reader: &[u8] = &mapped;
+
+    let mut magic = [0_u8; 8];
+    let mut version = [0_u8; 4];
+    reader.read_exact(&mut magic).ok()?;
+    reader.read_exact(&mut version).ok()?;
+
+    if magic != CACHE_MAGIC || u32::from_le_bytes(version) != CACHE_VERSION {
+        return None
+    }
+
+    let header: CacheHeader = bincode::deserialize_from(&mut reader).ok()?;
+    let options = CookOptions::fingerprint(info).ok()?;
+
+    if header.options != options || !header.source.is_valid(&info.source) {
+        return None
+    }
+
+    let cooked: CookedModel = bincode::deserialize_from(&mut reader).ok()?;
+    Some((cooked.scene, cooked.repository))
+}
+
+/// Write the scene and assets read from glTF source to `cache_path`, where `json` is the raw json of the source.
+///
+/// The assets must not be allocated yet, and the cache is written to a temporary file at first to avoid leaving a partial cache.
+pub fn write_cache(cache_path: &Path, info: &GltfModelInfo, json: &Value, scene: &Scene, repository: &AssetRepository) -> VkResult<()> {
+
+    let header = CacheHeader {
+        options: CookOptions::fingerprint(info)?,
+        source: SourceStamp::cook(&info.source, json)?,
+    };
+    let cooked = CookedModelRef { scene, repository };
+
+    let temp_path = cache_path.with_extension("cooking");
+    let write_error = |e: ::std::io::Error| VkError::model_cache(cache_path, e.to_string());
+
+    {
+        let file = File::create(&temp_path).map_err(write_error)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(&CACHE_MAGIC).map_err(write_error)?;
+        writer.write_all(&CACHE_VERSION.to_le_bytes()).map_err(write_error)?;
+        bincode::serialize_into(&mut writer, &header).map_err(VkError::serialize)?;
+        bincode::serialize_into(&mut writer, &cooked).map_err(VkError::serialize)?;
+        writer.flush().map_err(write_error)?;
+    }
+
+    ::std::fs::rename(&temp_path, cache_path).map_err(write_error)
+}
+
+/// The 64-bit FNV-1a hash, which is stable across platforms and compiler versions.
+fn fnv1a_hash(data: &[u8]) -> u64 {
+
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ (*byte as u64)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Serialize the Vulkan enums by their raw values, since ash does not implement serde traits.
+macro_rules! vk_enum_serde {
+    ($module:ident, $vk_type:ty) => {
+        pub mod $module {
+
+            use serde::{Serializer, Deserializer, Deserialize};
+
+            pub fn serialize<S: Serializer>(value: &$vk_type, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_i32(value.as_raw())
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$vk_type, D::Error> {
+                i32::deserialize(deserializer).map(<$vk_type>::from_raw)
+            }
+        }
+    };
+}
+
+/// The modules used by `#[serde(with = "...")]` for the Vulkan types in cooked assets.
+pub(crate) mod vk_serde {
+
+    vk_enum_serde!(primitive_topology, ash::vk::PrimitiveTopology);
+    vk_enum_serde!(index_type, ash::vk::IndexType);
+    vk_enum_serde!(filter, ash::vk::Filter);
+    vk_enum_serde!(sampler_mipmap_mode, ash::vk::SamplerMipmapMode);
+    vk_enum_serde!(sampler_address_mode, ash::vk::SamplerAddressMode);
+
+    pub mod extent_2d {
+
+        use ash::vk;
+        use serde::{Serialize, Serializer, Deserializer, Deserialize};
+
+        pub fn serialize<S: Serializer>(value: &vk::Extent2D, serializer: S) -> Result<S::Ok, S::Error> {
+            (value.width, value.height).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<vk::Extent2D, D::Error> {
+            <(u32, u32)>::deserialize(deserializer).map(|(width, height)| vk::Extent2D { width, height })
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,111 @@
+
+use crate::gltf::asset::ReferenceIndex;
+use crate::gltf::nodes::SceneGraph;
+use crate::camera::{FlightCamera, FlightCameraBuilder};
+use crate::{Mat4F, Vec3F};
+
+use serde_derive::{Serialize, Deserialize};
+
+/// The far plane used by the perspective cameras with infinite projection, since `FlightCamera` requires a finite far plane.
+const INFINITE_FAR_PLANE: f32 = 1000.0;
+
+// --------------------------------------------------------------------------------------
+/// The projection of glTF camera.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum CameraProjection {
+    Perspective {
+        /// the vertical field of view in radians.
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// the distance to far plane, or None for infinite projection.
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag : f32,
+        ymag : f32,
+        znear: f32,
+        zfar : f32,
+    },
+}
+
+/// A camera referenced by a node in glTF scene.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GltfCamera {
+
+    /// the name property of camera.
+    pub name: Option<String>,
+    /// the json index of node that references this camera.
+    pub node: ReferenceIndex,
+    pub projection: CameraProjection,
+}
+
+impl GltfCamera {
+
+    /// Read the camera of node, or return None if the node does not reference any camera.
+    pub fn from_doc(doc_node: &gltf::Node) -> Option<GltfCamera> {
+
+        use gltf::camera::Projection;
+
+        let doc_camera = doc_node.camera()?;
+
+        let projection = match doc_camera.projection() {
+            | Projection::Perspective(perspective) => CameraProjection::Perspective {
+                yfov : perspective.yfov(),
+                aspect_ratio: perspective.aspect_ratio(),
+                znear: perspective.znear(),
+                zfar : perspective.zfar(),
+            },
+            | Projection::Orthographic(orthographic) => CameraProjection::Orthographic {
+                xmag : orthographic.xmag(),
+                ymag : orthographic.ymag(),
+                znear: orthographic.znear(),
+                zfar : orthographic.zfar(),
+            },
+        };
+
+        let camera = GltfCamera {
+            name: doc_camera.name().map(String::from),
+            node: doc_node.index(),
+            projection,
+        };
+        Some(camera)
+    }
+
+    /// Create a `FlightCamera` placed at the current world transform of camera node and looking along its -Z axis.
+    ///
+    /// Return None for orthographic cameras, since `FlightCamera` only supports perspective projection.
+    pub fn flight_camera(&self, graph: &SceneGraph, source_transform: &Option<Mat4F>) -> Option<FlightCameraBuilder> {
+
+        let (yfov, aspect_ratio, znear, zfar) = match self.projection {
+            | CameraProjection::Perspective { yfov, aspect_ratio, znear, zfar } => (yfov, aspect_ratio, znear, zfar),
+            | CameraProjection::Orthographic { .. } => return None,
+        };
+
+        let world_transform = match source_transform {
+            | Some(transform) => (*transform) * graph.world_transform(self.node),
+            | None => graph.world_transform(self.node),
+        };
+
+        let position = world_transform.mul_point(Vec3F::zero());
+        let front = world_transform.mul_direction(Vec3F::new(0.0, 0.0, -1.0)).normalized();
+
+        // invert the front vector calculation of `FlightCamera`, whose x component is flipped for Vulkan.
+        let yaw = front.z.atan2(-front.x).to_degrees();
+        let pitch = front.y.max(-1.0).min(1.0).asin().to_degrees().max(-89.0).min(89.0);
+
+        let mut builder = FlightCamera::new()
+            .place_at(position)
+            .yaw(yaw)
+            .pitch(pitch)
+            .field_of_view(yfov)
+            .view_distance(znear, zfar.unwrap_or(INFINITE_FAR_PLANE));
+
+        if let Some(aspect_ratio) = aspect_ratio {
+            builder = builder.screen_aspect_ratio(aspect_ratio);
+        }
+
+        Some(builder)
+    }
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,515 @@
+
+use ash::vk;
+
+use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList, ReferenceIndex};
+use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
+use crate::gltf::scene::Scene;
+use crate::gltf::nodes::{Node, SceneGraph, NodePose};
+use crate::gltf::meshes::{Mesh, RenderParams};
+use crate::gltf::source::read_accessor_floats;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+use crate::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+use crate::ci::vma::{VmaAllocationCI, VmaBuffer};
+use crate::ci::VkObjectBuildableCI;
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::context::{VkDevice, BufferUploadInfo};
+use crate::error::{VkResult, VkErrorKind};
+use crate::utils::memory::IntegerAlignable;
+use crate::{vkuint, vkbytes, vkptr, Mat4F, Vec3F, Vec4F};
+
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::ptr;
+
+// --------------------------------------------------------------------------------------
+/// The data of an instance read by instanced vertex shader, in std430 layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct InstanceData {
+    /// the transform from mesh space to model space.
+    transform: Mat4F,
+    /// the index of morph targets of the drawn primitive, since the first instance is occupied by instance offset.
+    morph_index: vkuint,
+    _padding: [vkuint; 3],
+}
+
+/// Where the transform of an instance comes from.
+#[derive(Debug, Clone, Copy)]
+struct InstanceSource {
+    /// the json index of node referencing the mesh.
+    node: ReferenceIndex,
+    /// the local transform of `EXT_mesh_gpu_instancing` instance, which is applied before the node transform.
+    local: Option<Mat4F>,
+    morph_index: vkuint,
+}
+
+/// The instances of a primitive drawn by one instanced draw.
+#[derive(Debug, Clone)]
+struct InstancedDraw {
+    /// the json index of first node referencing the mesh, used to bind the skin of instances.
+    node: ReferenceIndex,
+    mesh: ReferenceIndex,
+    primitive: usize,
+    first_instance: vkuint,
+    instance_count: vkuint,
+}
+
+/// The consecutive draws sharing the same material, topology and skin, which are issued by one indirect draw.
+#[derive(Debug, Clone)]
+struct DrawBatch {
+    first_draw: usize,
+    draw_count: usize,
+    /// the offset of the first draw command of this batch in indirect buffer.
+    indirect_offset: vkbytes,
+    /// the type of indices used by the draws, or None for non-indexed draws.
+    index_type: Option<vk::IndexType>,
+}
+
+/// The instanced draws of a glTF scene.
+#[derive(Debug, Clone, Default)]
+struct SceneDrawList {
+    draws: Vec<InstancedDraw>,
+    batches: Vec<DrawBatch>,
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The instances of nodes defined by `EXT_mesh_gpu_instancing` extension.
+#[derive(Serialize, Deserialize)]
+pub struct InstanceAsset {
+
+    /// map the json index of node to the local transforms of its instances.
+    node_instances: HashMap<ReferenceIndex, Vec<Mat4F>>,
+}
+
+impl InstanceAsset {
+
+    pub fn new() -> InstanceAsset {
+        InstanceAsset { node_instances: HashMap::new() }
+    }
+}
+
+impl AssetAbstract for InstanceAsset {
+    const ASSET_NAME: &'static str = "Instances";
+
+    fn read_doc(&mut self, source: &GltfDocument, _scene: &Scene) -> VkResult<()> {
+
+        // the glTF crate drops the extensions it does not know, so the instances are parsed from the raw json.
+        let json_nodes = match source.json.get("nodes").and_then(Value::as_array) {
+            | Some(json_nodes) => json_nodes,
+            | None => return Ok(()),
+        };
+
+        for (node_index, json_node) in json_nodes.iter().enumerate() {
+
+            let attributes = match json_node.pointer("/extensions/EXT_mesh_gpu_instancing/attributes") {
+                | Some(attributes) => attributes,
+                | None => continue,
+            };
+
+            let read_attribute = |name: &str| -> VkResult<Option<Vec<f32>>> {
+                match attributes.get(name).and_then(Value::as_u64) {
+                    | Some(accessor_index) => {
+                        let (values, _) = read_accessor_floats(&source.json, &source.buffers, accessor_index as usize)?;
+                        Ok(Some(values))
+                    },
+                    | None => Ok(None),
+                }
+            };
+
+            let translations = read_attribute("TRANSLATION")?;
+            let rotations    = read_attribute("ROTATION")?;
+            let scales       = read_attribute("SCALE")?;
+
+            let instance_count = [(&translations, 3), (&rotations, 4), (&scales, 3)].iter()
+                .filter_map(|(values, components)| values.as_ref().map(|values| values.len() / components))
+                .max().unwrap_or(0);
+
+            let instances = (0..instance_count).map(|i| {
+
+                let pose = NodePose {
+                    translation: translations.as_ref().and_then(|v| v.get((i * 3)..(i * 3 + 3)))
+                        .map_or(Vec3F::zero(), |t| Vec3F::new(t[0], t[1], t[2])),
+                    rotation: rotations.as_ref().and_then(|v| v.get((i * 4)..(i * 4 + 4)))
+                        .map_or(Vec4F::new(0.0, 0.0, 0.0, 1.0), |r| Vec4F::new(r[0], r[1], r[2], r[3])),
+                    scale: scales.as_ref().and_then(|v| v.get((i * 3)..(i * 3 + 3)))
+                        .map_or(Vec3F::one(), |s| Vec3F::new(s[0], s[1], s[2])),
+                };
+                pose.local_transform()
+            }).collect();
+
+            self.node_instances.insert(node_index, instances);
+        }
+
+        Ok(())
+    }
+}
+
+impl InstanceAsset {
+
+    /// Merge the primitives of each loaded scene into instanced draws, and allocate the instance buffer for `frame_in_flight` frames.
+    ///
+    /// The instance data of every frame is initialized with the world transforms in `graph`.
+    /// Indirect draws are used if `multi_draw_indirect` and `draw_indirect_first_instance` features are enabled.
+    pub fn allocate(self, device: &mut VkDevice, scene: &Scene, nodes: &AssetElementList<Node>, meshes: &AssetElementList<Mesh>, graph: &SceneGraph, frame_in_flight: usize) -> VkResult<InstanceResource> {
+
+        let features = device.phy.features_enabled();
+        let is_indirect = features.multi_draw_indirect == vk::TRUE && features.draw_indirect_first_instance == vk::TRUE;
+
+        let mut sources = Vec::new();
+        let mut indirect_commands: Vec<u8> = Vec::new();
+
+        let scenes: Vec<SceneDrawList> = (0..scene.scene_count()).map(|scene_index| {
+
+            let mut draw_list = SceneDrawList::default();
+
+            // group the nodes referencing the same primitive, in the order of their first appearance.
+            let mut groups: Vec<((ReferenceIndex, usize, Option<ReferenceIndex>), Vec<ReferenceIndex>)> = Vec::new();
+            let mut group_positions = HashMap::new();
+
+            for draw in scene.scene_draws(scene_index) {
+                // skinned nodes with different skins can not share the joint matrices.
+                let group_key = (draw.mesh, draw.primitive, nodes.get(draw.node).skin());
+                let position = *group_positions.entry(group_key).or_insert_with(|| {
+                    groups.push((group_key, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[position].1.push(draw.node);
+            }
+
+            for ((mesh_index, primitive_index, _), group_nodes) in groups {
+
+                let primitive = &meshes.get(mesh_index).primitives()[primitive_index];
+                let first_instance = sources.len();
+
+                for node_index in group_nodes.iter().cloned() {
+                    match self.node_instances.get(&node_index) {
+                        | Some(instances) => {
+                            sources.extend(instances.iter().map(|instance| InstanceSource {
+                                node: node_index,
+                                local: Some(*instance),
+                                morph_index: primitive.morph_index(),
+                            }));
+                        },
+                        | None => {
+                            sources.push(InstanceSource {
+                                node: node_index,
+                                local: None,
+                                morph_index: primitive.morph_index(),
+                            });
+                        },
+                    }
+                }
+
+                if sources.len() == first_instance {
+                    continue
+                }
+
+                draw_list.draws.push(InstancedDraw {
+                    node: group_nodes[0],
+                    mesh: mesh_index,
+                    primitive: primitive_index,
+                    first_instance: first_instance as vkuint,
+                    instance_count: (sources.len() - first_instance) as vkuint,
+                });
+            }
+
+            draw_list.batches = batch_draws(&draw_list.draws, nodes, meshes, is_indirect, &mut indirect_commands);
+            draw_list
+        }).collect();
+
+        let frame_count = frame_in_flight.max(1);
+        // the buffer must not be empty even if there is no instance.
+        let frame_size = ((sources.len().max(1) * size_of::<InstanceData>()) as vkbytes)
+            .align_to(device.phy.limits.min_storage_buffer_offset_alignment);
+
+        let instance_buffer = {
+
+            let instance_ci = BufferCI::new(frame_size * (frame_count as vkbytes))
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER);
+            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let instance_allocation = device.vma.create_buffer(instance_ci.as_ref(), allocate_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            VmaBuffer::from(instance_allocation)
+        };
+
+        let indirect_buffer = if is_indirect && !indirect_commands.is_empty() {
+
+            let indirect_ci = BufferCI::new(indirect_commands.len() as vkbytes)
+                .usage(vk::BufferUsageFlags::INDIRECT_BUFFER | vk::BufferUsageFlags::TRANSFER_DST);
+            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let indirect_allocation = device.vma.create_buffer(indirect_ci.as_ref(), allocate_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            let indirect_buffer = VmaBuffer::from(indirect_allocation);
+
+            device.upload_buffer(&indirect_commands, BufferUploadInfo {
+                buffer: indirect_buffer.handle,
+                offset: 0,
+                dst_access: vk::AccessFlags::INDIRECT_COMMAND_READ,
+            })?;
+
+            Some(indirect_buffer)
+        } else {
+            None
+        };
+
+        let descriptor_pool = DescriptorPoolCI::new(frame_count as vkuint)
+            .add_descriptor(vk::DescriptorType::STORAGE_BUFFER, frame_count as vkuint)
+            .build(device)?;
+
+        // Binding 0: the instance data of current frame.
+        let set_layout = DescriptorSetLayoutCI::new()
+            .add_binding(vk::DescriptorSetLayoutBinding {
+                binding: 0,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                p_immutable_samplers: ptr::null(),
+            })
+            .build(device)?;
+
+        // each frame reads its own copy of instance data.
+        let descriptor_sets = (0..frame_count)
+            .fold(DescriptorSetAI::new(descriptor_pool), |set_ai, _| set_ai.add_set_layout(set_layout))
+            .build(device)?;
+
+        let buffer_writes: Vec<DescriptorBufferSetWI> = descriptor_sets.iter().enumerate()
+            .map(|(frame_index, descriptor_set)| {
+                DescriptorBufferSetWI::new(*descriptor_set, 0, vk::DescriptorType::STORAGE_BUFFER)
+                    .add_buffer(vk::DescriptorBufferInfo {
+                        buffer: instance_buffer.handle,
+                        offset: frame_size * (frame_index as vkbytes),
+                        range : frame_size,
+                    })
+            }).collect();
+
+        buffer_writes.iter()
+            .fold(DescriptorSetsUpdateCI::new(), |update_ci, write| update_ci.add_write(write))
+            .update(device);
+
+        let mut result = InstanceResource {
+            scenes, sources, is_indirect,
+            frame_count, frame_size,
+            instance_buffer, indirect_buffer,
+            descriptor_pool, set_layout, descriptor_sets,
+        };
+
+        // write the initial instance data to the buffer of each frame, so that the instances can be drawn before the first `update`.
+        for frame_index in 0..frame_count {
+            result.update(frame_index, graph);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Split the draws into batches, and write the indirect commands of each draw to `indirect_commands` if `is_indirect` is true.
+fn batch_draws(draws: &[InstancedDraw], nodes: &AssetElementList<Node>, meshes: &AssetElementList<Mesh>, is_indirect: bool, indirect_commands: &mut Vec<u8>) -> Vec<DrawBatch> {
+
+    let mut batches: Vec<DrawBatch> = Vec::new();
+    let mut last_state = None;
+
+    for (draw_index, draw) in draws.iter().enumerate() {
+
+        let primitive = &meshes.get(draw.mesh).primitives()[draw.primitive];
+
+        let command_bytes = match *primitive.render_params() {
+            | RenderParams::DrawArray { vertex_count, first_vertex } => {
+                let command = vk::DrawIndirectCommand {
+                    vertex_count,
+                    instance_count: draw.instance_count,
+                    first_vertex,
+                    first_instance: draw.first_instance,
+                };
+                struct_bytes(&command).to_vec()
+            },
+            | RenderParams::DrawIndex { index_count, first_index, vertex_offset, .. } => {
+                let command = vk::DrawIndexedIndirectCommand {
+                    index_count,
+                    instance_count: draw.instance_count,
+                    first_index,
+                    vertex_offset,
+                    first_instance: draw.first_instance,
+                };
+                struct_bytes(&command).to_vec()
+            },
+        };
+
+        // the draws in a batch must share everything bound between draws.
+        let state = (primitive.material().clone(), primitive.topology(), nodes.get(draw.node).skin(), primitive.render_params().index_type());
+        let is_mergeable = is_indirect && last_state.as_ref() == Some(&state);
+
+        if is_mergeable {
+            batches.last_mut().unwrap().draw_count += 1;
+        } else {
+            batches.push(DrawBatch {
+                first_draw: draw_index,
+                draw_count: 1,
+                indirect_offset: indirect_commands.len() as vkbytes,
+                index_type: primitive.render_params().index_type(),
+            });
+        }
+
+        if is_indirect {
+            indirect_commands.extend_from_slice(&command_bytes);
+        }
+        last_state = Some(state);
+    }
+
+    batches
+}
+
+fn struct_bytes<T>(data: &T) -> &[u8] {
+
+    unsafe {
+        ::std::slice::from_raw_parts(data as *const T as *const u8, size_of::<T>())
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The instanced draws of glTF scenes, with the per-instance transforms in a storage buffer.
+pub struct InstanceResource {
+
+    /// the instanced draws of each loaded scene.
+    scenes: Vec<SceneDrawList>,
+    /// the source of each instance, in the order of instance data.
+    sources: Vec<InstanceSource>,
+    /// whether the draws are issued by indirect draw commands.
+    is_indirect: bool,
+
+    /// the count of instance data copies in buffer, one for each frame in flight.
+    frame_count: usize,
+    /// the byte size of instance data in each frame(including the padding for alignment).
+    frame_size: vkbytes,
+
+    /// a persistently mapped storage buffer.
+    instance_buffer: VmaBuffer,
+    indirect_buffer: Option<VmaBuffer>,
+
+    descriptor_pool: vk::DescriptorPool,
+    set_layout: vk::DescriptorSetLayout,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+impl InstanceResource {
+
+    /// The layout of descriptor set bound at `ModelRenderParams::instance_set`, which contains a storage buffer of instance data.
+    pub fn set_layout(&self) -> vk::DescriptorSetLayout {
+        self.set_layout
+    }
+
+    pub fn is_indirect(&self) -> bool {
+        self.is_indirect
+    }
+
+    /// Return the count of draw calls and instances of the scene at `scene_index`.
+    pub fn draw_statistics(&self, scene_index: usize) -> (usize, usize) {
+
+        let draw_list = &self.scenes[scene_index];
+        let instance_count = draw_list.draws.iter().map(|draw| draw.instance_count as usize).sum();
+        (draw_list.batches.len(), instance_count)
+    }
+
+    /// Write the world transforms of instances to the buffer of `frame_index`.
+    ///
+    /// This method must be called when GPU is not reading the buffer of `frame_index`.
+    pub fn update(&mut self, frame_index: usize, graph: &SceneGraph) {
+
+        let frame_slot = frame_index % self.frame_count;
+        let frame_ptr = unsafe {
+            (self.instance_buffer.info.get_mapped_data() as vkptr<u8>).offset((self.frame_size as isize) * (frame_slot as isize)) as vkptr<InstanceData>
+        };
+
+        for (instance_index, source) in self.sources.iter().enumerate() {
+
+            let node_transform = graph.world_transform(source.node);
+            let instance = InstanceData {
+                transform: match source.local {
+                    | Some(local) => node_transform * local,
+                    | None => node_transform,
+                },
+                morph_index: source.morph_index,
+                _padding: [0; 3],
+            };
+
+            unsafe {
+                frame_ptr.offset(instance_index as isize).write(instance);
+            }
+        }
+    }
+
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+
+        if let Some(instance_set) = params.instance_set {
+            let descriptor_set = self.descriptor_sets[params.frame_index % self.frame_count];
+            recorder.bind_descriptor_sets(params.pipeline_layout, instance_set, &[descriptor_set], &[]);
+        }
+
+        let draw_list = &self.scenes[model.active_scene()];
+        // `MeshResource::record_command` binds the 32-bit indices.
+        let mut bound_index_type = vk::IndexType::UINT32;
+
+        for batch in draw_list.batches.iter() {
+
+            let batch_draws = &draw_list.draws[batch.first_draw..(batch.first_draw + batch.draw_count)];
+            let first_primitive = &model.meshes.list.get(batch_draws[0].mesh).primitives()[batch_draws[0].primitive];
+
+            // skip the primitives whose topology does not match the bound pipeline.
+            if params.topology.map_or(false, |topology| topology != first_primitive.topology()) {
+                continue
+            }
+
+            model.nodes.list.get(batch_draws[0].node).bind_skin(recorder, model, params);
+            first_primitive.bind_material(recorder, model, params);
+
+            if let Some(index_type) = batch.index_type {
+                if index_type != bound_index_type {
+                    model.meshes.bind_indices(recorder, index_type);
+                    bound_index_type = index_type;
+                }
+            }
+
+            if let Some(ref indirect_buffer) = self.indirect_buffer {
+
+                if batch.index_type.is_some() {
+                    recorder.draw_indexed_indirect(indirect_buffer.handle, batch.indirect_offset, batch.draw_count as vkuint, size_of::<vk::DrawIndexedIndirectCommand>() as vkuint);
+                } else {
+                    recorder.draw_indirect(indirect_buffer.handle, batch.indirect_offset, batch.draw_count as vkuint, size_of::<vk::DrawIndirectCommand>() as vkuint);
+                }
+            } else {
+
+                for draw in batch_draws {
+                    let primitive = &model.meshes.list.get(draw.mesh).primitives()[draw.primitive];
+                    match *primitive.render_params() {
+                        | RenderParams::DrawArray { vertex_count, first_vertex } => {
+                            recorder.draw(vertex_count, draw.instance_count, first_vertex, draw.first_instance);
+                        },
+                        | RenderParams::DrawIndex { index_count, first_index, vertex_offset, .. } => {
+                            recorder.draw_indexed(index_count, draw.instance_count, first_index, vertex_offset, draw.first_instance);
+                        },
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.set_layout);
+        // descriptor sets are freed with the pool.
+        device.discard(self.descriptor_pool);
+
+        if let Some(indirect_buffer) = self.indirect_buffer {
+            device.vma_discard(indirect_buffer)?;
+        }
+        device.vma_discard(self.instance_buffer)
+    }
+}
+// --------------------------------------------------------------------------------------
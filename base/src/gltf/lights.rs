@@ -0,0 +1,140 @@
+
+use crate::gltf::asset::ReferenceIndex;
+use crate::gltf::nodes::SceneGraph;
+use crate::error::VkResult;
+use crate::{Mat4F, Vec3F};
+
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+
+// --------------------------------------------------------------------------------------
+/// The type of light defined in `KHR_lights_punctual` extension.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LightKind {
+    Directional,
+    Point,
+    Spot {
+        /// the angle in radians from the center of spot where the falloff begins.
+        inner_cone_angle: f32,
+        /// the angle in radians from the center of spot where the falloff ends.
+        outer_cone_angle: f32,
+    },
+}
+
+/// A punctual light referenced by a node in glTF scene.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GltfLight {
+
+    /// the name property of light.
+    pub name: Option<String>,
+    /// the json index of node that references this light.
+    pub node: ReferenceIndex,
+    pub kind: LightKind,
+    /// the linear RGB color of light.
+    pub color: Vec3F,
+    /// the brightness of light, in candela for point and spot lights, and in lux for directional lights.
+    pub intensity: f32,
+    /// the distance where the light intensity reaches zero, or None for infinite range.
+    pub range: Option<f32>,
+}
+
+/// A light with the world transform of its node.
+#[derive(Debug, Clone, Copy)]
+pub struct SceneLight<'a> {
+
+    pub light: &'a GltfLight,
+    /// the transform from light space to world space.
+    pub world_transform: Mat4F,
+    /// the position of light in world space, which is meaningless for directional lights.
+    pub position: Vec3F,
+    /// the direction that light points to in world space(the -Z axis of node), which is meaningless for point lights.
+    pub direction: Vec3F,
+}
+
+impl GltfLight {
+
+    /// Place this light in world space by the current world transform of its node.
+    pub fn place(&self, graph: &SceneGraph, source_transform: &Option<Mat4F>) -> SceneLight {
+
+        let world_transform = match source_transform {
+            | Some(transform) => (*transform) * graph.world_transform(self.node),
+            | None => graph.world_transform(self.node),
+        };
+
+        SceneLight {
+            light: self,
+            position : world_transform.mul_point(Vec3F::zero()),
+            direction: world_transform.mul_direction(Vec3F::new(0.0, 0.0, -1.0)).normalized(),
+            world_transform,
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Read the `KHR_lights_punctual` lights referenced by `nodes` from the raw json of glTF file.
+///
+/// The glTF crate drops the extensions it does not know, so the lights are parsed from the json directly.
+pub fn read_lights(json: &Value, nodes: &[ReferenceIndex]) -> VkResult<Vec<GltfLight>> {
+
+    let light_definitions = match json.pointer("/extensions/KHR_lights_punctual/lights").and_then(Value::as_array) {
+        | Some(definitions) => definitions,
+        | None => return Ok(Vec::new()),
+    };
+
+    let json_nodes = json.get("nodes").and_then(Value::as_array);
+    let mut lights = Vec::new();
+
+    for node_index in nodes.iter().cloned() {
+
+        let light_index = json_nodes
+            .and_then(|json_nodes| json_nodes.get(node_index))
+            .and_then(|json_node| json_node.pointer("/extensions/KHR_lights_punctual/light"))
+            .and_then(Value::as_u64);
+
+        let definition = match light_index.and_then(|i| light_definitions.get(i as usize)) {
+            | Some(definition) => definition,
+            | None => continue,
+        };
+
+        if let Some(light) = read_light(definition, node_index) {
+            lights.push(light);
+        }
+    }
+
+    Ok(lights)
+}
+
+fn read_light(definition: &Value, node: ReferenceIndex) -> Option<GltfLight> {
+
+    let number = |pointer: &str| definition.pointer(pointer).and_then(Value::as_f64).map(|v| v as f32);
+
+    let kind = match definition.get("type").and_then(Value::as_str)? {
+        | "directional" => LightKind::Directional,
+        | "point" => LightKind::Point,
+        | "spot" => LightKind::Spot {
+            // the default values defined in `KHR_lights_punctual` specification.
+            inner_cone_angle: number("/spot/innerConeAngle").unwrap_or(0.0),
+            outer_cone_angle: number("/spot/outerConeAngle").unwrap_or(::std::f32::consts::FRAC_PI_4),
+        },
+        | _ => return None,
+    };
+
+    let color = definition.get("color")
+        .and_then(Value::as_array)
+        .filter(|color| color.len() == 3)
+        .map(|color| {
+            let component = |i: usize| color[i].as_f64().unwrap_or(1.0) as f32;
+            Vec3F::new(component(0), component(1), component(2))
+        })
+        .unwrap_or(Vec3F::one());
+
+    let light = GltfLight {
+        name: definition.get("name").and_then(Value::as_str).map(String::from),
+        intensity: number("/intensity").unwrap_or(1.0),
+        range: number("/range"),
+        node, kind, color,
+    };
+    Some(light)
+}
+// --------------------------------------------------------------------------------------
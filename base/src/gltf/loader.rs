@@ -1,49 +1,126 @@
 
-use std::path::Path;
-
 use crate::gltf::scene::Scene;
+use crate::gltf::source::{GltfSource, import};
+use crate::gltf::cache::{read_cache, write_cache};
 use crate::gltf::nodes::NodeAttachmentFlags;
-use crate::gltf::meshes::AttributeFlags;
+use crate::gltf::meshes::{VertexLayout, MeshOptimizeFlags, AttributeGeneration};
 use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetRepository};
 use crate::gltf::asset::VkglTFModel;
 
 use crate::context::VkDevice;
-use crate::error::{VkResult, VkError, VkErrorKind};
+use crate::error::{VkResult, VkError};
 use crate::Mat4F;
 
+use serde_derive::Serialize;
+use std::path::Path;
+
+
+/// Indicate which scenes will be loaded from glTF file.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum SceneSelection<'a> {
+    /// Load the default scene, or the first scene if the default scene is not specified.
+    Default,
+    /// Load the scene at the index of glTF file.
+    Index(usize),
+    /// Load the first scene with the name.
+    Name(&'a str),
+    /// Load all scenes, and render the default scene at first.
+    /// Use `VkglTFModel::set_active_scene` to switch between them at runtime.
+    All,
+}
 
 pub struct GltfModelInfo<'a> {
-    /// The path of model file.
-    pub path: &'a Path,
-    /// Indicate what attributes will be read from this model file.
-    pub attribute: AttributeFlags,
+    /// Where to load the glTF or GLB model from.
+    pub source: GltfSource<'a>,
+    /// Indicate which scenes will be loaded.
+    pub scene: SceneSelection<'a>,
+    /// Indicate what attributes will be read from this model file, and how they are arranged in vertex buffer.
+    ///
+    /// Use `AttributeFlags::into()` for an interleaved layout of default formats.
+    pub attribute: VertexLayout,
+    /// Convert the strips and fans to lists, so that the primitives only need pipelines of list topologies.
+    ///
+    /// Line loops are always converted to line lists, since Vulkan does not support them.
+    pub unroll_strips: bool,
+    /// Generate flat normals for the triangle primitives without NORMAL, if NORMAL is in `attribute`.
+    ///
+    /// The vertices shared by triangles of different normals are unwelded.
+    pub generate_normals: bool,
+    /// Generate MikkTSpace tangents for the triangle primitives without TANGENT, if TANGENT is in `attribute`.
+    ///
+    /// This requires NORMAL(or `generate_normals`) and TEXCOORD_0, and the vertices on UV seams or mirrors are unwelded.
+    pub generate_tangents: bool,
+    /// Indicate which optimizations are applied to the triangle lists, whose results are reported by `MeshResource::optimization_report`.
+    pub optimization: MeshOptimizeFlags,
     /// Indicate what properties will be read for Node hierarchy(etc. transform matrix).
     pub node: NodeAttachmentFlags,
     /// A matrix that will apply to position attribute of the model.
     pub transform: Option<Mat4F>,
+    /// The count of node attachment copies, so that the nodes can be updated for a frame while other frames are rendering.
+    /// Set it to 1 if the nodes are never changed at runtime.
+    pub frame_in_flight: usize,
+    /// The path of cooked model, or None to import the model from `source` each time.
+    ///
+    /// If the cooked model is valid, the CPU-side data(vertices, indices, draw ranges, nodes, materials, textures and animations) is read from this file without parsing the source.
+    /// Otherwise the model is imported from `source` and cooked to this file.
+    /// The cooked model becomes invalid when the glTF file(or its external buffers and images) or any option except `frame_in_flight` changes.
+    pub cache: Option<&'a Path>,
 }
 
 pub fn load_gltf(device: &mut VkDevice, info: GltfModelInfo) -> VkResult<VkglTFModel> {
 
-    let (doc, buffers, images) = gltf::import(info.path)
-        .map_err(VkErrorKind::ParseGltf)?;
+    if let Some(cache_path) = info.cache {
+        if let Some((scene, asset_repo)) = read_cache(cache_path, &info) {
+            return asset_repo.allocate(device, scene, info.frame_in_flight)
+        }
+    }
+
+    let (doc, buffers, images, json) = import(&info.source)?;
     let document = GltfDocument {
-        doc, buffers, images,
+        doc, buffers, images, json,
         transform: info.transform,
     };
 
-    // Only support loading the default scene or first scene in glTF file.
-    let dst_scene = document.doc.default_scene()
+    let default_scene = document.doc.default_scene()
         .or(document.doc.scenes().next())
         .ok_or(VkError::custom("glTF Scene is missing."))?;
 
-    let scene = Scene::from_doc(dst_scene);
-    let mut asset_repo = AssetRepository::new(info.attribute, info.node)?;
+    let (dst_scenes, active_scene) = match info.scene {
+        | SceneSelection::Default => (vec![default_scene], 0),
+        | SceneSelection::Index(scene_index) => {
+            let dst_scene = document.doc.scenes().nth(scene_index)
+                .ok_or(VkError::custom(format!("glTF Scene {} is missing.", scene_index)))?;
+            (vec![dst_scene], 0)
+        },
+        | SceneSelection::Name(scene_name) => {
+            let dst_scene = document.doc.scenes().find(|doc_scene| doc_scene.name() == Some(scene_name))
+                .ok_or(VkError::custom(format!("glTF Scene named {} is missing.", scene_name)))?;
+            (vec![dst_scene], 0)
+        },
+        | SceneSelection::All => (document.doc.scenes().collect(), default_scene.index()),
+    };
+
+    let mut scene = Scene::from_doc(dst_scenes, active_scene);
+    scene.read_cameras_and_lights(&document)?;
+    let generation = AttributeGeneration {
+        flat_normals: info.generate_normals,
+        tangents: info.generate_tangents,
+    };
+    let mut asset_repo = AssetRepository::new(info.attribute, info.unroll_strips, info.optimization, generation, info.node)?;
     asset_repo.meshes.read_doc(&document, &scene)?;
     asset_repo.nodes.read_doc(&document, &scene)?;
     asset_repo.materials.read_doc(&document, &scene)?;
+    asset_repo.animations.read_doc(&document, &scene)?;
+    asset_repo.instances.read_doc(&document, &scene)?;
+
+    if let Some(cache_path) = info.cache {
+        // the cache only speeds up the next loading, so the model is still allocated if it fails to be written.
+        if let Err(error) = write_cache(cache_path, &info, &document.json, &scene, &asset_repo) {
+            println!("[Warning] Failed to write glTF cache to {:?}: {}", cache_path, error);
+        }
+    }
 
-    let result = asset_repo.allocate(device, scene)?;
+    let result = asset_repo.allocate(device, scene, info.frame_in_flight)?;
     Ok(result)
 }
 
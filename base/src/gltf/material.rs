@@ -1,38 +1,153 @@
 
-use serde_derive::Serialize;
+use ash::vk;
+use serde_derive::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::ptr;
 
 use crate::gltf::asset::{GltfDocument, AssetAbstract};
 use crate::gltf::asset::ReferenceIndex;
+use crate::gltf::textures::{TextureAsset, TextureResource, DefaultTexture};
 use crate::gltf::scene::Scene;
+use crate::gltf::meshes::AttributeFlags;
+
+use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+use crate::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+use crate::ci::VkObjectBuildableCI;
+use crate::context::VkDevice;
 use crate::error::{VkResult, VkError};
 use crate::{vkfloat, vkuint};
 
 pub type MatSerializedData = Vec<u8>;
 pub type MaterialSlice<'a> = &'a [u8];
-pub type MaterialResource = MaterialAsset;
 
 const DEFAULT_MATERIAL_INDEX : usize = usize::max_value();
 const DEFAULT_MATERIAL_OFFSET: usize = 0;
-const MATERIAL_SIZE: usize = ::std::mem::size_of::<MaterialData>();
+pub(crate) const MATERIAL_SIZE: usize = ::std::mem::size_of::<MaterialData>();
 type MaterialOffset = usize;
 
+/// The count of texture bindings in the descriptor set of each material.
+const MATERIAL_TEXTURE_COUNT: usize = 5;
+
+/// The GLSL source of reference PBR vertex shader, which reads POSITION, NORMAL and TEXCOORD_0 attributes(`AttributeFlags::ATTR_PNTE0`),
+/// and TANGENT if it is defined by `pbr_shader_source`.
+pub const PBR_VERTEX_SHADER_SOURCE  : &'static str = include_str!("pbr.vert.glsl");
+/// The GLSL source of reference PBR vertex shader for skinned mesh, which additionally reads JOINTS_0 and WEIGHTS_0 attributes(`AttributeFlags::ATTR_PNTE0JW`).
+pub const PBR_SKINNED_VERTEX_SHADER_SOURCE: &'static str = include_str!("pbr_skinned.vert.glsl");
+/// The GLSL source of reference PBR vertex shader for mesh with morph targets, which reads the morph targets and weights from the set of `ModelRenderParams::morph_set`(set 3).
+pub const PBR_MORPHED_VERTEX_SHADER_SOURCE: &'static str = include_str!("pbr_morphed.vert.glsl");
+/// The GLSL source of reference PBR vertex shader for `VkglTFModel::record_command_instanced`, which reads the instance transforms from the set of `ModelRenderParams::instance_set`(set 4).
+pub const PBR_INSTANCED_VERTEX_SHADER_SOURCE: &'static str = include_str!("pbr_instanced.vert.glsl");
+/// The GLSL source of reference PBR fragment shader for glTF metallic-roughness material.
+pub const PBR_FRAGMENT_SHADER_SOURCE: &'static str = include_str!("pbr.frag.glsl");
+
+/// Return the source of a reference PBR shader(e.g. `PBR_VERTEX_SHADER_SOURCE`) for the vertex layout containing `attributes`.
+///
+/// If TANGENT is in `attributes`, `HAS_TANGENT` is defined, so that the normal map uses the tangents read from vertices(or generated on import),
+/// and the following attributes are read from the next locations. Otherwise the tangent frame is derived from screen-space derivatives.
+pub fn pbr_shader_source(source: &str, attributes: AttributeFlags) -> String {
+
+    if (attributes & AttributeFlags::TANGENT) == AttributeFlags::NONE {
+        return source.to_string()
+    }
+
+    // the macro must be defined after the `#version` directive.
+    match source.find('\n') {
+        | Some(version_end) => format!("{}\n#define HAS_TANGENT{}", &source[..version_end], &source[version_end..]),
+        | None => source.to_string(),
+    }
+}
+
 // ------------------------------------------------------------------------------------
+/// The alpha mode of glTF material, which decides the draw order and blending of primitives.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum AlphaMode {
+    /// the alpha value is ignored.
+    Opaque,
+    /// the fragment is discarded if its alpha value is less than the alpha cutoff.
+    Mask,
+    /// the fragment is blended with the background using its alpha value.
+    Blend,
+}
+
+impl From<gltf::material::AlphaMode> for AlphaMode {
+
+    fn from(mode: gltf::material::AlphaMode) -> AlphaMode {
+        match mode {
+            | gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            | gltf::material::AlphaMode::Mask   => AlphaMode::Mask,
+            | gltf::material::AlphaMode::Blend  => AlphaMode::Blend,
+        }
+    }
+}
+
+impl AlphaMode {
+
+    /// The value of alpha mode in material push constant(0 for opaque, 1 for mask and 2 for blend).
+    fn as_raw(&self) -> vkuint {
+        match self {
+            | AlphaMode::Opaque => 0,
+            | AlphaMode::Mask   => 1,
+            | AlphaMode::Blend  => 2,
+        }
+    }
+}
+
+/// The properties of material that affect the pipeline state and draw order of primitives.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MaterialMode {
+    pub alpha_mode: AlphaMode,
+    /// the back-face culling should be disabled for double-sided material.
+    pub double_sided: bool,
+}
+
+impl Default for MaterialMode {
+
+    fn default() -> MaterialMode {
+        MaterialMode {
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+impl From<&gltf::Material<'_>> for MaterialMode {
+
+    fn from(raw_material: &gltf::Material) -> MaterialMode {
+        MaterialMode {
+            alpha_mode: AlphaMode::from(raw_material.alpha_mode()),
+            double_sided: raw_material.double_sided(),
+        }
+    }
+}
+
+/// The material properties pushed as push constant, whose layout matches the `Material` block in pbr.frag.glsl.
+#[repr(C)]
 #[derive(Debug, Clone, Copy, Serialize)]
 struct MaterialData {
 
-    base_color_factor: [vkfloat; 4],
-    emissive_factor  : [vkfloat; 3],
-    metallic_factor  : vkfloat,
+    base_color_factor : [vkfloat; 4],
+    emissive_factor   : [vkfloat; 3],
+    metallic_factor   : vkfloat,
+    roughness_factor  : vkfloat,
+    normal_scale      : vkfloat,
+    occlusion_strength: vkfloat,
+    alpha_cutoff      : vkfloat,
+    alpha_mode        : vkuint,
 }
 
 impl Default for MaterialData {
 
+    /// The default values defined in glTF specification.
     fn default() -> MaterialData {
         MaterialData {
             base_color_factor: [1.0; 4],
             emissive_factor: [0.0; 3],
             metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            alpha_cutoff: 0.5,
+            alpha_mode: AlphaMode::Opaque.as_raw(),
         }
     }
 }
@@ -46,7 +161,12 @@ impl From<gltf::Material<'_>> for MaterialData {
         MaterialData {
             base_color_factor : raw_pbr.base_color_factor(),
             metallic_factor   : raw_pbr.metallic_factor(),
+            roughness_factor  : raw_pbr.roughness_factor(),
             emissive_factor   : raw_material.emissive_factor(),
+            normal_scale      : raw_material.normal_texture().map(|normal| normal.scale()).unwrap_or(1.0),
+            occlusion_strength: raw_material.occlusion_texture().map(|occlusion| occlusion.strength()).unwrap_or(1.0),
+            alpha_cutoff      : raw_material.alpha_cutoff(),
+            alpha_mode        : AlphaMode::from(raw_material.alpha_mode()).as_raw(),
         }
     }
 }
@@ -65,12 +185,60 @@ impl MaterialData {
 
 
 // ------------------------------------------------------------------------------------
+/// The json index of textures referenced by a material, in the order of their bindings in material descriptor set.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct MaterialTextures {
+
+    base_color: Option<ReferenceIndex>,
+    metallic_roughness: Option<ReferenceIndex>,
+    normal: Option<ReferenceIndex>,
+    occlusion: Option<ReferenceIndex>,
+    emissive: Option<ReferenceIndex>,
+}
+
+impl From<&gltf::Material<'_>> for MaterialTextures {
+
+    fn from(raw_material: &gltf::Material) -> MaterialTextures {
+
+        let raw_pbr = raw_material.pbr_metallic_roughness();
+
+        MaterialTextures {
+            base_color         : raw_pbr.base_color_texture().map(|info| info.texture().index()),
+            metallic_roughness : raw_pbr.metallic_roughness_texture().map(|info| info.texture().index()),
+            normal             : raw_material.normal_texture().map(|info| info.texture().index()),
+            occlusion          : raw_material.occlusion_texture().map(|info| info.texture().index()),
+            emissive           : raw_material.emissive_texture().map(|info| info.texture().index()),
+        }
+    }
+}
+
+impl MaterialTextures {
+
+    fn bindings(&self) -> [(Option<ReferenceIndex>, DefaultTexture); MATERIAL_TEXTURE_COUNT] {
+        [
+            (self.base_color,         DefaultTexture::White),
+            (self.metallic_roughness, DefaultTexture::White),
+            (self.normal,             DefaultTexture::Normal),
+            (self.occlusion,          DefaultTexture::White),
+            (self.emissive,           DefaultTexture::White),
+        ]
+    }
+}
+// ------------------------------------------------------------------------------------
+
+
+// ------------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize)]
 pub struct MaterialAsset {
 
     data_content: MatSerializedData,
     material_count: usize,
 
     material_mapping: HashMap<ReferenceIndex, MaterialOffset>,
+    material_textures: HashMap<ReferenceIndex, MaterialTextures>,
+    material_modes: HashMap<ReferenceIndex, MaterialMode>,
+
+    textures: TextureAsset,
 }
 
 impl MaterialAsset {
@@ -85,31 +253,86 @@ impl MaterialAsset {
         material_mapping.insert(DEFAULT_MATERIAL_INDEX, DEFAULT_MATERIAL_OFFSET);
         let material_count = 1;
 
-        let result = MaterialAsset { data_content, material_count, material_mapping };
+        // the default material does not reference any texture.
+        let mut material_textures = HashMap::new();
+        material_textures.insert(DEFAULT_MATERIAL_INDEX, MaterialTextures::default());
+        let mut material_modes = HashMap::new();
+        material_modes.insert(DEFAULT_MATERIAL_INDEX, MaterialMode::default());
+
+        let result = MaterialAsset {
+            data_content, material_count, material_mapping, material_textures, material_modes,
+            textures: TextureAsset::new(),
+        };
         Ok(result)
     }
 
-    pub const fn material_size(&self) -> vkuint {
-        MATERIAL_SIZE as vkuint
-    }
+    /// Upload the textures referenced by materials, and write them to a descriptor set for each material.
+    pub fn allocate(self, device: &mut VkDevice) -> VkResult<MaterialResource> {
 
-    pub fn get_material_serialized(&self, material_index: &Option<ReferenceIndex>) -> MaterialSlice {
+        let textures = self.textures.allocate(device)?;
 
-        let offset = self.material_mapping.get(&material_index.unwrap_or(DEFAULT_MATERIAL_INDEX)).cloned()
-            .unwrap_or(DEFAULT_MATERIAL_OFFSET);
-        &self.data_content[offset..(offset + MATERIAL_SIZE)]
+        let set_count = self.material_textures.len() as vkuint;
+        let descriptor_pool = DescriptorPoolCI::new(set_count)
+            .add_descriptor(vk::DescriptorType::COMBINED_IMAGE_SAMPLER, set_count * (MATERIAL_TEXTURE_COUNT as vkuint))
+            .build(device)?;
+
+        // Binding 0 ~ 4: the base color, metallic-roughness, normal, occlusion and emissive texture of material.
+        let set_layout = (0..MATERIAL_TEXTURE_COUNT).fold(DescriptorSetLayoutCI::new(), |layout_ci, binding| {
+            layout_ci.add_binding(vk::DescriptorSetLayoutBinding {
+                binding: binding as vkuint,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            })
+        }).build(device)?;
+
+        let mut descriptor_sets = HashMap::with_capacity(self.material_textures.len());
+
+        for (material_index, material_textures) in self.material_textures.iter() {
+
+            let descriptor_set = DescriptorSetAI::new(descriptor_pool)
+                .add_set_layout(set_layout)
+                .build(device)?
+                .remove(0);
+
+            let texture_writes: Vec<DescriptorImageSetWI> = material_textures.bindings().iter().enumerate()
+                .map(|(binding, (texture_index, default))| {
+                    DescriptorImageSetWI::new(descriptor_set, binding as vkuint, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .add_image(textures.descriptor(*texture_index, *default))
+                }).collect();
+
+            texture_writes.iter()
+                .fold(DescriptorSetsUpdateCI::new(), |update_ci, write| update_ci.add_write(write))
+                .update(device);
+
+            descriptor_sets.insert(*material_index, descriptor_set);
+        }
+
+        let result = MaterialResource {
+            data_content: self.data_content,
+            material_mapping: self.material_mapping,
+            material_modes: self.material_modes,
+            textures, descriptor_pool, set_layout, descriptor_sets,
+        };
+        Ok(result)
     }
 }
 
 impl AssetAbstract for MaterialAsset {
     const ASSET_NAME: &'static str = "Materials";
 
-    fn read_doc(&mut self, source: &GltfDocument, _scene: &Scene) -> VkResult<()> {
+    fn read_doc(&mut self, source: &GltfDocument, scene: &Scene) -> VkResult<()> {
+
+        self.textures.read_doc(source, scene)?;
 
         for doc_material in source.doc.materials() {
 
             if let Some(json_index) = doc_material.index() {
 
+                self.material_textures.insert(json_index, MaterialTextures::from(&doc_material));
+                self.material_modes.insert(json_index, MaterialMode::from(&doc_material));
+
                 let material = MaterialData::from(doc_material);
                 let material_serialized = material.serialize()?;
                 self.data_content.extend(material_serialized);
@@ -125,3 +348,60 @@ impl AssetAbstract for MaterialAsset {
     }
 }
 // ------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------
+pub struct MaterialResource {
+
+    data_content: MatSerializedData,
+    material_mapping: HashMap<ReferenceIndex, MaterialOffset>,
+    material_modes: HashMap<ReferenceIndex, MaterialMode>,
+
+    textures: TextureResource,
+
+    descriptor_pool: vk::DescriptorPool,
+    set_layout: vk::DescriptorSetLayout,
+    descriptor_sets: HashMap<ReferenceIndex, vk::DescriptorSet>,
+}
+
+impl MaterialResource {
+
+    pub const fn material_size(&self) -> vkuint {
+        MATERIAL_SIZE as vkuint
+    }
+
+    pub fn get_material_serialized(&self, material_index: &Option<ReferenceIndex>) -> MaterialSlice {
+
+        let offset = self.material_mapping.get(&material_index.unwrap_or(DEFAULT_MATERIAL_INDEX)).cloned()
+            .unwrap_or(DEFAULT_MATERIAL_OFFSET);
+        &self.data_content[offset..(offset + MATERIAL_SIZE)]
+    }
+
+    pub fn get_material_mode(&self, material_index: &Option<ReferenceIndex>) -> MaterialMode {
+
+        self.material_modes.get(&material_index.unwrap_or(DEFAULT_MATERIAL_INDEX)).cloned()
+            .unwrap_or_default()
+    }
+
+    /// The layout of material descriptor set, which should be included in the pipeline layout at the set index of `ModelRenderParams::material_set`.
+    ///
+    /// The base color, metallic-roughness, normal, occlusion and emissive texture are bound to binding 0 ~ 4 as combined image samplers.
+    pub fn set_layout(&self) -> vk::DescriptorSetLayout {
+        self.set_layout
+    }
+
+    /// Return the descriptor set containing the textures of material, or the descriptor set of default material if it is None.
+    pub fn get_descriptor_set(&self, material_index: &Option<ReferenceIndex>) -> vk::DescriptorSet {
+
+        self.descriptor_sets.get(&material_index.unwrap_or(DEFAULT_MATERIAL_INDEX)).cloned()
+            .unwrap_or(self.descriptor_sets[&DEFAULT_MATERIAL_INDEX])
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.set_layout);
+        // descriptor sets are freed with the pool.
+        device.discard(self.descriptor_pool);
+        self.textures.discard_by(device)
+    }
+}
+// ------------------------------------------------------------------------------------
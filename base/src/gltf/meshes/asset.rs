@@ -1,31 +1,46 @@
 
 use ash::vk;
 
-use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList};
+use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList, ReferenceIndex};
 use crate::gltf::scene::Scene;
 use crate::gltf::meshes::mesh::Mesh;
-use crate::gltf::meshes::attributes::{AttributesData, AttributeFlags};
+use crate::gltf::meshes::primitive::RenderParams;
+use crate::gltf::meshes::attributes::{AttributesData, VertexLayout};
 use crate::gltf::meshes::indices::IndicesData;
+use crate::gltf::meshes::morph::{MorphTargetsData, MorphResource};
+use crate::gltf::meshes::optimize::{MeshOptimizer, MeshOptimizeFlags, OptimizationReport};
+use crate::gltf::meshes::generate::AttributeGeneration;
 
 use crate::ci::buffer::BufferCI;
 use crate::ci::vma::{VmaAllocationCI, VmaBuffer};
 use crate::ci::pipeline::VertexInputSCI;
 
-use crate::context::VkDevice;
-use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi, CmdTransferApi};
+use crate::context::{VkDevice, BufferUploadInfo};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 
-use crate::error::{VkResult, VkError, VkErrorKind};
-use crate::vkptr;
+use crate::error::{VkResult, VkErrorKind};
+use crate::vkbytes;
+
+use serde_derive::{Serialize, Deserialize};
 
 use std::convert::TryFrom;
 
 
+#[derive(Serialize, Deserialize)]
 pub struct MeshAsset {
 
     attributes: AttributesData,
     indices: IndicesData,
+    morph: MorphTargetsData,
+    optimizer: MeshOptimizer,
 
     meshes: AssetElementList<Mesh>,
+    topology_groups: Vec<TopologyGroup>,
+
+    /// the missing attributes to generate for triangles.
+    generation: AttributeGeneration,
+    /// whether to convert strips and fans to lists.
+    is_unroll_strips: bool,
 }
 
 struct MeshAssetBlock {
@@ -39,20 +54,57 @@ pub struct MeshResource {
     pub(crate) list: AssetElementList<Mesh>,
 
     vertices: VmaBuffer,
+    /// the offset of each vertex stream in vertex buffer.
+    vertex_offsets: Vec<vkbytes>,
     indices: Option<VmaBuffer>,
+    /// the offset of 16-bit indices in index buffer.
+    short_indices_offset: vkbytes,
+
+    /// the statistics of mesh data before and after the import optimizations.
+    optimization_report: OptimizationReport,
+    /// the morph targets and weights of all meshes.
+    pub morph: MorphResource,
+    /// the draw ranges of all primitives grouped by their topologies.
+    topology_groups: Vec<TopologyGroup>,
 
     pub vertex_input: VertexInputSCI,
 }
 
-impl TryFrom<AttributeFlags> for MeshAsset {
-    type Error = VkError;
+/// The primitives sharing the same topology, which can be drawn with the same pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyGroup {
+
+    #[serde(with = "crate::gltf::cache::vk_serde::primitive_topology")]
+    pub topology: vk::PrimitiveTopology,
+    pub ranges: Vec<PrimitiveRange>,
+}
+
+/// The draw range of a primitive in the vertex and index buffer of model.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PrimitiveRange {
+
+    /// the json index of mesh containing the primitive.
+    pub mesh: ReferenceIndex,
+    /// the position of primitive in its mesh.
+    pub primitive: usize,
+    pub params: RenderParams,
+}
+
+impl MeshAsset {
 
-    fn try_from(flag: AttributeFlags) -> VkResult<MeshAsset> {
+    /// `is_unroll_strips` indicates whether to convert the strips and fans to lists, so that all primitives can be drawn with list topologies.
+    /// `optimization` indicates the optimizations applied to triangle lists.
+    /// `generation` indicates which missing attributes are generated for triangles.
+    pub fn new(layout: VertexLayout, is_unroll_strips: bool, optimization: MeshOptimizeFlags, generation: AttributeGeneration) -> VkResult<MeshAsset> {
 
         let result = MeshAsset {
-            attributes: AttributesData::try_from(flag)?,
+            attributes: AttributesData::try_from(layout)?,
             indices: Default::default(),
+            morph  : Default::default(),
+            optimizer: MeshOptimizer::new(optimization),
             meshes : Default::default(),
+            topology_groups: Vec::new(),
+            generation, is_unroll_strips,
         };
         Ok(result)
     }
@@ -66,7 +118,24 @@ impl AssetAbstract for MeshAsset {
         for doc_mesh in source.doc.meshes() {
 
             let json_index = doc_mesh.index();
-            let mesh = Mesh::from_doc(doc_mesh, source, &mut self.attributes, &mut self.indices)?;
+            let mesh = Mesh::from_doc(doc_mesh, source, &mut self.attributes, &mut self.indices, &mut self.morph, &mut self.optimizer, self.generation, self.is_unroll_strips)?;
+
+            for (primitive_index, primitive) in mesh.primitives().iter().enumerate() {
+
+                let range = PrimitiveRange {
+                    mesh: json_index,
+                    primitive: primitive_index,
+                    params: *primitive.render_params(),
+                };
+
+                match self.topology_groups.iter_mut().find(|group| group.topology == primitive.topology()) {
+                    | Some(group) => group.ranges.push(range),
+                    | None => self.topology_groups.push(TopologyGroup {
+                        topology: primitive.topology(),
+                        ranges: vec![range],
+                    }),
+                }
+            }
 
             self.meshes.push(json_index, mesh);
         }
@@ -77,24 +146,27 @@ impl AssetAbstract for MeshAsset {
 
 impl MeshAsset {
 
-    pub fn allocate(self, device: &mut VkDevice) -> VkResult<MeshResource> {
+    /// Upload the vertices and indices, and allocate the morph weights for `frame_in_flight` frames.
+    pub fn allocate(self, device: &mut VkDevice, frame_in_flight: usize) -> VkResult<MeshResource> {
 
-        // allocate staging buffer.
-        let staging_block = self.allocate_staging(&mut device.vma)?;
         // allocate mesh buffer.
         let mesh_block = self.allocate_mesh(&mut device.vma)?;
 
-        // copy data from staging buffer to mesh buffer.
-        MeshAsset::copy_staging2mesh(device, &staging_block, &mesh_block)?;
+        // upload vertices and indices data to mesh buffer.
+        self.upload_mesh(device, &mesh_block)?;
 
-        // discard staging resource.
-        staging_block.discard(&mut device.vma)?;
+        let vertex_input = self.attributes.input_descriptions();
+        let morph = self.morph.allocate(device, frame_in_flight)?;
 
         let result = MeshResource {
             vertices: mesh_block.vertices,
+            vertex_offsets: self.attributes.stream_offsets(),
             indices: mesh_block.indices,
+            short_indices_offset: self.indices.short_indices_offset(),
+            optimization_report: *self.optimizer.report(),
             list: self.meshes,
-            vertex_input: self.attributes.input_descriptions(),
+            topology_groups: self.topology_groups,
+            morph, vertex_input,
         };
         Ok(result)
     }
@@ -136,131 +208,92 @@ impl MeshAsset {
         Ok(mesh_block)
     }
 
-    fn allocate_staging(&self, vma: &mut vma::Allocator) -> VkResult<MeshAssetBlock> {
-
-        let staging_vertices = {
+    fn upload_mesh(&self, device: &mut VkDevice, meshes: &MeshAssetBlock) -> VkResult<()> {
 
-            let vertex_ci = BufferCI::new(self.attributes.buffer_size_estimated())
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (handle, allocation, info) = vma.create_buffer(
-                vertex_ci.as_ref(), allocate_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
-
-            let data_ptr = vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
-
-            self.attributes.data_content.map_data(data_ptr);
-
-            vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
-
-            VmaBuffer { handle, allocation, info }
-        };
-
-        // allocate index buffer for glTF attributes.
-        let staging_indices = if let Some(indices_size) = self.indices.buffer_size_estimated() {
-
-            let indices_ci = BufferCI::new(indices_size)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (handle, allocation, info) = vma.create_buffer(
-                indices_ci.as_ref(), allocate_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+        // copy the data of each vertex stream to target buffer, one stream after another.
+        let stream_offsets = self.attributes.stream_offsets();
+        for (stream, offset) in self.attributes.streams().iter().zip(stream_offsets) {
 
-            let data_ptr = vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
+            if stream.is_empty() { continue }
 
-            self.indices.map_data(data_ptr);
-
-            vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
+            device.upload_buffer(stream, BufferUploadInfo {
+                buffer: meshes.vertices.handle,
+                offset, // the starting offset of this stream in buffer.
+                dst_access: vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            })?;
+        }
 
-            Some(VmaBuffer { handle, allocation, info })
-        } else {
-            None
-        };
+        // copy index data to target buffer, where the 16-bit indices are placed after the 32-bit indices.
+        if let Some(ref meshes_indices) = meshes.indices {
 
-        let staging_meshes = MeshAssetBlock {
-            vertices: staging_vertices,
-            indices : staging_indices,
-        };
-        Ok(staging_meshes)
-    }
+            let index_regions = [
+                (self.indices.long_bytes(), 0),
+                (self.indices.short_bytes(), self.indices.short_indices_offset()),
+            ];
 
-    fn copy_staging2mesh(device: &VkDevice, staging: &MeshAssetBlock, meshes: &MeshAssetBlock) -> VkResult<()> {
+            for (index_bytes, offset) in index_regions.iter().cloned() {
 
-        let cmd_recorder = device.get_transfer_recorder();
+                if index_bytes.is_empty() { continue }
 
-        cmd_recorder.begin_record()?;
-
-        let vertex_copy_region = vk::BufferCopy {
-            src_offset: 0, // the starting offset of buffer.
-            dst_offset: 0,
-            size      : staging.vertices.info.get_size() as _,
-        };
-        // copy vertices data to target buffer.
-        cmd_recorder.copy_buf2buf(staging.vertices.handle, meshes.vertices.handle, &[vertex_copy_region]);
-
-        // copy index data to target buffer.
-        if let Some(ref staging_index) = staging.indices {
-            if let Some(ref meshes_indices) = meshes.indices {
-                let index_copy_region = vk::BufferCopy {
-                    src_offset: 0,
-                    dst_offset: 0,
-                    size      : staging_index.info.get_size() as _,
-                };
-                cmd_recorder.copy_buf2buf(staging_index.handle, meshes_indices.handle, &[index_copy_region]);
+                device.upload_buffer(index_bytes, BufferUploadInfo {
+                    buffer: meshes_indices.handle,
+                    offset,
+                    dst_access: vk::AccessFlags::INDEX_READ,
+                })?;
             }
         }
 
-        cmd_recorder.end_record()?;
-        // execute and wait the copy operation.
-        device.flush_transfer(cmd_recorder)?;
-
+        // the copy operations are executed on the next `submit_uploads` of device.
         Ok(())
     }
 }
 
-impl MeshAssetBlock {
-
-    fn discard(&self, vma: &mut vma::Allocator) -> VkResult<()> {
+impl MeshResource {
 
-        vma.destroy_buffer(self.vertices.handle, &self.vertices.allocation)
-            .map_err(VkErrorKind::Vma)?;
+    /// Return the draw ranges of primitives grouped by topology, in the order that each topology first appears in glTF file.
+    ///
+    /// Record the model once for each topology with `ModelRenderParams::topology` and a pipeline of matching topology.
+    pub fn topology_groups(&self) -> &[TopologyGroup] {
+        &self.topology_groups
+    }
 
-        if let Some(ref indices) = self.indices {
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
 
-            vma.destroy_buffer(indices.handle, &indices.allocation)
-                .map_err(VkErrorKind::Vma)?;
-        }
+        // each vertex stream is bound to the binding of its index.
+        let vertex_buffers = vec![self.vertices.handle; self.vertex_offsets.len()];
+        recorder.bind_vertex_buffers(0, &vertex_buffers, &self.vertex_offsets);
 
-        Ok(())
+        self.bind_indices(recorder, vk::IndexType::UINT32);
     }
-}
 
-impl MeshResource {
-
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
-
-        recorder.bind_vertex_buffers(0, &[self.vertices.handle], &[0]);
+    /// Bind the region of index buffer containing the indices of `index_type`.
+    ///
+    /// `record_command` binds the 32-bit indices, so the 16-bit indices must be bound before drawing the primitives using them.
+    pub fn bind_indices(&self, recorder: &VkCmdRecorder<IGraphics>, index_type: vk::IndexType) {
 
         if let Some(ref index_buffer) = self.indices {
-            recorder.bind_index_buffer(index_buffer.handle, vk::IndexType::UINT32, 0);
+
+            let offset = if index_type == vk::IndexType::UINT16 { self.short_indices_offset } else { 0 };
+            recorder.bind_index_buffer(index_buffer.handle, index_type, offset);
         }
     }
 
-    pub fn discard_by(&self, vma: &mut vma::Allocator) -> VkResult<()> {
+    /// Return the ACMR and buffer sizes of meshes before and after the import optimizations.
+    pub fn optimization_report(&self) -> &OptimizationReport {
+        &self.optimization_report
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-        vma.destroy_buffer(self.vertices.handle, &self.vertices.allocation)
+        device.vma.destroy_buffer(self.vertices.handle, &self.vertices.allocation)
             .map_err(VkErrorKind::Vma)?;
 
         if let Some(ref indices) = self.indices {
 
-            vma.destroy_buffer(indices.handle, &indices.allocation)
+            device.vma.destroy_buffer(indices.handle, &indices.allocation)
                 .map_err(VkErrorKind::Vma)?;
         }
 
-        Ok(())
+        self.morph.discard_by(device)
     }
 }
@@ -2,48 +2,242 @@
 use ash::vk;
 
 use crate::gltf::asset::GltfDocument;
+use crate::gltf::meshes::generate::GeneratedVertices;
 use crate::ci::pipeline::VertexInputSCI;
 use crate::error::{VkResult, VkError};
-use crate::{vkbytes, vkptr};
-use crate::{Vec3F, Vec2F, Vec4F, Vec4U};
+use crate::{vkbytes, Vec3F};
+
+use serde_derive::{Serialize, Deserialize};
 
 use std::ops::{BitAnd, BitOr, BitOrAssign, BitAndAssign};
 use std::convert::TryFrom;
 
 
 // --------------------------------------------------------------------------------------
+/// The vertex data of all primitives, arranged in the streams described by `VertexLayout`.
+#[derive(Serialize, Deserialize)]
 pub struct AttributesData {
 
-    /// the size of each vertices.
-    pub vertex_size: vkbytes,
-    /// the vertices attributes data of all primitive.
-    pub data_content: Box<dyn VertexAttributes>,
+    layout: VertexLayout,
+    /// the position of each attribute in its stream, in the order of `layout.attributes`.
+    offsets: Vec<usize>,
+    /// the size of a vertex in each stream.
+    strides: Vec<usize>,
+    /// the raw bytes of each stream.
+    streams: Vec<Vec<u8>>,
+    /// the total count of vertices in each stream.
+    vertex_count: usize,
 }
 
-impl TryFrom<AttributeFlags> for AttributesData {
+impl TryFrom<VertexLayout> for AttributesData {
     type Error = VkError;
 
-    fn try_from(flags: AttributeFlags) -> VkResult<AttributesData> {
+    fn try_from(layout: VertexLayout) -> VkResult<AttributesData> {
+
+        layout.validate()?;
 
-        let vertex_size = flags.vertex_size()
-            .ok_or(VkError::unimplemented("Primitive attributes combination"))?;
-        let content = flags.new_attributes()
-            .ok_or(VkError::unimplemented("Primitive attributes combination"))?;
+        let stream_count = layout.stream_count();
+        let mut strides = vec![0; stream_count];
+        let mut offsets = Vec::with_capacity(layout.attributes.len());
+
+        // attributes are packed tightly in their streams, since all element sizes are multiple of 4 bytes.
+        for attribute in layout.attributes.iter() {
+            offsets.push(strides[attribute.stream]);
+            strides[attribute.stream] += attribute.element_size();
+        }
 
-        let result = AttributesData { vertex_size, data_content: content };
+        let result = AttributesData {
+            streams: vec![Vec::new(); stream_count],
+            vertex_count: 0,
+            layout, offsets, strides,
+        };
         Ok(result)
     }
 }
 
 impl AttributesData {
 
+    /// Read the attributes of primitive and append them to the end of each stream.
+    ///
+    /// The attributes declared in layout but missing in primitive are filled with zero.
+    pub fn extend(&mut self, primitive: &gltf::Primitive, source: &GltfDocument) -> AttributeExtendInfo {
+
+        let vertex_count = primitive.attributes()
+            .map(|(_, accessor)| accessor.count())
+            .max().unwrap_or(0);
+
+        self.extend_vertices(primitive, source, vertex_count, None, None)
+    }
+
+    /// Append the vertices of `generated` to the end of each stream.
+    ///
+    /// Each vertex copies the attributes of its original vertex in primitive, except the attributes replaced by the generated ones.
+    pub fn extend_generated(&mut self, primitive: &gltf::Primitive, source: &GltfDocument, generated: &GeneratedVertices) -> AttributeExtendInfo {
+        self.extend_vertices(primitive, source, generated.vertex_count(), Some(&generated.origins), Some(generated))
+    }
+
+    /// Append `vertex_count` vertices whose attribute values are returned by `values`, for the vertices not read from glTF file.
+    ///
+    /// The attributes declared in layout but missing in `values` are filled with zero.
+    pub fn extend_values<'a>(&mut self, vertex_count: usize, values: impl Fn(AttributeFlags) -> Option<&'a [[f32; 4]]>) -> AttributeExtendInfo {
+
+        let first_vertex = self.vertex_count;
+
+        for (stream, stride) in self.streams.iter_mut().zip(self.strides.iter()) {
+            stream.resize(stream.len() + vertex_count * stride, 0);
+        }
+
+        for (attribute, offset) in self.layout.attributes.iter().zip(self.offsets.iter()) {
+
+            let stride = self.strides[attribute.stream];
+            let stream = &mut self.streams[attribute.stream];
+
+            if let Some(attribute_values) = values(attribute.attribute) {
+                for (i, value) in attribute_values.iter().take(vertex_count).enumerate() {
+                    let element_start = (first_vertex + i) * stride + offset;
+                    attribute.format.write(&mut stream[element_start..], value, attribute.component_count());
+                }
+            }
+        }
+
+        self.vertex_count += vertex_count;
+
+        AttributeExtendInfo { first_vertex, vertex_count }
+    }
+
+    fn extend_vertices(&mut self, primitive: &gltf::Primitive, source: &GltfDocument, vertex_count: usize, origins: Option<&[u32]>, generated: Option<&GeneratedVertices>) -> AttributeExtendInfo {
+
+        let first_vertex = self.vertex_count;
+
+        for (stream, stride) in self.streams.iter_mut().zip(self.strides.iter()) {
+            stream.resize(stream.len() + vertex_count * stride, 0);
+        }
+
+        for (attribute, offset) in self.layout.attributes.iter().zip(self.offsets.iter()) {
+
+            let generated_values = generated.and_then(|generated| match attribute.attribute {
+                | AttributeFlags::NORMAL  => generated.normals.as_ref(),
+                | AttributeFlags::TANGENT => generated.tangents.as_ref(),
+                | _ => None,
+            });
+
+            let stride = self.strides[attribute.stream];
+            let stream = &mut self.streams[attribute.stream];
+
+            if let Some(values) = generated_values {
+
+                for (i, value) in values.iter().take(vertex_count).enumerate() {
+                    let element_start = (first_vertex + i) * stride + offset;
+                    attribute.format.write(&mut stream[element_start..], value, attribute.component_count());
+                }
+            } else if let Some(values) = read_attribute(primitive, source, attribute.attribute) {
+
+                for i in 0..vertex_count {
+                    let origin = origins.map(|origins| origins[i] as usize).unwrap_or(i);
+                    if let Some(value) = values.get(origin) {
+                        let element_start = (first_vertex + i) * stride + offset;
+                        attribute.format.write(&mut stream[element_start..], value, attribute.component_count());
+                    }
+                }
+            }
+        }
+
+        self.vertex_count += vertex_count;
+
+        AttributeExtendInfo { first_vertex, vertex_count }
+    }
+
+    /// Return the combination of all attributes in vertex layout.
+    pub fn flags(&self) -> AttributeFlags {
+        self.layout.flags()
+    }
+
     pub fn buffer_size_estimated(&self) -> vkbytes {
-        (self.data_content.length() as vkbytes) * self.vertex_size
+        self.streams.iter()
+            .map(|stream| stream.len() as vkbytes)
+            .sum()
+    }
+
+    /// The total size of a vertex in all streams.
+    pub fn vertex_size(&self) -> usize {
+        self.strides.iter().sum()
+    }
+
+    /// Return the data of vertex at `vertex_index` in all streams.
+    pub fn vertex_bytes(&self, vertex_index: usize) -> Vec<u8> {
+
+        self.streams.iter().zip(self.strides.iter())
+            .flat_map(|(stream, stride)| stream[(vertex_index * stride)..((vertex_index + 1) * stride)].iter().cloned())
+            .collect()
+    }
+
+    /// Rearrange the last `vertex_count` vertices starting from `first_vertex`, where vertex `i` moves to `remap[i]`.
+    ///
+    /// The vertices remapped beyond `new_count` are dropped, and the vertices after `first_vertex + new_count` are truncated.
+    pub fn remap_vertices(&mut self, first_vertex: usize, vertex_count: usize, remap: &[u32], new_count: usize) {
+
+        debug_assert_eq!(first_vertex + vertex_count, self.vertex_count);
+
+        for (stream, stride) in self.streams.iter_mut().zip(self.strides.iter().cloned()) {
+
+            let origin = stream.split_off(first_vertex * stride);
+            let mut remapped = vec![0; new_count * stride];
+
+            for (old_index, new_index) in remap.iter().take(vertex_count).enumerate() {
+                let new_index = *new_index as usize;
+                if new_index < new_count {
+                    remapped[(new_index * stride)..((new_index + 1) * stride)]
+                        .copy_from_slice(&origin[(old_index * stride)..((old_index + 1) * stride)]);
+                }
+            }
+
+            stream.extend(remapped);
+        }
+
+        self.vertex_count = first_vertex + new_count;
+    }
+
+    /// Return the offset of each stream in vertex buffer, where the streams are placed one after another.
+    pub fn stream_offsets(&self) -> Vec<vkbytes> {
+
+        self.streams.iter().scan(0, |offset, stream| {
+            let stream_offset = *offset;
+            *offset += stream.len() as vkbytes;
+            Some(stream_offset)
+        }).collect()
+    }
+
+    /// Return the raw bytes of each stream.
+    pub fn streams(&self) -> &[Vec<u8>] {
+        &self.streams
     }
 
-    #[inline]
     pub fn input_descriptions(&self) -> VertexInputSCI {
-        self.data_content.input_descriptions()
+
+        let mut sci = VertexInputSCI::new();
+
+        for (binding, stride) in self.strides.iter().enumerate() {
+            sci = sci.add_binding(vk::VertexInputBindingDescription {
+                binding: binding as _,
+                stride : *stride as _,
+                input_rate: vk::VertexInputRate::VERTEX,
+            });
+        }
+
+        for (attribute, offset) in self.layout.attributes.iter().zip(self.offsets.iter()) {
+            sci = sci.add_attribute(vk::VertexInputAttributeDescription {
+                location: 0,
+                binding : attribute.stream as _,
+                // the combination has been checked in `VertexLayout::validate`.
+                format  : attribute.vk_format().unwrap(),
+                offset  : *offset as _,
+            });
+        }
+
+        // the locations follow the order that attributes are added to layout.
+        sci.inner_set_attribute_locations();
+
+        sci
     }
 }
 
@@ -52,396 +246,344 @@ pub struct AttributeExtendInfo {
     pub first_vertex: usize,
     pub vertex_count: usize,
 }
+// --------------------------------------------------------------------------------------
 
 
 // --------------------------------------------------------------------------------------
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct AttributeFlags(u32);
+/// The component format to store a vertex attribute in vertex buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttributeFormat {
+    /// 32-bit float components. Supported by all attributes except JOINTS_0.
+    Float32,
+    /// 16-bit signed normalized components, for NORMAL and TANGENT. 3-component attributes are padded to 4 components.
+    Snorm16,
+    /// 8-bit unsigned normalized components, for COLOR_0 and WEIGHTS_0.
+    Unorm8,
+    /// 16-bit unsigned integer components, for JOINTS_0 only.
+    Uint16,
+}
 
-impl AttributeFlags {
-    pub const NONE      : AttributeFlags = AttributeFlags(0b0);
-    pub const POSITION  : AttributeFlags = AttributeFlags(0b1);
-    pub const NORMAL    : AttributeFlags = AttributeFlags(0b10);
-    pub const TANGENT   : AttributeFlags = AttributeFlags(0b100);
-    pub const TEXCOORD_0: AttributeFlags = AttributeFlags(0b1000);
-    pub const TEXCOORD_1: AttributeFlags = AttributeFlags(0b10000);
-    pub const COLOR_0   : AttributeFlags = AttributeFlags(0b100000);
-    pub const JOINTS_0  : AttributeFlags = AttributeFlags(0b1000000);
-    pub const WEIGHTS_0 : AttributeFlags = AttributeFlags(0b10000000);
+impl AttributeFormat {
 
-    // POSITION.
-    pub const ATTR_P: AttributeFlags = AttributeFlags(0b1);
-    // POSITION, NORMAL.
-    pub const ATTR_PN: AttributeFlags = AttributeFlags(0b11);
-    // POSITION, TEXCOORD_0.
-    pub const ATTR_PTE0: AttributeFlags = AttributeFlags(0b1001);
-    // POSITION, NORMAL, TEXCOORD_0.
-    pub const ATTR_PNTE0: AttributeFlags = AttributeFlags(0b1011);
-    // POSITION, NORMAL, TANGENT, TEXCOORD_0, TEXCOORD_1, COLOR_0, JOINTS_0, WEIGHTS_0.
-    pub const ATTR_ALL: AttributeFlags = AttributeFlags(0b11111111);
-
-    fn vertex_size(&self) -> Option<vkbytes> {
-        use std::mem::size_of;
-        match *self {
-            | AttributeFlags::ATTR_P     => Some(size_of::<Attr_P>()     as _),
-            | AttributeFlags::ATTR_PN    => Some(size_of::<Attr_PN>()    as _),
-            | AttributeFlags::ATTR_PTE0  => Some(size_of::<Attr_PTe0>()  as _),
-            | AttributeFlags::ATTR_PNTE0 => Some(size_of::<Attr_PNTe0>() as _),
-            | AttributeFlags::ATTR_ALL   => Some(size_of::<Attr_All>()   as _),
-            | _ => None,
+    /// The default format of each attribute: `Uint16` for JOINTS_0 and `Float32` for the others.
+    pub fn default_for(attribute: AttributeFlags) -> AttributeFormat {
+        if attribute == AttributeFlags::JOINTS_0 {
+            AttributeFormat::Uint16
+        } else {
+            AttributeFormat::Float32
         }
     }
 
-    fn new_attributes(&self) -> Option<Box<dyn VertexAttributes>> {
-        match *self {
-            | AttributeFlags::ATTR_P => {
-                let attributes = Box::new(Attr_P::default());
-                Some(attributes as Box<dyn VertexAttributes>)
-            },
-            | AttributeFlags::ATTR_PN => {
-                let attributes = Box::new(Attr_PN::default());
-                Some(attributes as Box<dyn VertexAttributes>)
+    /// Encode the first `component_count` values to `dst`.
+    fn write(&self, dst: &mut [u8], value: &[f32; 4], component_count: usize) {
+
+        match self {
+            | AttributeFormat::Float32 => {
+                for (i, component) in value.iter().take(component_count).enumerate() {
+                    dst[(i * 4)..(i * 4 + 4)].copy_from_slice(&component.to_bits().to_ne_bytes());
+                }
             },
-            | AttributeFlags::ATTR_PTE0 => {
-                let attributes = Box::new(Attr_PTe0::default());
-                Some(attributes as Box<dyn VertexAttributes>)
+            | AttributeFormat::Snorm16 => {
+                for (i, component) in value.iter().enumerate() {
+                    let encoded = (component.max(-1.0).min(1.0) * 32767.0).round() as i16;
+                    dst[(i * 2)..(i * 2 + 2)].copy_from_slice(&encoded.to_ne_bytes());
+                }
             },
-            | AttributeFlags::ATTR_PNTE0 => {
-                let attributes = Box::new(Attr_PNTe0::default());
-                Some(attributes as Box<dyn VertexAttributes>)
+            | AttributeFormat::Unorm8 => {
+                for (i, component) in value.iter().enumerate() {
+                    dst[i] = (component.max(0.0).min(1.0) * 255.0).round() as u8;
+                }
             },
-            | AttributeFlags::ATTR_ALL => {
-                let attributes = Box::new(Attr_All::default());
-                Some(attributes as Box<dyn VertexAttributes>)
+            | AttributeFormat::Uint16 => {
+                for (i, component) in value.iter().enumerate() {
+                    dst[(i * 2)..(i * 2 + 2)].copy_from_slice(&(*component as u16).to_ne_bytes());
+                }
             },
-            | _ => None
         }
     }
 }
 
-impl BitAnd for AttributeFlags {
-    type Output = Self;
-
-    fn bitand(self, rhs: Self) -> Self {
-        AttributeFlags(self.0 & rhs.0)
-    }
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct LayoutAttribute {
+    /// a single attribute flag.
+    attribute: AttributeFlags,
+    format: AttributeFormat,
+    /// the index of stream(vertex binding) containing this attribute.
+    stream: usize,
 }
 
-impl BitAndAssign for AttributeFlags {
+impl LayoutAttribute {
 
-    fn bitand_assign(&mut self, rhs: Self) {
-        self.0 &= rhs.0
+    fn component_count(&self) -> usize {
+        match self.attribute {
+            | AttributeFlags::TEXCOORD_0
+            | AttributeFlags::TEXCOORD_1 => 2,
+            | AttributeFlags::POSITION
+            | AttributeFlags::NORMAL => 3,
+            | _ => 4,
+        }
     }
-}
-
-impl BitOr for AttributeFlags {
-    type Output = Self;
 
-    fn bitor(self, rhs: Self) -> Self {
-        AttributeFlags(self.0 | rhs.0)
+    /// Return the vertex input format, or None if the format is not supported by the attribute.
+    fn vk_format(&self) -> Option<vk::Format> {
+
+        let format = match (self.format, self.component_count()) {
+            | (AttributeFormat::Float32, 2) => vk::Format::R32G32_SFLOAT,
+            | (AttributeFormat::Float32, 3) => vk::Format::R32G32B32_SFLOAT,
+            | (AttributeFormat::Float32, _) => vk::Format::R32G32B32A32_SFLOAT,
+            // R16G16B16_SNORM is rarely supported as vertex format, so use 4 components instead.
+            | (AttributeFormat::Snorm16, 3)
+            | (AttributeFormat::Snorm16, 4) => vk::Format::R16G16B16A16_SNORM,
+            | (AttributeFormat::Unorm8, 4)  => vk::Format::R8G8B8A8_UNORM,
+            | (AttributeFormat::Uint16, 4)  => vk::Format::R16G16B16A16_UINT,
+            | _ => return None,
+        };
+
+        let is_supported = match self.attribute {
+            | AttributeFlags::NORMAL
+            | AttributeFlags::TANGENT   => self.format == AttributeFormat::Float32 || self.format == AttributeFormat::Snorm16,
+            | AttributeFlags::COLOR_0
+            | AttributeFlags::WEIGHTS_0 => self.format == AttributeFormat::Float32 || self.format == AttributeFormat::Unorm8,
+            | AttributeFlags::JOINTS_0  => self.format == AttributeFormat::Uint16,
+            | _ => self.format == AttributeFormat::Float32,
+        };
+
+        if is_supported { Some(format) } else { None }
     }
-}
-
-impl BitOrAssign for AttributeFlags {
 
-    fn bitor_assign(&mut self, rhs: Self) {
-        self.0 |= rhs.0;
+    fn element_size(&self) -> usize {
+        match self.format {
+            | AttributeFormat::Float32 => 4 * self.component_count(),
+            | AttributeFormat::Snorm16
+            | AttributeFormat::Uint16  => 2 * 4,
+            | AttributeFormat::Unorm8  => 4,
+        }
     }
 }
-// --------------------------------------------------------------------------------------
-
 
-// --------------------------------------------------------------------------------------
-/// glTF Primitive attributes.
-pub trait VertexAttributes {
+/// Describe which glTF attributes are read, how they are stored and how they are split into vertex streams.
+///
+/// Each attribute gets the location of the order it is added, and each stream is bound to the binding of its index.
+///
+/// ```ignore
+/// // POSITION in binding 0, and NORMAL(snorm16), TEXCOORD_0 in binding 1.
+/// let layout = VertexLayout::new()
+///     .add_attribute(AttributeFlags::POSITION)
+///     .new_stream()
+///     .add_attribute_with_format(AttributeFlags::NORMAL, AttributeFormat::Snorm16)
+///     .add_attribute(AttributeFlags::TEXCOORD_0);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VertexLayout {
+
+    attributes: Vec<LayoutAttribute>,
+    current_stream: usize,
+}
 
-    fn extend(&mut self, primitive: &gltf::Primitive, source: &GltfDocument) -> AttributeExtendInfo;
+impl VertexLayout {
 
-    fn length(&self) -> usize;
+    /// Create an empty layout with a single stream.
+    pub fn new() -> VertexLayout {
+        VertexLayout {
+            attributes: Vec::new(),
+            current_stream: 0,
+        }
+    }
 
-    fn map_data(&self, memory_ptr: vkptr);
+    /// Store all the attributes in `flags` with default format in a single stream.
+    pub fn interleaved(flags: AttributeFlags) -> VertexLayout {
 
-    fn input_descriptions(&self) -> VertexInputSCI;
-}
+        flags.single_attributes()
+            .fold(VertexLayout::new(), |layout, attribute| layout.add_attribute(attribute))
+    }
 
-macro_rules! attribute_type {
-    (position)   => (Vec3F);
-    (normal)     => (Vec3F);
-    (tangents)   => (Vec4F);
-    (texcoord_0) => (Vec2F);
-    (texcoord_1) => (Vec2F);
-    (color_0)    => (Vec4F);
-    (joints_0)   => (Vec4U);
-    (weights_0)  => (Vec4F);
-}
+    /// Store each attribute in `flags` with default format in its own stream.
+    pub fn deinterleaved(flags: AttributeFlags) -> VertexLayout {
 
-macro_rules! attribute_default {
-    (position)   => { Vec3F::zero() };
-    (normal)     => { Vec3F::zero() };
-    (tangents)   => { Vec4F::zero() };
-    (texcoord_0) => { Vec2F::zero() };
-    (texcoord_1) => { Vec2F::zero() };
-    (color_0)    => { Vec4F::zero() };
-    (joints_0)   => { Vec4U::zero() };
-    (weights_0)  => { Vec4F::zero() };
-}
+        flags.single_attributes().enumerate()
+            .fold(VertexLayout::new(), |layout, (i, attribute)| {
+                let layout = if i == 0 { layout } else { layout.new_stream() };
+                layout.add_attribute(attribute)
+            })
+    }
 
-macro_rules! attribute_format {
-    (position)   => { vk::Format::R32G32B32_SFLOAT };
-    (normal)     => { vk::Format::R32G32B32_SFLOAT };
-    (tangents)   => { vk::Format::R32G32B32A32_SFLOAT };
-    (texcoord_0) => { vk::Format::R32G32_SFLOAT };
-    (texcoord_1) => { vk::Format::R32G32_SFLOAT };
-    (color_0)    => { vk::Format::R32G32B32A32_SFLOAT };
-    (joints_0)   => { vk::Format::R16G16B16A16_UNORM };
-    (weights_0)  => { vk::Format::R32G32B32A32_SFLOAT };
-}
+    /// Add an attribute with its default format to the current stream.
+    pub fn add_attribute(self, attribute: AttributeFlags) -> VertexLayout {
+        self.add_attribute_with_format(attribute, AttributeFormat::default_for(attribute))
+    }
 
-macro_rules! read_attribute {
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, position) => {
+    /// Add an attribute with specific format to the current stream.
+    pub fn add_attribute_with_format(mut self, attribute: AttributeFlags, format: AttributeFormat) -> VertexLayout {
+        self.attributes.push(LayoutAttribute { attribute, format, stream: self.current_stream }); self
+    }
 
-        if let Some(pos_iter) = $reader.read_positions() {
+    /// Start a new stream, so that the attributes added later are stored in the next vertex binding.
+    pub fn new_stream(mut self) -> VertexLayout {
+        self.current_stream += 1; self
+    }
 
-            if $target.data.len() == $origin_length {
-                let vertex_iter = pos_iter.map(|pos| {
-                    let mut position = Vec3F::from(pos);
-                    if let Some(ref transform) = $source.transform {
-                        position = transform.mul_point(position);
-                    }
-                    $VertexType { position, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, pos) in pos_iter.enumerate() {
-                    $target.data[i + $origin_length].position = Vec3F::from(pos);
-                }
-            }
-        }
+    /// Return the combination of all attributes in this layout.
+    pub fn flags(&self) -> AttributeFlags {
+        self.attributes.iter()
+            .fold(AttributeFlags::NONE, |flags, attribute| flags | attribute.attribute)
+    }
 
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, normal) => {
+    fn stream_count(&self) -> usize {
+        self.attributes.iter()
+            .map(|attribute| attribute.stream + 1)
+            .max().unwrap_or(0)
+    }
 
-        if let Some(normal_iter) = $reader.read_normals() {
+    fn validate(&self) -> VkResult<()> {
 
-            if $target.data.len() == $origin_length {
-                let vertex_iter = normal_iter.map(|nor| {
-                    let normal = Vec3F::from(nor);
-                    $VertexType { normal, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, normal) in normal_iter.enumerate() {
-                    $target.data[i + $origin_length].normal = Vec3F::from(normal);
-                }
-            }
+        if self.attributes.is_empty() {
+            return Err(VkError::custom("The vertex layout does not contain any attribute."))
         }
 
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, tangents) => {
+        let mut flags = AttributeFlags::NONE;
+        for attribute in self.attributes.iter() {
 
-        if let Some(tangents_iter) = $reader.read_tangents() {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = tangents_iter.map(|tan| {
-                    let tangents = Vec4F::from(tan);
-                    $VertexType { tangents, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, tangent) in tangents_iter.enumerate() {
-                    $target.data[i + $origin_length].tangents = Vec4F::from(tangent);
-                }
+            if attribute.attribute.single_attributes().count() != 1 {
+                return Err(VkError::custom("Each attribute of vertex layout must be a single attribute flag."))
             }
-        }
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, texcoord_0) => {
-
-        if let Some(texcoord_0_iter) = $reader.read_tex_coords(0) {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = texcoord_0_iter.into_f32().map(|texcoord| {
-                    let texcoord_0 = Vec2F::from(texcoord);
-                    $VertexType { texcoord_0, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, texcoord_0) in texcoord_0_iter.into_f32().enumerate() {
-                    $target.data[i + $origin_length].texcoord_0 = Vec2F::from(texcoord_0);
-                }
+            if (flags & attribute.attribute) != AttributeFlags::NONE {
+                return Err(VkError::custom(format!("Duplicated attribute in vertex layout: {:?}.", attribute.attribute)))
             }
-        }
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, texcoord_1) => {
-
-        if let Some(texcoord_1_iter) = $reader.read_tex_coords(1) {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = texcoord_1_iter.into_f32().map(|texcoord| {
-                    let texcoord_1 = Vec2F::from(texcoord);
-                    $VertexType { texcoord_1, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, texcoord_1) in texcoord_1_iter.into_f32().enumerate() {
-                    $target.data[i + $origin_length].texcoord_1 = Vec2F::from(texcoord_1);
-                }
+            if attribute.vk_format().is_none() {
+                return Err(VkError::unimplemented(format!("{:?} format for attribute {:?}", attribute.format, attribute.attribute)))
             }
+            flags |= attribute.attribute;
         }
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, color_0) => {
-
-        if let Some(color_0_iter) = $reader.read_colors(0) {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = color_0_iter.into_rgba_f32().map(|color| {
-                    let color_0 = Vec4F::from(color);
-                    $VertexType { color_0, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, color_0) in color_0_iter.into_rgba_f32().enumerate() {
-                    $target.data[i + $origin_length].color_0 = Vec4F::from(color_0);
-                }
-            }
-        }
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, joints_0) => {
-
-        if let Some(joints_0_iter) = $reader.read_joints(0) {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = joints_0_iter.into_u16().map(|joint| {
-                    let joints_0 = Vec4U::from(joint);
-                    $VertexType { joints_0, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, joints_0) in joints_0_iter.into_u16().enumerate() {
-                    $target.data[i + $origin_length].joints_0 = Vec4U::from(joints_0);
-                }
-            }
-        }
-    };
-    ($target:ident, $reader:ident, $origin_length:ident, $VertexType:ident, $source:ident, weights_0) => {
-
-        if let Some(weights_0_iter) = $reader.read_weights(0) {
-
-            if $target.data.len() == $origin_length {
-                let vertex_iter = weights_0_iter.into_f32().map(|weight| {
-                    let weights_0 = Vec4F::from(weight);
-                    $VertexType { weights_0, ..Default::default() }
-                });
-                $target.data.extend(vertex_iter);
-            } else {
-                for (i, weights_0) in weights_0_iter.into_f32().enumerate() {
-                    $target.data[i + $origin_length].weights_0 = Vec4F::from(weights_0);
-                }
-            }
-        }
-    };
-}
 
-macro_rules! define_attributes {
-    ($name_attributes:ident, $name_vertex:ident, {
-        $(
-            $attribute:ident,
-        )*
-    }) => {
-
-        #[allow(non_camel_case_types)]
-        #[derive(Default)]
-        struct $name_attributes {
-            data: Vec<$name_vertex>,
+        for stream in 0..self.stream_count() {
+            if self.attributes.iter().all(|attribute| attribute.stream != stream) {
+                return Err(VkError::custom(format!("Stream {} of vertex layout does not contain any attribute.", stream)))
+            }
         }
 
-        #[allow(non_camel_case_types)]
-        #[repr(C)]
-        #[derive(Debug, Clone, Copy)]
-        struct $name_vertex {
-            $(
-                $attribute: attribute_type!($attribute),
-            )*
-        }
+        Ok(())
+    }
+}
 
-        impl Default for $name_vertex {
+impl From<AttributeFlags> for VertexLayout {
 
-            fn default() -> $name_vertex {
-                $name_vertex {
-                    $(
-                        $attribute: attribute_default!($attribute),
-                    )*
-                }
-            }
-        }
+    fn from(flags: AttributeFlags) -> VertexLayout {
+        VertexLayout::interleaved(flags)
+    }
+}
+// --------------------------------------------------------------------------------------
 
-        impl VertexAttributes for $name_attributes {
 
-            fn extend(&mut self, primitive: &gltf::Primitive, source: &GltfDocument) -> AttributeExtendInfo {
+// --------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeFlags(u32);
 
-                let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
-                let start_vertex_index = self.data.len();
+impl AttributeFlags {
+    pub const NONE      : AttributeFlags = AttributeFlags(0b0);
+    pub const POSITION  : AttributeFlags = AttributeFlags(0b1);
+    pub const NORMAL    : AttributeFlags = AttributeFlags(0b10);
+    pub const TANGENT   : AttributeFlags = AttributeFlags(0b100);
+    pub const TEXCOORD_0: AttributeFlags = AttributeFlags(0b1000);
+    pub const TEXCOORD_1: AttributeFlags = AttributeFlags(0b10000);
+    pub const COLOR_0   : AttributeFlags = AttributeFlags(0b100000);
+    pub const JOINTS_0  : AttributeFlags = AttributeFlags(0b1000000);
+    pub const WEIGHTS_0 : AttributeFlags = AttributeFlags(0b10000000);
 
-                $(
-                    read_attribute!(self, reader, start_vertex_index, $name_vertex, source, $attribute);
-                )*
+    // POSITION.
+    pub const ATTR_P: AttributeFlags = AttributeFlags(0b1);
+    // POSITION, NORMAL.
+    pub const ATTR_PN: AttributeFlags = AttributeFlags(0b11);
+    // POSITION, TEXCOORD_0.
+    pub const ATTR_PTE0: AttributeFlags = AttributeFlags(0b1001);
+    // POSITION, NORMAL, TEXCOORD_0.
+    pub const ATTR_PNTE0: AttributeFlags = AttributeFlags(0b1011);
+    // POSITION, NORMAL, TEXCOORD_0, JOINTS_0, WEIGHTS_0.
+    pub const ATTR_PNTE0JW: AttributeFlags = AttributeFlags(0b11001011);
+    // POSITION, NORMAL, TANGENT, TEXCOORD_0, TEXCOORD_1, COLOR_0, JOINTS_0, WEIGHTS_0.
+    pub const ATTR_ALL: AttributeFlags = AttributeFlags(0b11111111);
 
-                AttributeExtendInfo {
-                    first_vertex: start_vertex_index,
-                    vertex_count: self.data.len() - start_vertex_index,
-                }
-            }
+    /// All the single attributes, in the order of their default locations.
+    const SINGLE_ATTRIBUTES: [AttributeFlags; 8] = [
+        AttributeFlags::POSITION, AttributeFlags::NORMAL, AttributeFlags::TANGENT, AttributeFlags::TEXCOORD_0,
+        AttributeFlags::TEXCOORD_1, AttributeFlags::COLOR_0, AttributeFlags::JOINTS_0, AttributeFlags::WEIGHTS_0,
+    ];
 
-            fn length(&self) -> usize {
-                self.data.len()
-            }
+    fn single_attributes(self) -> impl Iterator<Item = AttributeFlags> {
+        AttributeFlags::SINGLE_ATTRIBUTES.iter().cloned()
+            .filter(move |attribute| (self & *attribute) != AttributeFlags::NONE)
+    }
+}
 
-            fn map_data(&self, memory_ptr: vkptr) {
+impl BitAnd for AttributeFlags {
+    type Output = Self;
 
-                unsafe {
-                    (memory_ptr as vkptr<$name_vertex>).copy_from(self.data.as_ptr(), self.data.len());
-                }
-            }
+    fn bitand(self, rhs: Self) -> Self {
+        AttributeFlags(self.0 & rhs.0)
+    }
+}
 
-            fn input_descriptions(&self) -> VertexInputSCI {
+impl BitAndAssign for AttributeFlags {
 
-                let input_binding = vk::VertexInputBindingDescription {
-                    binding: 0,
-                    stride : ::std::mem::size_of::<$name_vertex>() as _,
-                    input_rate: vk::VertexInputRate::VERTEX,
-                };
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0
+    }
+}
 
-                let mut sci = VertexInputSCI::new()
-                    .add_binding(input_binding);
+impl BitOr for AttributeFlags {
+    type Output = Self;
 
-                $(
-                    sci = sci.add_attribute(vk::VertexInputAttributeDescription {
-                        location: 0,
-                        binding : 0,
-                        format  : attribute_format!($attribute),
-                        offset  : memoffset::offset_of!($name_vertex, $attribute) as _,
-                    });
-                )*
+    fn bitor(self, rhs: Self) -> Self {
+        AttributeFlags(self.0 | rhs.0)
+    }
+}
 
-                sci.inner_set_attribute_locations();
+impl BitOrAssign for AttributeFlags {
 
-                sci
-            }
-        }
-    };
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
 }
+// --------------------------------------------------------------------------------------
 
-// glTF Primitive with only position attribute.
-define_attributes!(Attr_P, AttrVertex_P, { position, });
-
-/// glTF Primitive with position and normal attributes.
-define_attributes!(Attr_PN, AttrVertexPN, { position, normal, });
 
-/// glTF Primitive with position and normal attributes.
-define_attributes!(Attr_PTe0, AttrVertexPTe0, { position, texcoord_0, });
+// --------------------------------------------------------------------------------------
+/// Read an attribute of primitive, with all values expanded to 4 components.
+fn read_attribute(primitive: &gltf::Primitive, source: &GltfDocument, attribute: AttributeFlags) -> Option<Vec<[f32; 4]>> {
 
-/// glTF Primitive with position, normal and texcoord_0 attributes.
-define_attributes!(Attr_PNTe0, AttrVertex_PNTe0, { position, normal, texcoord_0, });
+    let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
 
-/// glTF Primitive with all attributes.
-define_attributes!(Attr_All, AttrVertex_Ultimate, { position, normal, tangents, texcoord_0, texcoord_1, color_0, joints_0, weights_0, });
+    match attribute {
+        | AttributeFlags::POSITION => {
+            reader.read_positions().map(|pos_iter| pos_iter.map(|pos| {
+                let mut position = Vec3F::from(pos);
+                if let Some(ref transform) = source.transform {
+                    position = transform.mul_point(position);
+                }
+                [position.x, position.y, position.z, 0.0]
+            }).collect())
+        },
+        | AttributeFlags::NORMAL => {
+            reader.read_normals().map(|normal_iter| normal_iter.map(|n| [n[0], n[1], n[2], 0.0]).collect())
+        },
+        | AttributeFlags::TANGENT => {
+            reader.read_tangents().map(|tangents_iter| tangents_iter.collect())
+        },
+        | AttributeFlags::TEXCOORD_0 => {
+            reader.read_tex_coords(0).map(|texcoord_iter| texcoord_iter.into_f32().map(|t| [t[0], t[1], 0.0, 0.0]).collect())
+        },
+        | AttributeFlags::TEXCOORD_1 => {
+            reader.read_tex_coords(1).map(|texcoord_iter| texcoord_iter.into_f32().map(|t| [t[0], t[1], 0.0, 0.0]).collect())
+        },
+        | AttributeFlags::COLOR_0 => {
+            reader.read_colors(0).map(|color_iter| color_iter.into_rgba_f32().collect())
+        },
+        | AttributeFlags::JOINTS_0 => {
+            reader.read_joints(0).map(|joints_iter| joints_iter.into_u16().map(|j| [j[0] as f32, j[1] as f32, j[2] as f32, j[3] as f32]).collect())
+        },
+        | AttributeFlags::WEIGHTS_0 => {
+            reader.read_weights(0).map(|weights_iter| weights_iter.into_f32().collect())
+        },
+        | _ => None,
+    }
+}
 // --------------------------------------------------------------------------------------
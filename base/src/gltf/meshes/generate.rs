@@ -0,0 +1,262 @@
+
+use crate::gltf::asset::GltfDocument;
+use crate::gltf::meshes::attributes::AttributeFlags;
+
+use crate::Vec3F;
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+
+// --------------------------------------------------------------------------------------
+/// Indicate which missing attributes are generated when reading the triangle primitives.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct AttributeGeneration {
+    /// generate flat normals for the primitives without NORMAL.
+    pub flat_normals: bool,
+    /// generate MikkTSpace tangents for the primitives without TANGENT, which requires NORMAL(or flat normals) and TEXCOORD_0.
+    pub tangents: bool,
+}
+
+impl AttributeGeneration {
+
+    /// Return the attributes to generate for primitive, where only the attributes in `layout_flags` are considered.
+    pub fn required_by(&self, primitive: &gltf::Primitive, source: &GltfDocument, layout_flags: AttributeFlags) -> AttributeFlags {
+
+        let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
+        let mut result = AttributeFlags::NONE;
+
+        let is_normal_missing = reader.read_normals().is_none();
+        if self.flat_normals && is_normal_missing && (layout_flags & AttributeFlags::NORMAL) != AttributeFlags::NONE {
+            result |= AttributeFlags::NORMAL;
+        }
+
+        let is_normal_available = !is_normal_missing || self.flat_normals;
+        let is_texcoord_available = reader.read_tex_coords(0).is_some();
+        if self.tangents && reader.read_tangents().is_none() && is_normal_available && is_texcoord_available
+            && (layout_flags & AttributeFlags::TANGENT) != AttributeFlags::NONE {
+            result |= AttributeFlags::TANGENT;
+        }
+
+        result
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The vertices of a triangle list primitive after generating its missing attributes.
+///
+/// The indexed vertices are unwelded to triangle corners at first, and the corners sharing the same original vertex and generated attributes are welded back,
+/// so a vertex is only split where its generated attributes differ between triangles(e.g. the flat normals at the edges, or the tangents at UV seams).
+pub struct GeneratedVertices {
+    /// the original vertex of each generated vertex, relative to the first vertex of primitive.
+    pub origins: Vec<u32>,
+    /// the triangle list indices referring to the generated vertices.
+    pub indices: Vec<u32>,
+    /// the generated normal of each vertex, if NORMAL is generated.
+    pub normals: Option<Vec<[f32; 4]>>,
+    /// the generated tangent of each vertex, if TANGENT is generated.
+    pub tangents: Option<Vec<[f32; 4]>>,
+}
+
+impl GeneratedVertices {
+
+    /// Generate the attributes in `generation` for the triangle list `indices` of primitive, which are relative to its first vertex.
+    pub fn generate(primitive: &gltf::Primitive, source: &GltfDocument, indices: &[u32], generation: AttributeFlags) -> GeneratedVertices {
+
+        let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
+
+        // the normals are not affected by `source.transform`, so the untransformed positions are used here.
+        let positions: Vec<Vec3F> = reader.read_positions()
+            .map(|pos_iter| pos_iter.map(Vec3F::from).collect())
+            .unwrap_or(Vec::new());
+
+        let corners = &indices[..(indices.len() - indices.len() % 3)];
+        let corner_positions: Vec<Vec3F> = corners.iter()
+            .map(|&index| positions.get(index as usize).cloned().unwrap_or(Vec3F::zero()))
+            .collect();
+
+        let is_generate_normals = (generation & AttributeFlags::NORMAL) != AttributeFlags::NONE;
+        let is_generate_tangents = (generation & AttributeFlags::TANGENT) != AttributeFlags::NONE;
+
+        let corner_normals: Vec<Vec3F> = if is_generate_normals {
+            flat_normals(&corner_positions)
+        } else {
+            let normals: Vec<Vec3F> = reader.read_normals()
+                .map(|normal_iter| normal_iter.map(Vec3F::from).collect())
+                .unwrap_or(Vec::new());
+            corners.iter()
+                .map(|&index| normals.get(index as usize).cloned().unwrap_or(Vec3F::zero()))
+                .collect()
+        };
+
+        let corner_tangents: Option<Vec<[f32; 4]>> = if is_generate_tangents {
+            // MikkTSpace assumes the texture origin at bottom-left, while glTF places it at top-left.
+            let texcoords: Vec<[f32; 2]> = reader.read_tex_coords(0)
+                .map(|texcoord_iter| texcoord_iter.into_f32().map(|t| [t[0], 1.0 - t[1]]).collect())
+                .unwrap_or(Vec::new());
+            let corner_texcoords: Vec<[f32; 2]> = corners.iter()
+                .map(|&index| texcoords.get(index as usize).cloned().unwrap_or([0.0, 0.0]))
+                .collect();
+            Some(mikktspace_tangents(&corner_positions, &corner_normals, &corner_texcoords))
+        } else {
+            None
+        };
+
+        // weld the corners whose original vertex and generated attributes are all the same.
+        let mut welded: HashMap<(u32, [u32; 7]), u32> = HashMap::new();
+        let mut result = GeneratedVertices {
+            origins : Vec::new(),
+            indices : Vec::with_capacity(corners.len()),
+            normals : if is_generate_normals { Some(Vec::new()) } else { None },
+            tangents: if is_generate_tangents { Some(Vec::new()) } else { None },
+        };
+
+        for (corner, &origin) in corners.iter().enumerate() {
+
+            let normal = corner_normals[corner];
+            let normal = [normal.x, normal.y, normal.z, 0.0];
+            let tangent = corner_tangents.as_ref().map(|tangents| tangents[corner]).unwrap_or([0.0; 4]);
+
+            let mut key = [0; 7];
+            if is_generate_normals {
+                key[0] = normal[0].to_bits(); key[1] = normal[1].to_bits(); key[2] = normal[2].to_bits();
+            }
+            key[3] = tangent[0].to_bits(); key[4] = tangent[1].to_bits(); key[5] = tangent[2].to_bits(); key[6] = tangent[3].to_bits();
+
+            let next_vertex = result.origins.len() as u32;
+            let vertex = *welded.entry((origin, key)).or_insert(next_vertex);
+
+            if vertex == next_vertex {
+                result.origins.push(origin);
+                if let Some(ref mut normals) = result.normals {
+                    normals.push(normal);
+                }
+                if let Some(ref mut tangents) = result.tangents {
+                    tangents.push(tangent);
+                }
+            }
+            result.indices.push(vertex);
+        }
+
+        result
+    }
+
+    /// The count of generated vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.origins.len()
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Compute the face normal of each triangle for its three corners.
+fn flat_normals(corner_positions: &[Vec3F]) -> Vec<Vec3F> {
+
+    corner_positions.chunks(3).flat_map(|triangle| {
+
+        let cross = Vec3F::cross(triangle[1] - triangle[0], triangle[2] - triangle[0]);
+        let length = cross.magnitude();
+        // the degenerate triangles get an arbitrary normal, so that the shader never normalizes a zero vector.
+        let normal = if length > 0.0 { cross / length } else { Vec3F::new(0.0, 0.0, 1.0) };
+
+        vec![normal; 3]
+    }).collect()
+}
+
+/// Compute the tangent of each triangle corner with the MikkTSpace construction.
+///
+/// The tangent of each triangle is derived from its texture coordinate gradients and projected to the tangent plane of each corner.
+/// The corners sharing the same position, normal, texture coordinate and orientation are then averaged with the corner angles as weights.
+/// Unlike the reference implementation, the corners are grouped by value only, without checking whether their triangles are connected.
+fn mikktspace_tangents(positions: &[Vec3F], normals: &[Vec3F], texcoords: &[[f32; 2]]) -> Vec<[f32; 4]> {
+
+    let corner_count = positions.len();
+    // the tangent of each triangle, and whether it preserves the orientation of texture space.
+    let mut triangle_tangents: Vec<Option<(Vec3F, bool)>> = Vec::with_capacity(corner_count / 3);
+
+    for triangle in 0..(corner_count / 3) {
+
+        let (c0, c1, c2) = (triangle * 3, triangle * 3 + 1, triangle * 3 + 2);
+        let d1 = positions[c1] - positions[c0];
+        let d2 = positions[c2] - positions[c0];
+        let st1 = [texcoords[c1][0] - texcoords[c0][0], texcoords[c1][1] - texcoords[c0][1]];
+        let st2 = [texcoords[c2][0] - texcoords[c0][0], texcoords[c2][1] - texcoords[c0][1]];
+
+        let signed_area = st1[0] * st2[1] - st1[1] * st2[0];
+        let tangent = d1 * st2[1] - d2 * st1[1];
+
+        // the triangles with degenerate texture coordinates do not contribute to the tangents.
+        if signed_area.abs() > ::std::f32::EPSILON && tangent.magnitude_squared() > 0.0 {
+            triangle_tangents.push(Some((tangent, signed_area > 0.0)));
+        } else {
+            triangle_tangents.push(None);
+        }
+    }
+
+    // the accumulated tangents of each group of corners, keyed by the values of position, normal, texture coordinate and orientation.
+    let corner_key = |corner: usize, is_preserving: bool| {
+        let (p, n, t) = (positions[corner], normals[corner], texcoords[corner]);
+        [
+            p.x.to_bits(), p.y.to_bits(), p.z.to_bits(),
+            n.x.to_bits(), n.y.to_bits(), n.z.to_bits(),
+            t[0].to_bits(), t[1].to_bits(), is_preserving as u32,
+        ]
+    };
+    let mut groups: HashMap<[u32; 9], Vec3F> = HashMap::new();
+
+    for (triangle, triangle_tangent) in triangle_tangents.iter().enumerate() {
+
+        if let Some((tangent, is_preserving)) = triangle_tangent {
+
+            for i in 0..3 {
+
+                let corner = triangle * 3 + i;
+                let normal = normalize_or_zero(normals[corner]);
+                let projected = normalize_or_zero(project_to_plane(*tangent, normal));
+
+                let prev_edge = normalize_or_zero(project_to_plane(positions[triangle * 3 + (i + 2) % 3] - positions[corner], normal));
+                let next_edge = normalize_or_zero(project_to_plane(positions[triangle * 3 + (i + 1) % 3] - positions[corner], normal));
+                let angle = Vec3F::dot(prev_edge, next_edge).max(-1.0).min(1.0).acos();
+
+                *groups.entry(corner_key(corner, *is_preserving)).or_insert(Vec3F::zero()) += projected * angle;
+            }
+        }
+    }
+
+    (0..corner_count).map(|corner| {
+
+        let normal = normalize_or_zero(normals[corner]);
+        let (tangent, is_preserving) = match triangle_tangents[corner / 3] {
+            | Some((_, is_preserving)) => {
+                let sum = groups.get(&corner_key(corner, is_preserving)).cloned().unwrap_or(Vec3F::zero());
+                (normalize_or_zero(sum), is_preserving)
+            },
+            | None => (Vec3F::zero(), true),
+        };
+
+        let tangent = if tangent.magnitude_squared() > 0.0 { tangent } else { any_perpendicular(normal) };
+        let sign = if is_preserving { 1.0 } else { -1.0 };
+
+        [tangent.x, tangent.y, tangent.z, sign]
+    }).collect()
+}
+
+fn project_to_plane(v: Vec3F, normal: Vec3F) -> Vec3F {
+    v - normal * Vec3F::dot(normal, v)
+}
+
+fn normalize_or_zero(v: Vec3F) -> Vec3F {
+    let length = v.magnitude();
+    if length > 0.0 { v / length } else { Vec3F::zero() }
+}
+
+/// Return a unit vector perpendicular to `normal`, for the corners whose tangents cannot be derived.
+fn any_perpendicular(normal: Vec3F) -> Vec3F {
+
+    let axis = if normal.x.abs() < 0.9 { Vec3F::new(1.0, 0.0, 0.0) } else { Vec3F::new(0.0, 1.0, 0.0) };
+    let perpendicular = normalize_or_zero(Vec3F::cross(normal, axis));
+
+    if perpendicular.magnitude_squared() > 0.0 { perpendicular } else { axis }
+}
+// --------------------------------------------------------------------------------------
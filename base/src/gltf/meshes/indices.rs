@@ -1,61 +1,134 @@
 
+use ash::vk;
+
 use crate::gltf::asset::GltfDocument;
-use crate::error::{VkResult, VkError};
 
-use crate::{vkuint, vkbytes, vkptr};
+use crate::{vkuint, vkbytes};
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::mem::size_of;
+
+/// The max vertex count of a mesh whose indices can be stored as 16-bit integers.
+pub const SHORT_INDEX_VERTEX_LIMIT: usize = 1 << 16;
 
+#[derive(Serialize, Deserialize)]
 pub struct IndicesData {
 
-    start_index: u32,
-    data_content: Vec<vkuint>,
+    /// the 32-bit indices, placed at the beginning of index buffer.
+    long_indices: Vec<u32>,
+    /// the 16-bit indices, placed after the 32-bit indices in index buffer.
+    short_indices: Vec<u16>,
 }
 
 pub struct IndicesExtendInfo {
 
+    /// the position of first index in the indices of the same type.
     pub first_index  : vkuint,
     pub indices_count: vkuint,
+    pub index_type   : vk::IndexType,
 }
 
 impl IndicesData {
 
-    pub fn extend(&mut self, primitive: &gltf::Primitive, source: &GltfDocument) -> VkResult<IndicesExtendInfo> {
+    /// Read the indices of primitive, which are relative to the first vertex of primitive.
+    ///
+    /// The sequential indices are generated if the primitive does not contain indices.
+    pub fn read_local(primitive: &gltf::Primitive, source: &GltfDocument, vertex_count: usize) -> Vec<u32> {
 
         let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
-        let indices_range = get_indices_range(primitive)?;
 
-        let start_index = self.start_index.clone();
+        match reader.read_indices() {
+            | Some(index_iter) => index_iter.into_u32().collect(),
+            | None => (0..(vertex_count as u32)).collect(),
+        }
+    }
+
+    /// Convert the indices of a strip, fan or loop primitive to the indices of list topology.
+    pub fn unroll(mode: gltf::mesh::Mode, origin: Vec<u32>) -> Vec<u32> {
+
+        use gltf::mesh::Mode;
+
+        let n = origin.len();
+
+        match mode {
+            | Mode::LineStrip => {
+                (1..n).flat_map(|i| vec![origin[i - 1], origin[i]]).collect()
+            },
+            | Mode::LineLoop => {
+                let closing = if n > 1 { vec![origin[n - 1], origin[0]] } else { Vec::new() };
+                (1..n).flat_map(|i| vec![origin[i - 1], origin[i]]).chain(closing).collect()
+            },
+            | Mode::TriangleStrip => {
+                // swap the first two vertices of odd triangles to keep the same winding order.
+                (2..n).flat_map(|i| if i % 2 == 0 {
+                    vec![origin[i - 2], origin[i - 1], origin[i]]
+                } else {
+                    vec![origin[i - 2], origin[i], origin[i - 1]]
+                }).collect()
+            },
+            | Mode::TriangleFan => {
+                (2..n).flat_map(|i| vec![origin[i - 1], origin[i], origin[0]]).collect()
+            },
+            | _ => origin,
+        }
+    }
 
-        // TODO: Support other integer type.
-        let index_iter = reader.read_indices()
-            .ok_or(VkError::custom("Missing indices property in glTF primitive."))?
-            .into_u32()
-            .map(move |index_element| index_element + start_index);
+    /// Append the indices relative to the first vertex of primitive, which is passed as the vertex offset of draw.
+    ///
+    /// The indices are stored as 16-bit integers if `is_short` is true, where all indices must be less than `SHORT_INDEX_VERTEX_LIMIT`.
+    pub fn extend(&mut self, indices: &[u32], is_short: bool) -> IndicesExtendInfo {
 
-        let result = IndicesExtendInfo {
-            first_index  : self.data_content.len() as _,
-            indices_count: index_iter.size_hint().0 as _,
-        };
+        if is_short {
 
-        self.data_content.extend(index_iter);
-        self.start_index += indices_range as u32;
+            let result = IndicesExtendInfo {
+                first_index  : self.short_indices.len() as _,
+                indices_count: indices.len() as _,
+                index_type   : vk::IndexType::UINT16,
+            };
+            self.short_indices.extend(indices.iter().map(|index_element| *index_element as u16));
+            result
+        } else {
 
-        Ok(result)
+            let result = IndicesExtendInfo {
+                first_index  : self.long_indices.len() as _,
+                indices_count: indices.len() as _,
+                index_type   : vk::IndexType::UINT32,
+            };
+            self.long_indices.extend_from_slice(indices);
+            result
+        }
     }
 
     pub fn buffer_size_estimated(&self) -> Option<vkbytes> {
 
-        if self.start_index > 0 {
-            let indices_size = (self.data_content.len() * ::std::mem::size_of::<vkuint>()) as vkbytes;
+        let indices_size = (self.long_indices.len() * size_of::<u32>() + self.short_indices.len() * size_of::<u16>()) as vkbytes;
+
+        if indices_size > 0 {
             Some(indices_size)
         } else {
             None
         }
     }
 
-    pub fn map_data(&self, memory_ptr: vkptr) {
+    /// The offset of 16-bit indices in index buffer.
+    pub fn short_indices_offset(&self) -> vkbytes {
+        (self.long_indices.len() * size_of::<u32>()) as vkbytes
+    }
+
+    /// Return the raw bytes of the 32-bit indices.
+    pub fn long_bytes(&self) -> &[u8] {
 
         unsafe {
-            (memory_ptr as vkptr<vkuint>).copy_from(self.data_content.as_ptr(), self.data_content.len());
+            ::std::slice::from_raw_parts(self.long_indices.as_ptr() as *const u8, self.long_indices.len() * size_of::<u32>())
+        }
+    }
+
+    /// Return the raw bytes of the 16-bit indices.
+    pub fn short_bytes(&self) -> &[u8] {
+
+        unsafe {
+            ::std::slice::from_raw_parts(self.short_indices.as_ptr() as *const u8, self.short_indices.len() * size_of::<u16>())
         }
     }
 }
@@ -64,32 +137,8 @@ impl Default for IndicesData {
 
     fn default() -> IndicesData {
         IndicesData {
-            start_index: 0,
-            data_content: Vec::new(),
+            long_indices : Vec::new(),
+            short_indices: Vec::new(),
         }
     }
 }
-
-fn get_indices_range(primitive: &gltf::Primitive) -> VkResult<u64> {
-
-    let indices_accessor = primitive.indices()
-        .ok_or(VkError::custom("Failed to get indices property of glTF::Primitive"))?;
-
-    // Get the maximum index of this primitive.
-    let index_max = get_index(indices_accessor.max())?;
-    // Get the minimum index of this primitive.
-    let index_min = get_index(indices_accessor.min())?;
-    // calculate the range of these indices.
-    let indices_range = index_max - index_min + 1;
-
-    Ok(indices_range)
-}
-
-fn get_index(value: Option<gltf::json::Value>) -> VkResult<u64> {
-
-    value
-        .and_then(|v| v.as_array().cloned())
-        .and_then(|v| v.first().cloned())
-        .and_then(|v| v.as_u64())
-        .ok_or(VkError::custom("Invalid or missing min/max property for indices property."))
-}
@@ -1,46 +1,65 @@
 
 use crate::gltf::asset::GltfDocument;
-use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 
-use crate::gltf::meshes::primitive::Primitive;
+use crate::gltf::meshes::primitive::{Primitive, estimated_vertex_count};
 use crate::gltf::meshes::attributes::AttributesData;
-use crate::gltf::meshes::indices::IndicesData;
-
-use crate::command::{VkCmdRecorder, IGraphics};
+use crate::gltf::meshes::indices::{IndicesData, SHORT_INDEX_VERTEX_LIMIT};
+use crate::gltf::meshes::morph::MorphTargetsData;
+use crate::gltf::meshes::optimize::MeshOptimizer;
+use crate::gltf::meshes::generate::AttributeGeneration;
+use crate::gltf::bounds::BoundingBox;
 
 use crate::error::VkResult;
 
+use serde_derive::{Serialize, Deserialize};
+
 // --------------------------------------------------------------------------------------
 /// A wrapper class for mesh level in glTF, containing the render parameters read from glTF file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mesh {
 
     /// a mesh may contain multiple glTF::Primitive.
     primitives: Vec<Primitive>,
+    /// the union of bounding boxes of all primitives in mesh space.
+    bounds: BoundingBox,
 }
 
 impl Mesh {
 
-    pub fn from_doc(doc_mesh: gltf::Mesh, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData) -> VkResult<Mesh> {
+    pub fn from_doc(doc_mesh: gltf::Mesh, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData, morph: &mut MorphTargetsData, optimizer: &mut MeshOptimizer, generation: AttributeGeneration, is_unroll_strips: bool) -> VkResult<Mesh> {
+
+        morph.extend_mesh(&doc_mesh);
+
+        // use 16-bit indices if the indices of each primitive are in the range of u16.
+        let layout_flags = attributes.flags();
+        let max_vertex_count = doc_mesh.primitives()
+            .map(|doc_primitive| estimated_vertex_count(&doc_primitive, source, generation, layout_flags))
+            .max().unwrap_or(0);
+        let is_short_indices = max_vertex_count <= SHORT_INDEX_VERTEX_LIMIT;
 
+        let mesh_index = doc_mesh.index();
         let mesh_iter = doc_mesh.primitives();
         let mut primitives = Vec::with_capacity(mesh_iter.size_hint().0);
 
         for doc_primitive in mesh_iter {
 
-            let primitive = Primitive::from_doc(doc_primitive, source, attributes, indices)?;
+            let primitive = Primitive::from_doc(doc_primitive, mesh_index, source, attributes, indices, morph, optimizer, generation, is_unroll_strips, is_short_indices)?;
             primitives.push(primitive);
         }
 
-        let mesh = Mesh { primitives };
+        let bounds = primitives.iter()
+            .fold(BoundingBox::empty(), |bounds, primitive| bounds.union(primitive.bounds()));
+
+        let mesh = Mesh { primitives, bounds };
         Ok(mesh)
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    pub fn primitives(&self) -> &[Primitive] {
+        &self.primitives
+    }
 
-        for primitive in self.primitives.iter() {
-            primitive.record_command(recorder, model, params);
-        }
+    pub fn bounds(&self) -> &BoundingBox {
+        &self.bounds
     }
 }
 // --------------------------------------------------------------------------------------
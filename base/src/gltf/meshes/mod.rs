@@ -1,9 +1,20 @@
 
-pub use self::asset::{MeshAsset, MeshResource};
-pub use self::attributes::AttributeFlags;
+pub use self::asset::{MeshAsset, MeshResource, TopologyGroup, PrimitiveRange};
+pub use self::attributes::{AttributeFlags, AttributeFormat, VertexLayout};
+pub use self::generate::AttributeGeneration;
+pub use self::mesh::Mesh;
+pub use self::morph::MorphResource;
+pub use self::optimize::{MeshOptimizeFlags, OptimizationReport};
+pub use self::primitive::{Primitive, RenderParams};
+
+pub(crate) use self::attributes::AttributesData;
+pub(crate) use self::indices::SHORT_INDEX_VERTEX_LIMIT;
 
 mod asset;
 mod attributes;
+mod generate;
 mod indices;
 mod mesh;
+mod morph;
+mod optimize;
 mod primitive;
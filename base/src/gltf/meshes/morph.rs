@@ -0,0 +1,377 @@
+
+use ash::vk;
+
+use crate::gltf::asset::{GltfDocument, ReferenceIndex};
+use crate::gltf::material::MATERIAL_SIZE;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+use crate::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+use crate::ci::vma::{VmaAllocationCI, VmaBuffer};
+use crate::ci::VkObjectBuildableCI;
+use crate::context::{VkDevice, BufferUploadInfo};
+use crate::error::{VkResult, VkErrorKind};
+use crate::utils::memory::IntegerAlignable;
+use crate::{vkuint, vkbytes, vkptr, Vec3F, Vec4F};
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+use std::ptr;
+
+/// The index of morph primitive reserved for the primitives without morph targets.
+pub const NO_MORPH_INDEX: vkuint = 0;
+/// The count of storage buffer bindings in morph descriptor set.
+const MORPH_BINDING_COUNT: usize = 3;
+/// The morph index of each draw is pushed to vertex shader after the material data, so that both can be pushed in the same pipeline layout.
+pub const MORPH_INDEX_PUSH_OFFSET: vkuint = MATERIAL_SIZE as vkuint;
+
+// --------------------------------------------------------------------------------------
+/// The morph target displacements of a vertex for one target.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct MorphDelta {
+    position: Vec4F,
+    normal  : Vec4F,
+    tangent : Vec4F,
+}
+
+/// The information to locate the displacements and weights of a primitive in shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct MorphPrimitive {
+    /// the index of first displacement of this primitive in displacement buffer.
+    delta_offset: vkuint,
+    /// the index of first vertex of this primitive in vertex buffer.
+    first_vertex: vkuint,
+    /// the count of displacements of each target, which equals to the vertex count of this primitive.
+    vertex_count: vkuint,
+    target_count: vkuint,
+    /// the index of first weight of the mesh in weight buffer.
+    weight_offset: vkuint,
+}
+
+/// The range of morph weights of a mesh in weight buffer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct WeightRange {
+    offset: usize,
+    count : usize,
+}
+
+/// The morph targets of all primitives, collected when reading glTF meshes.
+#[derive(Serialize, Deserialize)]
+pub struct MorphTargetsData {
+
+    primitives: Vec<MorphPrimitive>,
+    deltas: Vec<MorphDelta>,
+    weights: Vec<f32>,
+    weight_ranges: HashMap<ReferenceIndex, WeightRange>,
+}
+
+impl Default for MorphTargetsData {
+
+    fn default() -> MorphTargetsData {
+        MorphTargetsData {
+            // the first element is the placeholder for primitives without morph targets.
+            primitives: vec![MorphPrimitive::default()],
+            deltas: Vec::new(),
+            weights: Vec::new(),
+            weight_ranges: HashMap::new(),
+        }
+    }
+}
+
+impl MorphTargetsData {
+
+    /// Reserve the morph weights of mesh, which are initialized with the default weights of mesh.
+    pub fn extend_mesh(&mut self, doc_mesh: &gltf::Mesh) {
+
+        let target_count = doc_mesh.primitives()
+            .map(|doc_primitive| doc_primitive.morph_targets().count())
+            .max().unwrap_or(0);
+
+        if target_count == 0 {
+            return
+        }
+
+        let offset = self.weights.len();
+        let default_weights = doc_mesh.weights().unwrap_or(&[]);
+        self.weights.extend((0..target_count).map(|i| default_weights.get(i).cloned().unwrap_or(0.0)));
+        self.weight_ranges.insert(doc_mesh.index(), WeightRange { offset, count: target_count });
+    }
+
+    /// Read the morph targets of primitive, and return its index in morph primitives.
+    ///
+    /// Return `NO_MORPH_INDEX` if the primitive does not contain any morph target.
+    ///
+    /// `origins` is the original vertex of each vertex if the vertices of primitive have been unwelded, so that the displacements follow their vertices.
+    pub fn extend_primitive(&mut self, doc_primitive: &gltf::Primitive, mesh_index: ReferenceIndex, first_vertex: usize, vertex_count: usize, origins: Option<&[u32]>, source: &GltfDocument) -> vkuint {
+
+        let weight_range = match self.weight_ranges.get(&mesh_index) {
+            | Some(range) if doc_primitive.morph_targets().count() > 0 => *range,
+            | _ => return NO_MORPH_INDEX,
+        };
+
+        let reader = doc_primitive.reader(|b| Some(&source.buffers[b.index()]));
+        let delta_offset = self.deltas.len();
+        let mut target_count = 0;
+
+        let origin_count = match origins {
+            | Some(_) => doc_primitive.attributes().map(|(_, accessor)| accessor.count()).max().unwrap_or(0),
+            | None => vertex_count,
+        };
+
+        for (positions, normals, tangents) in reader.read_morph_targets() {
+
+            let mut target_deltas = vec![MorphDelta::default(); origin_count];
+
+            if let Some(positions) = positions {
+                for (delta, position) in target_deltas.iter_mut().zip(positions) {
+                    let mut displacement = Vec3F::from(position);
+                    // the displacements are not affected by the translation of `source.transform`.
+                    if let Some(ref transform) = source.transform {
+                        displacement = transform.mul_direction(displacement);
+                    }
+                    delta.position = Vec4F::from(displacement);
+                }
+            }
+            if let Some(normals) = normals {
+                for (delta, normal) in target_deltas.iter_mut().zip(normals) {
+                    delta.normal = Vec4F::from(Vec3F::from(normal));
+                }
+            }
+            if let Some(tangents) = tangents {
+                for (delta, tangent) in target_deltas.iter_mut().zip(tangents) {
+                    delta.tangent = Vec4F::from(Vec3F::from(tangent));
+                }
+            }
+
+            match origins {
+                | Some(origins) => {
+                    self.deltas.extend(origins.iter().map(|&origin| target_deltas.get(origin as usize).cloned().unwrap_or_default()));
+                },
+                | None => self.deltas.extend(target_deltas),
+            }
+            target_count += 1;
+        }
+
+        let morph_index = self.primitives.len() as vkuint;
+        self.primitives.push(MorphPrimitive {
+            delta_offset : delta_offset as vkuint,
+            first_vertex : first_vertex as vkuint,
+            vertex_count : vertex_count as vkuint,
+            // the weights beyond the target count of mesh are ignored.
+            target_count : target_count.min(weight_range.count) as vkuint,
+            weight_offset: weight_range.offset as vkuint,
+        });
+
+        morph_index
+    }
+
+    /// Upload the displacements and primitive information to device, and allocate a mapped buffer for morph weights,
+    /// which contains a copy of weights for each of `frame_in_flight` frames.
+    pub fn allocate(self, device: &mut VkDevice, frame_in_flight: usize) -> VkResult<MorphResource> {
+
+        let primitives_buffer = upload_storage(device, slice_bytes(&self.primitives))?;
+        let deltas_buffer = upload_storage(device, slice_bytes(&self.deltas))?;
+
+        let frame_count = frame_in_flight.max(1);
+        // the buffer must not be empty even if there is no morph weight.
+        let frame_size = ((self.weights.len().max(1) * ::std::mem::size_of::<f32>()) as vkbytes)
+            .align_to(device.phy.limits.min_storage_buffer_offset_alignment);
+
+        let weights_buffer = {
+
+            let weights_ci = BufferCI::new(frame_size * (frame_count as vkbytes))
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER);
+            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let weights_allocation = device.vma.create_buffer(weights_ci.as_ref(), allocate_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            VmaBuffer::from(weights_allocation)
+        };
+
+        let descriptor_pool = DescriptorPoolCI::new(frame_count as vkuint)
+            .add_descriptor(vk::DescriptorType::STORAGE_BUFFER, (MORPH_BINDING_COUNT * frame_count) as vkuint)
+            .build(device)?;
+
+        // Binding 0 ~ 2: the primitive information, displacements and weights of morph targets.
+        let set_layout = (0..MORPH_BINDING_COUNT).fold(DescriptorSetLayoutCI::new(), |layout_ci, binding| {
+            layout_ci.add_binding(vk::DescriptorSetLayoutBinding {
+                binding: binding as vkuint,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                p_immutable_samplers: ptr::null(),
+            })
+        }).build(device)?;
+
+        // each frame reads its own copy of weights, while the primitive information and displacements are shared.
+        let descriptor_sets = (0..frame_count)
+            .fold(DescriptorSetAI::new(descriptor_pool), |set_ai, _| set_ai.add_set_layout(set_layout))
+            .build(device)?;
+
+        let buffer_writes: Vec<DescriptorBufferSetWI> = descriptor_sets.iter().enumerate()
+            .flat_map(|(frame_index, descriptor_set)| {
+
+                let buffer_infos = [
+                    (primitives_buffer.handle, 0, vk::WHOLE_SIZE),
+                    (deltas_buffer.handle, 0, vk::WHOLE_SIZE),
+                    (weights_buffer.handle, frame_size * (frame_index as vkbytes), frame_size),
+                ];
+
+                buffer_infos.iter().enumerate().map(|(binding, &(buffer, offset, range))| {
+                    DescriptorBufferSetWI::new(*descriptor_set, binding as vkuint, vk::DescriptorType::STORAGE_BUFFER)
+                        .add_buffer(vk::DescriptorBufferInfo { buffer, offset, range })
+                }).collect::<Vec<_>>()
+            }).collect();
+
+        buffer_writes.iter()
+            .fold(DescriptorSetsUpdateCI::new(), |update_ci, write| update_ci.add_write(write))
+            .update(device);
+
+        let mut result = MorphResource {
+            weights: self.weights,
+            weight_ranges: self.weight_ranges,
+            dirty_frames: vec![true; frame_count],
+            frame_size,
+            primitives_buffer, deltas_buffer, weights_buffer,
+            descriptor_pool, set_layout, descriptor_sets,
+        };
+
+        // write the default weights to every frame.
+        for frame_index in 0..frame_count {
+            result.update(frame_index);
+        }
+
+        Ok(result)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+pub struct MorphResource {
+
+    weights: Vec<f32>,
+    weight_ranges: HashMap<ReferenceIndex, WeightRange>,
+    /// whether the weights have changed since they were last written to the buffer of each frame.
+    dirty_frames: Vec<bool>,
+    /// the byte size of weights in each frame(including the padding for alignment).
+    frame_size: vkbytes,
+
+    primitives_buffer: VmaBuffer,
+    deltas_buffer: VmaBuffer,
+    /// a persistently mapped storage buffer, which contains a copy of weights for each frame in flight.
+    weights_buffer: VmaBuffer,
+
+    descriptor_pool: vk::DescriptorPool,
+    set_layout: vk::DescriptorSetLayout,
+    /// the descriptor set of each frame in flight.
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+impl MorphResource {
+
+    pub fn weights(&self, mesh_index: ReferenceIndex) -> Option<&[f32]> {
+        self.weight_ranges.get(&mesh_index)
+            .map(|range| &self.weights[range.offset..(range.offset + range.count)])
+    }
+
+    /// Overwrite the morph weights of mesh. The extra weights are ignored, and the missing weights are unchanged.
+    pub fn set_weights(&mut self, mesh_index: ReferenceIndex, weights: &[f32]) {
+
+        if let Some(range) = self.weight_ranges.get(&mesh_index) {
+
+            let count = range.count.min(weights.len());
+            self.weights[range.offset..(range.offset + count)].copy_from_slice(&weights[..count]);
+
+            for is_dirty in self.dirty_frames.iter_mut() {
+                *is_dirty = true;
+            }
+        }
+    }
+
+    pub fn set_layout(&self) -> vk::DescriptorSetLayout {
+        self.set_layout
+    }
+
+    /// The push constant range of the morph index of each draw, which should be included in the pipeline layout if `ModelRenderParams::morph_set` is specified.
+    pub fn push_constant_range(&self) -> vk::PushConstantRange {
+
+        vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            offset: MORPH_INDEX_PUSH_OFFSET,
+            size  : ::std::mem::size_of::<vkuint>() as vkuint,
+        }
+    }
+
+    /// Return the descriptor set reading the weights written for `frame_index`.
+    ///
+    /// `frame_index` wraps around the frame count specified in allocation.
+    pub fn descriptor_set(&self, frame_index: usize) -> vk::DescriptorSet {
+        self.descriptor_sets[frame_index % self.descriptor_sets.len()]
+    }
+
+    /// Write the weights to the buffer of `frame_index` if they have changed since last written to this frame.
+    ///
+    /// This method must be called when GPU is not reading the buffer of `frame_index`.
+    pub fn update(&mut self, frame_index: usize) {
+
+        let frame_slot = frame_index % self.dirty_frames.len();
+
+        if self.dirty_frames[frame_slot] {
+
+            unsafe {
+                let data_ptr = (self.weights_buffer.info.get_mapped_data() as vkptr<u8>).offset((self.frame_size as isize) * (frame_slot as isize)) as vkptr<f32>;
+                data_ptr.copy_from_nonoverlapping(self.weights.as_ptr(), self.weights.len());
+            }
+            self.dirty_frames[frame_slot] = false;
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.set_layout);
+        // descriptor sets are freed with the pool.
+        device.discard(self.descriptor_pool);
+
+        device.vma_discard(self.primitives_buffer)?;
+        device.vma_discard(self.deltas_buffer)?;
+        device.vma_discard(self.weights_buffer)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+fn slice_bytes<T>(data: &[T]) -> &[u8] {
+
+    unsafe {
+        ::std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * ::std::mem::size_of::<T>())
+    }
+}
+
+/// Create a device local storage buffer, and upload `data` to it.
+fn upload_storage(device: &mut VkDevice, data: &[u8]) -> VkResult<VmaBuffer> {
+
+    // the buffer must not be empty even if there is no data.
+    let buffer_size = (data.len() as vkbytes).max(::std::mem::size_of::<Vec4F>() as vkbytes);
+
+    let buffer_ci = BufferCI::new(buffer_size)
+        .usage(vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST);
+    let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+    let buffer_allocation = device.vma.create_buffer(buffer_ci.as_ref(), allocate_ci.as_ref())
+        .map_err(VkErrorKind::Vma)?;
+    let buffer = VmaBuffer::from(buffer_allocation);
+
+    if !data.is_empty() {
+        device.upload_buffer(data, BufferUploadInfo {
+            buffer: buffer.handle,
+            offset: 0,
+            dst_access: vk::AccessFlags::SHADER_READ,
+        })?;
+    }
+
+    Ok(buffer)
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,447 @@
+
+use crate::gltf::meshes::attributes::AttributesData;
+use crate::Vec3F;
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::HashMap;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+/// The size of LRU cache assumed by vertex cache optimization.
+const OPTIMIZE_CACHE_SIZE: usize = 32;
+/// The size of FIFO cache used to measure ACMR, which is close to the post-transform cache of common hardware.
+const ANALYZE_CACHE_SIZE: usize = 16;
+
+// --------------------------------------------------------------------------------------
+/// Indicate which optimizations are applied to the triangle list primitives on import.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MeshOptimizeFlags(u32);
+
+impl MeshOptimizeFlags {
+    pub const NONE        : MeshOptimizeFlags = MeshOptimizeFlags(0b0);
+    /// Merge the vertices whose data are identical in vertex buffer.
+    pub const DEDUPLICATE : MeshOptimizeFlags = MeshOptimizeFlags(0b1);
+    /// Reorder the triangles to reuse the transformed vertices in post-transform cache(Tom Forsyth's algorithm).
+    pub const VERTEX_CACHE: MeshOptimizeFlags = MeshOptimizeFlags(0b10);
+    /// Reorder the clusters of triangles so that the outer triangles are drawn first, which reduces overdraw.
+    pub const OVERDRAW    : MeshOptimizeFlags = MeshOptimizeFlags(0b100);
+    /// Reorder the vertices in the order they are referenced by indices to improve memory locality.
+    pub const VERTEX_FETCH: MeshOptimizeFlags = MeshOptimizeFlags(0b1000);
+
+    pub const ALL: MeshOptimizeFlags = MeshOptimizeFlags(0b1111);
+
+    pub fn contains(&self, other: MeshOptimizeFlags) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitAnd for MeshOptimizeFlags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        MeshOptimizeFlags(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for MeshOptimizeFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        MeshOptimizeFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for MeshOptimizeFlags {
+
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// The statistics of mesh data before and after the import optimizations.
+///
+/// ACMR(average cache miss ratio) is the count of vertex shader invocations per triangle, measured with a FIFO cache of 16 vertices.
+/// It only counts the triangle list primitives, and ranges from about 0.5(optimal) to 3.0(no reuse).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct OptimizationReport {
+
+    pub triangle_count: usize,
+    pub cache_misses_before: usize,
+    pub cache_misses_after : usize,
+
+    pub vertex_bytes_before: usize,
+    pub vertex_bytes_after : usize,
+    /// the size of index buffer if all indices were stored as 32-bit integers.
+    pub index_bytes_before: usize,
+    pub index_bytes_after : usize,
+}
+
+impl OptimizationReport {
+
+    pub fn acmr_before(&self) -> f32 {
+        ratio(self.cache_misses_before, self.triangle_count)
+    }
+
+    pub fn acmr_after(&self) -> f32 {
+        ratio(self.cache_misses_after, self.triangle_count)
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f32 {
+    if total == 0 { 0.0 } else { count as f32 / total as f32 }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Apply the import optimizations to the primitives of meshes, and collect the report.
+#[derive(Serialize, Deserialize)]
+pub struct MeshOptimizer {
+
+    flags: MeshOptimizeFlags,
+    report: OptimizationReport,
+}
+
+impl MeshOptimizer {
+
+    pub fn new(flags: MeshOptimizeFlags) -> MeshOptimizer {
+        MeshOptimizer { flags, report: Default::default() }
+    }
+
+    pub fn flags(&self) -> MeshOptimizeFlags {
+        self.flags
+    }
+
+    pub fn report(&self) -> &OptimizationReport {
+        &self.report
+    }
+
+    /// Record the size of a primitive that is not optimized.
+    pub fn record_vertices(&mut self, vertex_bytes: usize) {
+        self.report.vertex_bytes_before += vertex_bytes;
+        self.report.vertex_bytes_after  += vertex_bytes;
+    }
+
+    /// Record the size of index data written to index buffer.
+    pub fn record_indices(&mut self, index_count: usize, index_bytes: usize) {
+        self.report.index_bytes_before += index_count * 4;
+        self.report.index_bytes_after  += index_bytes;
+    }
+
+    /// Optimize a triangle list primitive, whose vertices are the last `vertex_count` vertices in `attributes` starting from `first_vertex`.
+    ///
+    /// `indices` are relative to `first_vertex`, and `positions` are used to sort the triangles for overdraw.
+    /// The vertices are only reordered or merged if `is_vertex_fixed` is false(e.g. the morph targets refer to vertices by their order).
+    /// Return the count of vertices after optimization.
+    pub fn optimize_triangles(&mut self, attributes: &mut AttributesData, first_vertex: usize, vertex_count: usize, indices: &mut Vec<u32>, positions: &[Vec3F], is_vertex_fixed: bool) -> usize {
+
+        let vertex_size = attributes.vertex_size();
+        self.report.vertex_bytes_before += vertex_count * vertex_size;
+        self.report.triangle_count += indices.len() / 3;
+        self.report.cache_misses_before += count_cache_misses(indices, vertex_count);
+
+        let mut vertex_count = vertex_count;
+        let mut positions = positions.to_vec();
+
+        if self.flags.contains(MeshOptimizeFlags::DEDUPLICATE) && !is_vertex_fixed {
+
+            let (remap, unique_count) = deduplicate_vertices(attributes, first_vertex, vertex_count);
+            apply_vertex_remap(attributes, first_vertex, vertex_count, indices, &mut positions, &remap, unique_count);
+            vertex_count = unique_count;
+        }
+
+        if self.flags.contains(MeshOptimizeFlags::VERTEX_CACHE) {
+            *indices = optimize_vertex_cache(indices, vertex_count);
+        }
+
+        if self.flags.contains(MeshOptimizeFlags::OVERDRAW) && !positions.is_empty() {
+            *indices = optimize_overdraw(indices, &positions, vertex_count);
+        }
+
+        if self.flags.contains(MeshOptimizeFlags::VERTEX_FETCH) && !is_vertex_fixed {
+
+            let (remap, used_count) = fetch_order_remap(indices, vertex_count);
+            apply_vertex_remap(attributes, first_vertex, vertex_count, indices, &mut positions, &remap, used_count);
+            vertex_count = used_count;
+        }
+
+        self.report.vertex_bytes_after += vertex_count * vertex_size;
+        self.report.cache_misses_after += count_cache_misses(indices, vertex_count);
+
+        vertex_count
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Move vertex `i` to `remap[i]`, and update the indices and positions to match the new order.
+///
+/// The vertices remapped beyond `new_count` are dropped.
+fn apply_vertex_remap(attributes: &mut AttributesData, first_vertex: usize, vertex_count: usize, indices: &mut Vec<u32>, positions: &mut Vec<Vec3F>, remap: &[u32], new_count: usize) {
+
+    attributes.remap_vertices(first_vertex, vertex_count, remap, new_count);
+
+    for index in indices.iter_mut() {
+        *index = remap[*index as usize];
+    }
+
+    if !positions.is_empty() {
+        let mut new_positions = vec![Vec3F::zero(); new_count];
+        for (position, new_index) in positions.iter().zip(remap.iter()) {
+            if let Some(slot) = new_positions.get_mut(*new_index as usize) {
+                *slot = *position;
+            }
+        }
+        *positions = new_positions;
+    }
+}
+
+/// Map each vertex to the first vertex with identical data. Return the remap table and the count of unique vertices.
+fn deduplicate_vertices(attributes: &AttributesData, first_vertex: usize, vertex_count: usize) -> (Vec<u32>, usize) {
+
+    let mut unique_vertices: HashMap<Vec<u8>, u32> = HashMap::with_capacity(vertex_count);
+    let mut remap = Vec::with_capacity(vertex_count);
+
+    for vertex_index in 0..vertex_count {
+        let key = attributes.vertex_bytes(first_vertex + vertex_index);
+        let next_index = unique_vertices.len() as u32;
+        remap.push(*unique_vertices.entry(key).or_insert(next_index));
+    }
+
+    (remap, unique_vertices.len())
+}
+
+/// Map the vertices to the order of their first reference in indices, where the unreferenced vertices are dropped.
+///
+/// Return the remap table and the count of referenced vertices.
+fn fetch_order_remap(indices: &[u32], vertex_count: usize) -> (Vec<u32>, usize) {
+
+    const UNUSED: u32 = ::std::u32::MAX;
+
+    let mut remap = vec![UNUSED; vertex_count];
+    let mut next_index = 0;
+
+    for index in indices.iter() {
+        let slot = &mut remap[*index as usize];
+        if *slot == UNUSED {
+            *slot = next_index;
+            next_index += 1;
+        }
+    }
+
+    // the unreferenced vertices keep `UNUSED`, and are dropped by the remap.
+    (remap, next_index as usize)
+}
+
+/// Count the vertex shader invocations of drawing `indices` with a FIFO post-transform cache.
+fn count_cache_misses(indices: &[u32], vertex_count: usize) -> usize {
+
+    let mut cache_timestamps = vec![0_usize; vertex_count];
+    let mut timestamp = ANALYZE_CACHE_SIZE + 1;
+    let mut misses = 0;
+
+    for index in indices.iter() {
+        let vertex_timestamp = &mut cache_timestamps[*index as usize];
+        if timestamp - *vertex_timestamp > ANALYZE_CACHE_SIZE {
+            *vertex_timestamp = timestamp;
+            timestamp += 1;
+            misses += 1;
+        }
+    }
+
+    misses
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Reorder the triangles with Tom Forsyth's "Linear-Speed Vertex Cache Optimisation".
+fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+
+    let triangle_count = indices.len() / 3;
+
+    // the triangles referencing each vertex, which are removed once they are emitted.
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    for triangle in 0..triangle_count {
+        for corner in 0..3 {
+            vertex_triangles[indices[triangle * 3 + corner] as usize].push(triangle);
+        }
+    }
+
+    let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = (0..vertex_count)
+        .map(|v| vertex_score(None, vertex_triangles[v].len()))
+        .collect();
+    let triangle_score = |triangle: usize, vertex_scores: &[f32]| -> f32 {
+        (0..3).map(|corner| vertex_scores[indices[triangle * 3 + corner] as usize]).sum()
+    };
+    let mut triangle_scores: Vec<f32> = (0..triangle_count)
+        .map(|triangle| triangle_score(triangle, &vertex_scores))
+        .collect();
+
+    let mut is_emitted = vec![false; triangle_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(OPTIMIZE_CACHE_SIZE + 3);
+    let mut result = Vec::with_capacity(triangle_count * 3);
+    // the position to search the next triangle when no triangle in cache is available.
+    let mut scan_cursor = 0;
+    let mut best_triangle = None;
+
+    for _ in 0..triangle_count {
+
+        let triangle = match best_triangle {
+            | Some(triangle) => triangle,
+            | None => {
+                while is_emitted[scan_cursor] { scan_cursor += 1; }
+                scan_cursor
+            },
+        };
+
+        is_emitted[triangle] = true;
+        let corners = [indices[triangle * 3], indices[triangle * 3 + 1], indices[triangle * 3 + 2]];
+        result.extend_from_slice(&corners);
+
+        // move the vertices of emitted triangle to the front of LRU cache.
+        for vertex in corners.iter().cloned() {
+            vertex_triangles[vertex as usize].retain(|t| *t != triangle);
+            cache.retain(|v| *v != vertex);
+        }
+        let evicted: Vec<u32> = {
+            let mut new_cache: Vec<u32> = Vec::with_capacity(cache.len() + 3);
+            for vertex in corners.iter().chain(cache.iter()) {
+                if !new_cache.contains(vertex) {
+                    new_cache.push(*vertex);
+                }
+            }
+            let evicted = if new_cache.len() > OPTIMIZE_CACHE_SIZE { new_cache.split_off(OPTIMIZE_CACHE_SIZE) } else { Vec::new() };
+            cache = new_cache;
+            evicted
+        };
+
+        // update the scores of affected vertices and their triangles.
+        for vertex in evicted.iter() {
+            cache_positions[*vertex as usize] = None;
+        }
+        for (position, vertex) in cache.iter().enumerate() {
+            cache_positions[*vertex as usize] = Some(position);
+        }
+
+        best_triangle = None;
+        let mut best_score = -1.0;
+
+        for vertex in cache.iter().chain(evicted.iter()).cloned() {
+            let vertex = vertex as usize;
+            vertex_scores[vertex] = vertex_score(cache_positions[vertex], vertex_triangles[vertex].len());
+        }
+        for vertex in cache.iter().chain(evicted.iter()).cloned() {
+            for adjacent in vertex_triangles[vertex as usize].iter().cloned() {
+                triangle_scores[adjacent] = triangle_score(adjacent, &vertex_scores);
+                if triangle_scores[adjacent] > best_score {
+                    best_score = triangle_scores[adjacent];
+                    best_triangle = Some(adjacent);
+                }
+            }
+        }
+    }
+
+    // keep the trailing indices which do not form a triangle.
+    result.extend_from_slice(&indices[(triangle_count * 3)..]);
+    result
+}
+
+/// The score of vertex in Forsyth's algorithm, where `cache_position` is the position in LRU cache.
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+
+    if remaining_triangles == 0 {
+        return -1.0
+    }
+
+    let cache_score = match cache_position {
+        // the vertices of the last triangle get a fixed score, so that the next triangle does not just reuse them.
+        | Some(position) if position < 3 => 0.75,
+        | Some(position) => {
+            let scale = 1.0 / (OPTIMIZE_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(1.5)
+        },
+        | None => 0.0,
+    };
+
+    // the vertices with fewer remaining triangles are preferred to finish them off.
+    let valence_score = 2.0 * (remaining_triangles as f32).powf(-0.5);
+    cache_score + valence_score
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Split the triangles into clusters at the cache flushes, and draw the clusters facing outward first.
+///
+/// This is a simplified version of "Fast Triangle Reordering for Vertex Locality and Reduced Overdraw"(Sander et al.),
+/// so the cache efficiency inside each cluster is kept.
+fn optimize_overdraw(indices: &[u32], positions: &[Vec3F], vertex_count: usize) -> Vec<u32> {
+
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return indices.to_vec()
+    }
+
+    let position = |index: u32| positions.get(index as usize).cloned().unwrap_or(Vec3F::zero());
+
+    // a new cluster starts at the triangle whose vertices all miss the cache.
+    let mut cluster_starts = Vec::new();
+    let mut cache_timestamps = vec![0_usize; vertex_count];
+    let mut timestamp = ANALYZE_CACHE_SIZE + 1;
+
+    for triangle in 0..triangle_count {
+        let mut misses = 0;
+        for corner in 0..3 {
+            let vertex_timestamp = &mut cache_timestamps[indices[triangle * 3 + corner] as usize];
+            if timestamp - *vertex_timestamp > ANALYZE_CACHE_SIZE {
+                *vertex_timestamp = timestamp;
+                timestamp += 1;
+                misses += 1;
+            }
+        }
+        if misses == 3 || triangle == 0 {
+            cluster_starts.push(triangle);
+        }
+    }
+
+    let mesh_center = positions.iter().fold(Vec3F::zero(), |sum, p| sum + *p) / (positions.len().max(1) as f32);
+
+    let mut clusters: Vec<(f32, usize, usize)> = cluster_starts.iter().enumerate().map(|(i, start)| {
+
+        let end = cluster_starts.get(i + 1).cloned().unwrap_or(triangle_count);
+        let mut normal = Vec3F::zero();
+        let mut center = Vec3F::zero();
+        let mut area = 0.0;
+
+        for triangle in *start..end {
+            let (p0, p1, p2) = (position(indices[triangle * 3]), position(indices[triangle * 3 + 1]), position(indices[triangle * 3 + 2]));
+            let cross = Vec3F::cross(p1 - p0, p2 - p0);
+            let triangle_area = cross.magnitude();
+            normal += cross;
+            center += (p0 + p1 + p2) * (triangle_area / 3.0);
+            area += triangle_area;
+        }
+
+        let center = if area > 0.0 { center / area } else { center };
+        let normal_length = normal.magnitude();
+        let normal = if normal_length > 0.0 { normal / normal_length } else { normal };
+
+        // the clusters facing away from the mesh center are likely to occlude the others.
+        (Vec3F::dot(center - mesh_center, normal), *start, end)
+    }).collect();
+
+    clusters.sort_by(|c1, c2| c2.0.partial_cmp(&c1.0).unwrap_or(::std::cmp::Ordering::Equal));
+
+    let mut result = Vec::with_capacity(indices.len());
+    for (_, start, end) in clusters {
+        result.extend_from_slice(&indices[(start * 3)..(end * 3)]);
+    }
+    result.extend_from_slice(&indices[(triangle_count * 3)..]);
+    result
+}
+// --------------------------------------------------------------------------------------
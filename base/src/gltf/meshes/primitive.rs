@@ -1,105 +1,305 @@
 
+use ash::vk;
+
 use crate::gltf::asset::{GltfDocument, ReferenceIndex};
 use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 
-use crate::gltf::meshes::attributes::AttributesData;
+use crate::gltf::meshes::attributes::{AttributesData, AttributeFlags};
 use crate::gltf::meshes::indices::IndicesData;
+use crate::gltf::meshes::morph::{MorphTargetsData, NO_MORPH_INDEX, MORPH_INDEX_PUSH_OFFSET};
+use crate::gltf::meshes::optimize::{MeshOptimizer, MeshOptimizeFlags};
+use crate::gltf::meshes::generate::{AttributeGeneration, GeneratedVertices};
+use crate::gltf::bounds::BoundingBox;
 
 use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 
-use crate::{VkResult, VkError};
-use crate::vkuint;
+use crate::VkResult;
+use crate::{vkuint, vksint, Vec3F};
+
+use serde_derive::{Serialize, Deserialize};
 
 // --------------------------------------------------------------------------------------
 /// A wrapper class for primitive level in glTF, containing the render parameters read from glTF file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Primitive {
 
     /// the draw parameters used in rendering.
     params: RenderParams,
+    /// the topology to assemble the vertices of this primitive.
+    #[serde(with = "crate::gltf::cache::vk_serde::primitive_topology")]
+    topology: vk::PrimitiveTopology,
     /// the json index of material of this primitive.
     material: Option<ReferenceIndex>,
+    /// the bounding box of this primitive in mesh space.
+    bounds: BoundingBox,
+    /// the index of morph targets of this primitive, which is passed to shader as push constant.
+    morph_index: vkuint,
 }
 
 impl Primitive {
 
-    pub fn from_doc(doc_primitive: gltf::Primitive, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData) -> VkResult<Primitive> {
+    /// Read the primitive. If `is_unroll_strips` is true, strips and fans are converted to lists.
+    ///
+    /// The indices are stored as 16-bit integers if `is_short_indices` is true, and the triangle lists are optimized by `optimizer`.
+    /// The missing attributes in `generation` are generated for triangles, where the strips and fans are always converted to lists.
+    pub fn from_doc(doc_primitive: gltf::Primitive, mesh_index: ReferenceIndex, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData, morph: &mut MorphTargetsData, optimizer: &mut MeshOptimizer, generation: AttributeGeneration, is_unroll_strips: bool, is_short_indices: bool) -> VkResult<Primitive> {
 
-        if doc_primitive.mode() != gltf::mesh::Mode::Triangles {
-            // Currently only support triangle topology.
-            return Err(VkError::unimplemented(format!("{} render mode.", translate_draw_mode(doc_primitive.mode()))))
-        }
+        let generated_attributes = if is_triangle_mode(doc_primitive.mode()) {
+            generation.required_by(&doc_primitive, source, attributes.flags())
+        } else {
+            AttributeFlags::NONE
+        };
+        let is_generate = generated_attributes != AttributeFlags::NONE;
+
+        let (topology, is_convert_required) = translate_draw_mode(doc_primitive.mode(), is_unroll_strips || is_generate);
+
+        let is_triangle_list = topology == vk::PrimitiveTopology::TRIANGLE_LIST;
+        // the optimizations require indices, so the indices are generated for non-indexed triangles.
+        let is_index_required = is_convert_required || is_generate || (is_triangle_list && !optimizer.flags().is_empty());
+
+        // read indices data of glTF::Primitive, which are relative to the first vertex of this primitive.
+        let read_local_indices = || {
+            let vertex_count = doc_primitive.attributes()
+                .map(|(_, accessor)| accessor.count())
+                .max().unwrap_or(0);
+            let local_indices = IndicesData::read_local(&doc_primitive, source, vertex_count);
+            if is_convert_required {
+                // generate the indices of list topology from the strip, fan or loop.
+                IndicesData::unroll(doc_primitive.mode(), local_indices)
+            } else {
+                local_indices
+            }
+        };
 
         // read vertices attribute data of glTF::Primitive.
-        let attribute_info = attributes.data_content.extend(&doc_primitive, source);
-
-        let render_params = match doc_primitive.indices() {
-            | None => {
-                // set the draw method of this primitive to drawArray.
-                RenderParams::DrawArray {
-                    first_vertex: attribute_info.first_vertex as _,
-                    vertex_count: attribute_info.vertex_count as _,
-                }
-            },
-            | Some(_) => {
-                // read indices data of glTF::Primitive.
-                let indices_info = indices.extend(&doc_primitive, source)?;
-                // set the draw method of this primitive to drawIndexed.
-                RenderParams::DrawIndex {
-                    first_index: indices_info.first_index,
-                    index_count: indices_info.indices_count,
-                }
-            },
+        // the vertices are unwelded where their generated attributes differ, and `vertex_origins` keeps their original vertices.
+        let (mut attribute_info, generated_indices, vertex_origins) = if is_generate {
+            let generated = GeneratedVertices::generate(&doc_primitive, source, &read_local_indices(), generated_attributes);
+            let attribute_info = attributes.extend_generated(&doc_primitive, source, &generated);
+            (attribute_info, Some(generated.indices), Some(generated.origins))
+        } else {
+            (attributes.extend(&doc_primitive, source), None, None)
         };
 
+        let render_params = if doc_primitive.indices().is_none() && !is_index_required {
+
+            optimizer.record_vertices(attribute_info.vertex_count * attributes.vertex_size());
+            // set the draw method of this primitive to drawArray.
+            RenderParams::DrawArray {
+                first_vertex: attribute_info.first_vertex as _,
+                vertex_count: attribute_info.vertex_count as _,
+            }
+        } else {
+
+            let mut local_indices = generated_indices.unwrap_or_else(read_local_indices);
+
+            if is_triangle_list {
+
+                let positions = if optimizer.flags().contains(MeshOptimizeFlags::OVERDRAW) {
+                    let reader = doc_primitive.reader(|b| Some(&source.buffers[b.index()]));
+                    let origin_positions: Vec<Vec3F> = reader.read_positions()
+                        .map(|pos_iter| pos_iter.map(Vec3F::from).collect())
+                        .unwrap_or(Vec::new());
+                    match vertex_origins {
+                        | Some(ref origins) => origins.iter()
+                            .map(|&origin| origin_positions.get(origin as usize).cloned().unwrap_or(Vec3F::zero()))
+                            .collect(),
+                        | None => origin_positions,
+                    }
+                } else {
+                    Vec::new()
+                };
+
+                // the morph targets refer to the vertices by their original order.
+                let is_vertex_fixed = doc_primitive.morph_targets().count() > 0;
+                attribute_info.vertex_count = optimizer.optimize_triangles(attributes, attribute_info.first_vertex, attribute_info.vertex_count, &mut local_indices, &positions, is_vertex_fixed);
+            } else {
+                optimizer.record_vertices(attribute_info.vertex_count * attributes.vertex_size());
+            }
+
+            let indices_info = indices.extend(&local_indices, is_short_indices);
+            let index_size = if indices_info.index_type == vk::IndexType::UINT16 { 2 } else { 4 };
+            optimizer.record_indices(local_indices.len(), local_indices.len() * index_size);
+
+            // set the draw method of this primitive to drawIndexed.
+            RenderParams::DrawIndex {
+                first_index  : indices_info.first_index,
+                index_count  : indices_info.indices_count,
+                vertex_offset: attribute_info.first_vertex as _,
+                index_type   : indices_info.index_type,
+            }
+        };
+
+        // the min and max property of POSITION accessor may be omitted, so the bounding box is calculated from the positions.
+        // the primitives without POSITION attribute get an empty bounding box.
+        let reader = doc_primitive.reader(|b| Some(&source.buffers[b.index()]));
+        let mut bounds = reader.read_positions()
+            .map(|positions| BoundingBox::from_points(positions.map(Vec3F::from)))
+            .unwrap_or_else(BoundingBox::empty);
+        if let Some(ref transform) = source.transform {
+            bounds = bounds.transform(transform);
+        }
+
+        let morph_index = morph.extend_primitive(&doc_primitive, mesh_index, attribute_info.first_vertex, attribute_info.vertex_count, vertex_origins.as_ref().map(|origins| origins.as_slice()), source);
+
         let result = Primitive {
             params: render_params,
+            topology,
             material: doc_primitive.material().index(),
+            bounds, morph_index,
         };
         Ok(result)
     }
 
+    pub fn material(&self) -> &Option<ReferenceIndex> {
+        &self.material
+    }
+
+    pub fn center(&self) -> Vec3F {
+        self.bounds.center()
+    }
+
+    /// The bounding box of this primitive in mesh space, without the displacement of morph targets.
+    pub fn bounds(&self) -> &BoundingBox {
+        &self.bounds
+    }
+
+    /// Whether the vertices of this primitive are displaced by morph targets in shader.
+    pub fn is_morphed(&self) -> bool {
+        self.morph_index != NO_MORPH_INDEX
+    }
+
+    pub fn topology(&self) -> vk::PrimitiveTopology {
+        self.topology
+    }
+
+    pub fn render_params(&self) -> &RenderParams {
+        &self.params
+    }
+
     pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
 
-        if let Some(material_stage) = params.material_stage {
+        self.bind_material(recorder, model, params);
 
-            let material_data = model.materials.get_material_serialized(&self.material);
-            recorder.push_constants(params.pipeline_layout, material_stage, 0, material_data);
+        if params.morph_set.is_some() {
+            // the primitives without morph targets also push their index, since the push constant is kept between draws.
+            recorder.push_constants(params.pipeline_layout, vk::ShaderStageFlags::VERTEX, MORPH_INDEX_PUSH_OFFSET, &self.morph_index.to_ne_bytes());
         }
 
         match self.params {
             | RenderParams::DrawArray { vertex_count, first_vertex } => {
                 recorder.draw(vertex_count, 1, first_vertex, 0);
             },
-            | RenderParams::DrawIndex {  index_count,  first_index } => {
-                recorder.draw_indexed(index_count, 1, first_index, 0, 0);
+            | RenderParams::DrawIndex { index_count, first_index, vertex_offset, .. } => {
+                recorder.draw_indexed(index_count, 1, first_index, vertex_offset, 0);
             },
         }
     }
+
+    /// Bind the pipeline, push constants and descriptor set of the material of this primitive, as specified by `params`.
+    pub fn bind_material(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+
+        if let Some(ref material_pipelines) = params.material_pipelines {
+
+            let material_mode = model.materials.get_material_mode(&self.material);
+            recorder.bind_pipeline(material_pipelines.select(material_mode));
+        }
+
+        if let Some(material_stage) = params.material_stage {
+
+            let material_data = model.materials.get_material_serialized(&self.material);
+            recorder.push_constants(params.pipeline_layout, material_stage, 0, material_data);
+        }
+
+        if let Some(material_set) = params.material_set {
+
+            let descriptor_set = model.materials.get_descriptor_set(&self.material);
+            recorder.bind_descriptor_sets(params.pipeline_layout, material_set, &[descriptor_set], &[]);
+        }
+    }
+
+    /// the index of morph targets of this primitive in morph descriptor set.
+    pub fn morph_index(&self) -> vkuint {
+        self.morph_index
+    }
 }
 // --------------------------------------------------------------------------------------
 
 // --------------------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+/// The range of vertices or indices to draw a primitive.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum RenderParams {
     DrawArray { vertex_count: vkuint, first_vertex: vkuint },
-    DrawIndex {  index_count: vkuint,  first_index: vkuint },
+    /// `first_index` is the position in the indices of `index_type`, and `vertex_offset` is the first vertex of primitive.
+    DrawIndex {  index_count: vkuint,  first_index: vkuint, vertex_offset: vksint,
+        #[serde(with = "crate::gltf::cache::vk_serde::index_type")]
+        index_type: vk::IndexType,
+    },
+}
+
+impl RenderParams {
+
+    /// The type of indices to bind before drawing, or None for non-indexed draw.
+    pub fn index_type(&self) -> Option<vk::IndexType> {
+        match self {
+            | RenderParams::DrawArray { .. } => None,
+            | RenderParams::DrawIndex { index_type, .. } => Some(*index_type),
+        }
+    }
 }
 // --------------------------------------------------------------------------------------
 
 // --------------------------------------------------------------------------------------
-fn translate_draw_mode(from: gltf::mesh::Mode) -> &'static str {
+fn is_triangle_mode(mode: gltf::mesh::Mode) -> bool {
+
+    use gltf::mesh::Mode::*;
+
+    match mode {
+        | Triangles | TriangleStrip | TriangleFan => true,
+        | Points | Lines | LineLoop | LineStrip => false,
+    }
+}
+
+/// Return the max vertex count that primitive may have after import, to decide the index type of its mesh.
+///
+/// The vertex count of the primitives with generated attributes is bounded by their triangle corners, since their vertices may be unwelded.
+pub fn estimated_vertex_count(doc_primitive: &gltf::Primitive, source: &GltfDocument, generation: AttributeGeneration, layout_flags: AttributeFlags) -> usize {
+
+    let vertex_count = doc_primitive.attributes()
+        .map(|(_, accessor)| accessor.count())
+        .max().unwrap_or(0);
+
+    if is_triangle_mode(doc_primitive.mode()) && generation.required_by(doc_primitive, source, layout_flags) != AttributeFlags::NONE {
+        let element_count = doc_primitive.indices().map(|accessor| accessor.count()).unwrap_or(vertex_count);
+        // strips and fans produce at most 3 corners for each element.
+        let corner_count = match doc_primitive.mode() {
+            | gltf::mesh::Mode::Triangles => element_count,
+            | _ => element_count * 3,
+        };
+        vertex_count.max(corner_count)
+    } else {
+        vertex_count
+    }
+}
+
+/// Return the Vulkan topology of glTF primitive mode, and whether the vertices need to be converted to list topology.
+///
+/// LineLoop has no equivalent topology in Vulkan, so it is always converted to LineList.
+fn translate_draw_mode(from: gltf::mesh::Mode, is_unroll_strips: bool) -> (vk::PrimitiveTopology, bool) {
 
     use gltf::mesh::Mode::*;
 
     match from {
-        | Points        => "Points",
-        | Lines         => "Lines",
-        | LineLoop      => "LineLoop",
-        | LineStrip     => "LineStrip",
-        | Triangles     => "Triangles",
-        | TriangleStrip => "TriangleStrip",
-        | TriangleFan   => "TriangleFan",
+        | Points    => (vk::PrimitiveTopology::POINT_LIST, false),
+        | Lines     => (vk::PrimitiveTopology::LINE_LIST, false),
+        | LineLoop  => (vk::PrimitiveTopology::LINE_LIST, true),
+        | Triangles => (vk::PrimitiveTopology::TRIANGLE_LIST, false),
+        | LineStrip     if is_unroll_strips => (vk::PrimitiveTopology::LINE_LIST, true),
+        | TriangleStrip if is_unroll_strips => (vk::PrimitiveTopology::TRIANGLE_LIST, true),
+        | TriangleFan   if is_unroll_strips => (vk::PrimitiveTopology::TRIANGLE_LIST, true),
+        | LineStrip     => (vk::PrimitiveTopology::LINE_STRIP, false),
+        | TriangleStrip => (vk::PrimitiveTopology::TRIANGLE_STRIP, false),
+        | TriangleFan   => (vk::PrimitiveTopology::TRIANGLE_FAN, false),
     }
 }
 // --------------------------------------------------------------------------------------
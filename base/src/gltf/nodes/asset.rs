@@ -5,30 +5,47 @@ use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList};
 use crate::gltf::asset::ReferenceIndex;
 use crate::gltf::scene::Scene;
 use crate::gltf::nodes::node::Node;
-use crate::gltf::nodes::attachment::{NodeAttachments, NodeAttachmentFlags};
+use crate::gltf::nodes::attachment::{NodeAttachments, NodeAttachmentFlags, AttachmentContent};
+use crate::gltf::nodes::graph::SceneGraph;
 
 use crate::ci::vma::VmaBuffer;
 use crate::context::{VkDevice, VmaResourceDiscardable};
-use crate::command::CmdTransferApi;
 use crate::error::{VkResult, VkError, VkErrorKind};
-use crate::{vkbytes, vkptr};
+use crate::{vkbytes, vkptr, vkuint};
+use crate::Mat4F;
+
+use serde_derive::{Serialize, Deserialize};
 
-use std::collections::HashMap;
 use std::convert::TryFrom;
 
+#[derive(Serialize, Deserialize)]
 pub struct NodeAsset {
 
-    attachments: NodeAttachments,
+    /// the properties of node attachments, which are collected from scene in allocation.
+    attachment_flags: NodeAttachmentFlags,
 
     nodes: AssetElementList<Node>,
+    graph: Option<SceneGraph>,
 }
 
 pub struct NodeResource {
 
     pub(crate) list: AssetElementList<Node>,
     pub(crate) attachment_size_aligned: vkbytes,
-    pub(crate) attachment_mapping: HashMap<ReferenceIndex, usize>,
 
+    attachments: NodeAttachments,
+    graph: SceneGraph,
+
+    /// the count of attachment copies in buffer, one for each frame in flight.
+    frame_count: usize,
+    /// the byte size of attachments of all nodes in each frame.
+    frame_size: vkbytes,
+    /// the version of world transform of each node in `attachments`.
+    content_versions: Vec<u64>,
+    /// the version of world transform of each node written to the buffer of each frame.
+    frame_versions: Vec<Vec<u64>>,
+
+    /// a persistently mapped dynamic uniform buffer.
     buffer: VmaBuffer,
 }
 
@@ -37,9 +54,13 @@ impl TryFrom<NodeAttachmentFlags> for NodeAsset {
 
     fn try_from(flag: NodeAttachmentFlags) -> VkResult<NodeAsset> {
 
+        // check whether the property combination is supported before reading any node.
+        let _ = NodeAttachments::try_from(flag)?;
+
         let result = NodeAsset {
-            attachments: NodeAttachments::try_from(flag)?,
+            attachment_flags: flag,
             nodes: Default::default(),
+            graph: None,
         };
         Ok(result)
     }
@@ -58,7 +79,7 @@ impl AssetAbstract for NodeAsset {
             self.nodes.push(json_index, node);
         }
 
-        scene.read_node_attachment(&self.nodes, &mut self.attachments);
+        self.graph = Some(SceneGraph::from_doc(source, scene));
 
         Ok(())
     }
@@ -66,21 +87,30 @@ impl AssetAbstract for NodeAsset {
 
 impl NodeAsset {
 
-    pub fn allocate(self, device: &mut VkDevice, min_alignment: vkbytes) -> VkResult<NodeResource> {
+    /// Allocate the buffer for node attachments of `scene`, which contains a copy of attachments for each of `frame_in_flight` frames.
+    pub fn allocate(self, device: &mut VkDevice, scene: &Scene, min_alignment: vkbytes, frame_in_flight: usize) -> VkResult<NodeResource> {
 
         use crate::ci::buffer::BufferCI;
         use crate::ci::vma::VmaAllocationCI;
         use crate::utils::memory::IntegerAlignable;
 
-        let attachment_size_aligned = self.attachments.element_size.align_to(min_alignment);
-        let request_attachments_size = attachment_size_aligned * (self.attachments.data_content.length() as vkbytes);
+        let graph = self.graph
+            .ok_or(VkError::custom("The glTF nodes must be read before allocation."))?;
+
+        let mut attachments = NodeAttachments::try_from(self.attachment_flags)?;
+        scene.read_node_attachment(&self.nodes, &mut attachments);
+
+        let frame_count = frame_in_flight.max(1);
+        let attachment_size_aligned = attachments.element_size.align_to(min_alignment);
+        let frame_size = attachment_size_aligned * (attachments.data_content.length() as vkbytes);
 
         // allocate dynamic uniform buffer for Node attachments data.
         let attachments_buffer = {
 
-            let attachments_ci = BufferCI::new(request_attachments_size)
-                .usage(vk::BufferUsageFlags::UNIFORM_BUFFER | vk::BufferUsageFlags::TRANSFER_DST);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let attachments_ci = BufferCI::new(frame_size * (frame_count as vkbytes))
+                .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
             let attachments_allocation = device.vma.create_buffer(
                 attachments_ci.as_ref(), allocate_ci.as_ref())
                 .map_err(VkErrorKind::Vma)?;
@@ -88,52 +118,23 @@ impl NodeAsset {
             VmaBuffer::from(attachments_allocation)
         };
 
-        // allocate staging buffer.
-        let staging_buffer = {
-
-            let staging_ci = BufferCI::new(request_attachments_size)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (staging_buffer, allocation, info) = device.vma.create_buffer(
-                staging_ci.as_ref(), allocate_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
-
-            let data_ptr = device.vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
-
-            self.attachments.data_content.map_data(data_ptr, info.get_size() as _, min_alignment);
-
-            device.vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
-
-            VmaBuffer { handle: staging_buffer, allocation, info }
-        };
-
-        { // copy staging data to target memory.
-            let cmd_recorder = device.get_transfer_recorder();
-
-            let copy_region = vk::BufferCopy {
-                src_offset: 0,
-                dst_offset: 0,
-                size: staging_buffer.info.get_size() as _,
-            };
-
-            cmd_recorder.begin_record()?
-                .copy_buf2buf(staging_buffer.handle, attachments_buffer.handle, &[copy_region])
-                .end_record()?;
-
-            device.flush_transfer(cmd_recorder)?;
+        // write the initial attachments data to the buffer of each frame.
+        let data_ptr = attachments_buffer.info.get_mapped_data();
+        for frame_index in 0..frame_count {
+            let frame_ptr = unsafe { data_ptr.offset((frame_size as isize) * (frame_index as isize)) };
+            attachments.data_content.map_data(frame_ptr as vkptr, frame_size, min_alignment);
         }
 
-        { // destroy staging buffer.
-            device.vma_discard(staging_buffer)?;
-        }
+        let content_versions: Vec<u64> = (0..graph.node_count())
+            .map(|node_index| graph.version(node_index))
+            .collect();
+        let frame_versions = vec![content_versions.clone(); frame_count];
 
         // done.
         let result = NodeResource {
             list  : self.nodes,
             buffer: attachments_buffer,
-            attachment_mapping: self.attachments.attachments_mapping,
+            attachments, graph, frame_count, frame_size, content_versions, frame_versions,
             attachment_size_aligned,
         };
         Ok(result)
@@ -142,6 +143,7 @@ impl NodeAsset {
 
 impl NodeResource {
 
+    /// The descriptor of node attachments, which should be bound as `vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC`.
     pub fn node_descriptor(&self) -> vk::DescriptorBufferInfo {
 
         vk::DescriptorBufferInfo {
@@ -150,6 +152,70 @@ impl NodeResource {
             range : self.attachment_size_aligned,
         }
     }
+
+    pub fn graph(&self) -> &SceneGraph {
+        &self.graph
+    }
+
+    /// The scene graph to change the local transforms of nodes, and the changes take effect after `update`.
+    pub fn graph_mut(&mut self) -> &mut SceneGraph {
+        &mut self.graph
+    }
+
+    /// Return the dynamic offset of node attachment in the buffer of frame, or None if the node does not have any attachment.
+    ///
+    /// `frame_index` wraps around the frame count specified in allocation.
+    pub fn attachment_offset(&self, node_index: ReferenceIndex, frame_index: usize) -> Option<vkuint> {
+
+        self.attachments.attachments_mapping.get(&node_index).map(|position| {
+            let frame_offset = self.frame_size * ((frame_index % self.frame_count) as vkbytes);
+            (frame_offset + self.attachment_size_aligned * (*position as vkbytes)) as vkuint
+        })
+    }
+
+    /// Return the world transform of node written in its attachment, or None if the node does not have any attachment.
+    pub fn attached_transform(&self, node_index: ReferenceIndex) -> Option<Mat4F> {
+
+        self.attachments.attachments_mapping.get(&node_index)
+            .map(|position| self.attachments.data_content.transform(*position))
+    }
+
+    /// Recalculate the world transforms of changed nodes, and write their attachments to the buffer of `frame_index`.
+    ///
+    /// This method must be called when GPU is not reading the buffer of `frame_index`.
+    pub fn update(&mut self, frame_index: usize) {
+
+        self.graph.update();
+
+        let frame_slot = frame_index % self.frame_count;
+        let frame_ptr = unsafe {
+            (self.buffer.info.get_mapped_data() as vkptr<u8>).offset((self.frame_size as isize) * (frame_slot as isize))
+        };
+
+        for (node_index, position) in self.attachments.attachments_mapping.iter() {
+
+            let version = self.graph.version(*node_index);
+            if self.frame_versions[frame_slot][*node_index] == version {
+                continue
+            }
+
+            if self.content_versions[*node_index] != version {
+                let attachment = AttachmentContent {
+                    transform: Some(self.graph.world_transform(*node_index)),
+                };
+                self.attachments.data_content.update(*position, attachment);
+                self.content_versions[*node_index] = version;
+            }
+
+            let element_bytes = self.attachments.data_content.element_bytes(*position);
+            unsafe {
+                let element_ptr = frame_ptr.offset((self.attachment_size_aligned as isize) * (*position as isize));
+                element_ptr.copy_from_nonoverlapping(element_bytes.as_ptr(), element_bytes.len());
+            }
+
+            self.frame_versions[frame_slot][*node_index] = version;
+        }
+    }
 }
 
 impl VmaResourceDiscardable for NodeResource {
@@ -4,6 +4,8 @@ use crate::error::{VkResult, VkError};
 use crate::{vkbytes, vkptr};
 use crate::Mat4F;
 
+use serde_derive::{Serialize, Deserialize};
+
 use std::ops::{BitAnd, BitOr, BitOrAssign, BitAndAssign};
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -58,8 +60,17 @@ pub trait AttachmentData {
 
     fn extend(&mut self, attachment: AttachmentContent) -> usize;
 
+    /// Overwrite the attachment data at `position`, which must be a position returned by `extend`.
+    fn update(&mut self, position: usize, attachment: AttachmentContent);
+
     fn length(&self) -> usize;
 
+    /// Return the transform matrix of the attachment data at `position`.
+    fn transform(&self, position: usize) -> Mat4F;
+
+    /// Return the raw bytes of the attachment data at `position`.
+    fn element_bytes(&self, position: usize) -> &[u8];
+
     fn map_data(&self, memory_ptr: vkptr, block_size: vkbytes, alignment: vkbytes);
 }
 
@@ -136,10 +147,29 @@ macro_rules! define_node_attachments {
                 length
             }
 
+            fn update(&mut self, position: usize, attachment: AttachmentContent) {
+
+                debug_assert!(position < self.data.len());
+                $(
+                    read_transform!(self, attachment, $name_uniform, position, $attribute);
+                )*
+            }
+
             fn length(&self) -> usize {
                 self.data.len()
             }
 
+            fn transform(&self, position: usize) -> Mat4F {
+                self.data[position].transform
+            }
+
+            fn element_bytes(&self, position: usize) -> &[u8] {
+
+                unsafe {
+                    ::std::slice::from_raw_parts(&self.data[position] as *const $name_uniform as *const u8, ::std::mem::size_of::<$name_uniform>())
+                }
+            }
+
             fn map_data(&self, memory_ptr: vkptr, block_size: vkbytes, alignment: vkbytes) {
 
                 let mut vert_align = unsafe {
@@ -158,7 +188,7 @@ define_node_attachments!(NA_T, NAttachment_T, {
 // --------------------------------------------------------------------------------------
 
 // --------------------------------------------------------------------------------------
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NodeAttachmentFlags(u32);
 
 impl NodeAttachmentFlags {
@@ -171,7 +201,7 @@ impl NodeAttachmentFlags {
     fn element_size(&self) -> Option<vkbytes> {
         use std::mem::size_of;
         match *self {
-            | NodeAttachmentFlags::NAF_T => Some(size_of::<NA_T>() as _),
+            | NodeAttachmentFlags::NAF_T => Some(size_of::<NAttachment_T>() as _),
             | _ => None,
         }
     }
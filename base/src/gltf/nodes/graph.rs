@@ -0,0 +1,166 @@
+
+use crate::gltf::asset::{GltfDocument, ReferenceIndex};
+use crate::gltf::scene::Scene;
+use crate::{Mat4F, Vec3F, Vec4F};
+
+use serde_derive::{Serialize, Deserialize};
+
+// --------------------------------------------------------------------------------------
+/// The local transform of a node decomposed to translation, rotation and scale.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NodePose {
+
+    pub translation: Vec3F,
+    /// the rotation quaternion in (x, y, z, w) order.
+    pub rotation: Vec4F,
+    pub scale: Vec3F,
+}
+
+impl NodePose {
+
+    pub fn local_transform(&self) -> Mat4F {
+
+        let rotation = vek::Quaternion::from_xyzw(self.rotation.x, self.rotation.y, self.rotation.z, self.rotation.w);
+        Mat4F::translation_3d(self.translation) * Mat4F::from(rotation) * Mat4F::scaling_3d(self.scale)
+    }
+}
+
+impl<'a> From<&gltf::Node<'a>> for NodePose {
+
+    fn from(doc_node: &gltf::Node<'a>) -> NodePose {
+
+        let (translation, rotation, scale) = doc_node.transform().decomposed();
+
+        NodePose {
+            translation: Vec3F::from(translation),
+            rotation   : Vec4F::from(rotation),
+            scale      : Vec3F::from(scale),
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize)]
+struct GraphNode {
+
+    pose: NodePose,
+
+    parent: Option<ReferenceIndex>,
+    children: Vec<ReferenceIndex>,
+
+    /// the transform from node space to model space.
+    world_transform: Mat4F,
+    /// whether the world transform of this node and its descendants need to be recalculated.
+    is_dirty: bool,
+    /// increased each time the world transform of this node changes.
+    version: u64,
+}
+
+/// The node hierarchy of glTF scene, whose local transforms can be changed at runtime.
+///
+/// Nodes are indexed by their json index, and the world transforms are recalculated lazily in `update`.
+#[derive(Serialize, Deserialize)]
+pub struct SceneGraph {
+
+    nodes: Vec<GraphNode>,
+    roots: Vec<ReferenceIndex>,
+}
+
+impl SceneGraph {
+
+    pub fn from_doc(source: &GltfDocument, scene: &Scene) -> SceneGraph {
+
+        let mut nodes: Vec<GraphNode> = source.doc.nodes().map(|doc_node| {
+            GraphNode {
+                pose: NodePose::from(&doc_node),
+                parent: None,
+                children: doc_node.children().map(|doc_child| doc_child.index()).collect(),
+                world_transform: Mat4F::identity(),
+                is_dirty: true,
+                version: 0,
+            }
+        }).collect();
+
+        for parent_index in 0..nodes.len() {
+            for child_index in nodes[parent_index].children.clone() {
+                nodes[child_index].parent = Some(parent_index);
+            }
+        }
+
+        let mut graph = SceneGraph {
+            nodes,
+            roots: scene.root_nodes().to_vec(),
+        };
+        graph.update();
+        graph
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn pose(&self, node_index: ReferenceIndex) -> &NodePose {
+        &self.nodes[node_index].pose
+    }
+
+    pub fn set_pose(&mut self, node_index: ReferenceIndex, pose: NodePose) {
+        self.nodes[node_index].pose = pose;
+        self.nodes[node_index].is_dirty = true;
+    }
+
+    pub fn set_translation(&mut self, node_index: ReferenceIndex, translation: Vec3F) {
+        self.nodes[node_index].pose.translation = translation;
+        self.nodes[node_index].is_dirty = true;
+    }
+
+    /// Set the rotation quaternion of node in (x, y, z, w) order.
+    pub fn set_rotation(&mut self, node_index: ReferenceIndex, rotation: Vec4F) {
+        self.nodes[node_index].pose.rotation = rotation;
+        self.nodes[node_index].is_dirty = true;
+    }
+
+    pub fn set_scale(&mut self, node_index: ReferenceIndex, scale: Vec3F) {
+        self.nodes[node_index].pose.scale = scale;
+        self.nodes[node_index].is_dirty = true;
+    }
+
+    pub fn parent(&self, node_index: ReferenceIndex) -> Option<ReferenceIndex> {
+        self.nodes[node_index].parent
+    }
+
+    /// Return the world transform of node calculated in last `update`.
+    pub fn world_transform(&self, node_index: ReferenceIndex) -> Mat4F {
+        self.nodes[node_index].world_transform
+    }
+
+    pub(crate) fn version(&self, node_index: ReferenceIndex) -> u64 {
+        self.nodes[node_index].version
+    }
+
+    /// Recalculate the world transforms of the dirty nodes and their descendants.
+    pub fn update(&mut self) {
+
+        for root_index in self.roots.clone() {
+            self.update_node(root_index, &Mat4F::identity(), false);
+        }
+    }
+
+    fn update_node(&mut self, node_index: ReferenceIndex, parent_transform: &Mat4F, is_parent_changed: bool) {
+
+        let is_changed = is_parent_changed || self.nodes[node_index].is_dirty;
+
+        if is_changed {
+            let node = &mut self.nodes[node_index];
+            node.world_transform = (*parent_transform) * node.pose.local_transform();
+            node.is_dirty = false;
+            node.version += 1;
+        }
+
+        let world_transform = self.nodes[node_index].world_transform;
+        for child_index in self.nodes[node_index].children.clone() {
+            self.update_node(child_index, &world_transform, is_changed);
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
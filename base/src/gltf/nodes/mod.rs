@@ -2,7 +2,9 @@
 pub use self::asset::{NodeAsset, NodeResource};
 pub use self::attachment::{NodeAttachments, NodeAttachmentFlags};
 pub use self::node::Node;
+pub use self::graph::{SceneGraph, NodePose};
 
 mod attachment;
 mod asset;
+mod graph;
 mod node;
@@ -2,13 +2,18 @@
 use crate::gltf::asset::{ReferenceIndex, AssetElementList};
 use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 use crate::gltf::nodes::attachment::{NodeAttachments, AttachmentContent};
+use crate::gltf::meshes::Mesh;
+use crate::gltf::scene::DrawItem;
 use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 use crate::error::VkResult;
-use crate::{vkuint, Mat4F};
+use crate::Mat4F;
+
+use serde_derive::{Serialize, Deserialize};
 
 
 // --------------------------------------------------------------------------------------
 /// A wrapper class for node level in glTF, containing the render parameters read from glTF file.
+#[derive(Serialize, Deserialize)]
 pub struct Node {
 
     /// the name property of current node.
@@ -19,6 +24,8 @@ pub struct Node {
     local_mesh: Option<ReferenceIndex>,
     /// the json index of children nodes.
     children: Vec<ReferenceIndex>,
+    /// the json index of glTF::Skin used by the mesh of this node.
+    skin: Option<ReferenceIndex>,
     /// the transform property of current node.
     local_transform: Mat4F,
 }
@@ -42,10 +49,27 @@ impl Node {
             .map(|doc_node| doc_node.index())
             .collect();
 
-        let result = Node { _name: name, json_index, local_mesh, children, local_transform };
+        let skin = node.skin().map(|doc_skin| doc_skin.index());
+
+        let result = Node { _name: name, json_index, local_mesh, children, skin, local_transform };
         Ok(result)
     }
 
+    /// the json index of mesh referenced by this node.
+    pub fn mesh(&self) -> Option<ReferenceIndex> {
+        self.local_mesh
+    }
+
+    /// the json index of skin used by the mesh of this node.
+    pub fn skin(&self) -> Option<ReferenceIndex> {
+        self.skin
+    }
+
+    /// Whether the mesh of this node is deformed by the joints of a skin.
+    pub fn is_skinned(&self) -> bool {
+        self.skin.is_some()
+    }
+
     pub fn read_attachment(&self, nodes: &AssetElementList<Node>, attachments: &mut NodeAttachments, parent_transform: &Mat4F) {
 
         // apply parent node's transformation to current node level.
@@ -67,22 +91,46 @@ impl Node {
         }
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    /// Collect the primitives of this node and its child nodes into `draws`.
+    pub fn read_draws(&self, nodes: &AssetElementList<Node>, meshes: &AssetElementList<Mesh>, draws: &mut Vec<DrawItem>) {
 
         if let Some(local_mesh) = self.local_mesh {
 
-            // calculate the dynamic offset.
-            let dyn_offset = (model.nodes.attachment_size_aligned as vkuint) * (model.nodes.attachment_mapping.get(&self.json_index).unwrap().clone() as vkuint);
-            // bind descriptors with dynamic offset for node attachment.
-            recorder.bind_descriptor_sets(params.pipeline_layout, 0, &[params.descriptor_set], &[dyn_offset]);
+            let mesh = meshes.get(local_mesh);
+            for primitive_index in 0..mesh.primitives().len() {
+
+                draws.push(DrawItem {
+                    node: self.json_index,
+                    mesh: local_mesh,
+                    primitive: primitive_index,
+                });
+            }
+        }
 
-            let mesh = model.meshes.list.get(local_mesh);
-            mesh.record_command(recorder, model, params);
+        for child_json_index in self.children.iter().cloned() {
+            let child_node = nodes.get(child_json_index);
+            child_node.read_draws(nodes, meshes, draws);
         }
+    }
+
+    /// Bind the descriptor set of model with the dynamic offset of this node's attachment, and the joint matrices of its skin.
+    pub fn bind_attachment(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+
+        // calculate the dynamic offset in the attachments of current frame.
+        let dyn_offset = model.nodes.attachment_offset(self.json_index, params.frame_index).unwrap();
+        // bind descriptors with dynamic offset for node attachment.
+        recorder.bind_descriptor_sets(params.pipeline_layout, 0, &[params.descriptor_set], &[dyn_offset]);
+
+        self.bind_skin(recorder, model, params);
+    }
+
+    /// Bind the joint matrices of skin if this node is skinned and `params.skin_set` is specified.
+    pub fn bind_skin(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
 
-        for child_node_index in self.children.iter().cloned() {
-            let child_node = model.nodes.list.get(child_node_index);
-            child_node.record_command(recorder, model, params);
+        if let (Some(skin_set), Some(skin)) = (params.skin_set, self.skin) {
+            // bind the joint matrices of skin written for current frame.
+            let skin_descriptor_set = model.animations.get_descriptor_set(skin, params.frame_index);
+            recorder.bind_descriptor_sets(params.pipeline_layout, skin_set, &[skin_descriptor_set], &[]);
         }
     }
 }
@@ -0,0 +1,283 @@
+
+use crate::gltf::obj::parser::{ObjData, FaceGroup, Corner, MtlMaterial};
+use crate::error::{VkResult, VkError};
+
+use serde_json::{Value, json};
+
+use std::collections::HashMap;
+
+/// The component type of float in glTF accessor.
+const COMPONENT_FLOAT: u32 = 5126;
+/// The component type of unsigned int in glTF accessor.
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+/// The target of buffer view for vertex attributes.
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+/// The target of buffer view for indices.
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+/// The glTF primitive mode of triangle list.
+const MODE_TRIANGLES: u32 = 4;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN : u32 = 0x004E4942;
+
+// --------------------------------------------------------------------------------------
+/// Build a GLB containing a single mesh with a primitive for each face group of `obj`, referenced by the only node of the only scene.
+///
+/// The vertices of each primitive are deduplicated by their position, texture coordinate and normal indices.
+/// The textures are referenced by their paths relative to the directory of OBJ file.
+pub fn build_glb(obj: &ObjData) -> VkResult<Vec<u8>> {
+
+    let mut builder = DocumentBuilder::default();
+
+    let material_mapping: HashMap<&str, usize> = obj.materials.iter().enumerate()
+        .map(|(material_index, material)| (material.name.as_str(), material_index))
+        .collect();
+    let materials: Vec<Value> = obj.materials.iter()
+        .map(|material| builder.material(material))
+        .collect();
+
+    let primitives: Vec<Value> = obj.groups.iter()
+        .filter(|group| !group.triangles.is_empty())
+        .map(|group| {
+            // the faces using undefined materials are rendered with the default material.
+            let material = group.material.as_ref()
+                .and_then(|name| material_mapping.get(name.as_str()).cloned());
+            builder.primitive(obj, group, material)
+        })
+        .collect();
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "vulkan-base OBJ importer" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": primitives }],
+        "buffers": [{ "byteLength": builder.bin.len() }],
+        "bufferViews": builder.views,
+        "accessors": builder.accessors,
+    });
+
+    let optional_arrays = vec![
+        ("materials", materials),
+        ("textures", builder.textures),
+        ("images", builder.images),
+    ];
+    for (property, elements) in optional_arrays {
+        if !elements.is_empty() {
+            document[property] = Value::from(elements);
+        }
+    }
+
+    let json_bytes = serde_json::to_vec(&document)
+        .map_err(|e| VkError::custom(format!("Failed to serialize glTF json of OBJ model: {}", e)))?;
+
+    Ok(pack_glb(json_bytes, builder.bin))
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Collect the binary data and the json elements of glTF document.
+#[derive(Default)]
+struct DocumentBuilder {
+
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    /// the json index of texture for each image path.
+    texture_mapping: HashMap<String, usize>,
+}
+
+impl DocumentBuilder {
+
+    fn primitive(&mut self, obj: &ObjData, group: &FaceGroup, material: Option<usize>) -> Value {
+
+        let corners = || group.triangles.iter().flat_map(|triangle| triangle.iter());
+
+        // NORMAL is omitted if any corner lacks normal, so that the normals can be generated on import.
+        // TEXCOORD_0 is kept if any corner has texture coordinate, and the missing ones are filled with zero.
+        let is_normal_complete = corners().all(|corner| corner.normal.is_some());
+        let is_texcoord_present = corners().any(|corner| corner.texcoord.is_some());
+
+        let mut vertex_mapping: HashMap<Corner, u32> = HashMap::new();
+        let mut vertices: Vec<Corner> = Vec::new();
+        let mut indices: Vec<u32> = Vec::with_capacity(group.triangles.len() * 3);
+
+        for corner in corners() {
+
+            let key = Corner {
+                position: corner.position,
+                texcoord: if is_texcoord_present { corner.texcoord } else { None },
+                normal  : if is_normal_complete  { corner.normal } else { None },
+            };
+
+            let vertex_index = *vertex_mapping.entry(key).or_insert_with(|| {
+                vertices.push(key);
+                (vertices.len() - 1) as u32
+            });
+            indices.push(vertex_index);
+        }
+
+        let positions: Vec<[f32; 3]> = vertices.iter()
+            .map(|vertex| obj.positions[vertex.position])
+            .collect();
+        let (min, max) = positions.iter().fold(([::std::f32::MAX; 3], [::std::f32::MIN; 3]), |(mut min, mut max), position| {
+            for i in 0..3 {
+                min[i] = min[i].min(position[i]);
+                max[i] = max[i].max(position[i]);
+            }
+            (min, max)
+        });
+
+        let mut attributes = json!({
+            "POSITION": self.accessor(&flatten(&positions), positions.len(), "VEC3", COMPONENT_FLOAT, TARGET_ARRAY_BUFFER, Some((&min, &max))),
+        });
+
+        if is_normal_complete {
+            let normals: Vec<[f32; 3]> = vertices.iter()
+                .map(|vertex| vertex.normal.map(|normal| obj.normals[normal]).unwrap_or([0.0; 3]))
+                .collect();
+            attributes["NORMAL"] = self.accessor(&flatten(&normals), normals.len(), "VEC3", COMPONENT_FLOAT, TARGET_ARRAY_BUFFER, None);
+        }
+
+        if is_texcoord_present {
+            let texcoords: Vec<[f32; 2]> = vertices.iter()
+                .map(|vertex| vertex.texcoord.map(|texcoord| obj.texcoords[texcoord]).unwrap_or([0.0; 2]))
+                .collect();
+            attributes["TEXCOORD_0"] = self.accessor(&flatten(&texcoords), texcoords.len(), "VEC2", COMPONENT_FLOAT, TARGET_ARRAY_BUFFER, None);
+        }
+
+        let index_bytes: Vec<u8> = indices.iter()
+            .flat_map(|index| index.to_le_bytes().to_vec())
+            .collect();
+        let indices_accessor = self.accessor(&index_bytes, indices.len(), "SCALAR", COMPONENT_UNSIGNED_INT, TARGET_ELEMENT_ARRAY_BUFFER, None);
+
+        let mut primitive = json!({
+            "attributes": attributes,
+            "indices": indices_accessor,
+            "mode": MODE_TRIANGLES,
+        });
+        if let Some(material) = material {
+            primitive["material"] = json!(material);
+        }
+
+        primitive
+    }
+
+    /// Append `bytes` to the binary buffer with a buffer view and an accessor, and return the json index of accessor.
+    fn accessor(&mut self, bytes: &[u8], count: usize, accessor_type: &str, component_type: u32, target: u32, bounds: Option<(&[f32; 3], &[f32; 3])>) -> Value {
+
+        // all elements are 4-byte aligned, so the buffer views need no padding.
+        let view_index = self.views.len();
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.bin.extend_from_slice(bytes);
+
+        let mut accessor = json!({
+            "bufferView": view_index,
+            "componentType": component_type,
+            "count": count,
+            "type": accessor_type,
+        });
+        // the min and max are required by POSITION accessor.
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+
+        self.accessors.push(accessor);
+        json!(self.accessors.len() - 1)
+    }
+
+    fn material(&mut self, material: &MtlMaterial) -> Value {
+
+        let [r, g, b] = material.diffuse;
+        let alpha = material.dissolve.max(0.0).min(1.0);
+
+        let mut pbr = json!({
+            "baseColorFactor": [r, g, b, alpha],
+            "metallicFactor": material.metallic,
+            "roughnessFactor": material.roughness,
+        });
+
+        let alpha_mode = if alpha < 1.0 { "BLEND" } else { "OPAQUE" };
+
+        let mut result = json!({
+            "name": material.name,
+            "emissiveFactor": material.emissive,
+            "alphaMode": alpha_mode,
+        });
+
+        if let Some(ref diffuse_map) = material.diffuse_map {
+            pbr["baseColorTexture"] = json!({ "index": self.texture(diffuse_map) });
+        }
+        if let Some(ref emissive_map) = material.emissive_map {
+            result["emissiveTexture"] = json!({ "index": self.texture(emissive_map) });
+            // the emissive texture is multiplied by the emissive factor, which defaults to black in MTL.
+            if material.emissive == [0.0; 3] {
+                result["emissiveFactor"] = json!([1.0, 1.0, 1.0]);
+            }
+        }
+        if let Some(ref normal_map) = material.normal_map {
+            result["normalTexture"] = json!({ "index": self.texture(normal_map), "scale": material.normal_scale });
+        }
+
+        result["pbrMetallicRoughness"] = pbr;
+        result
+    }
+
+    /// Return the json index of texture sampling the image at `path`, where each image is referenced by a single texture.
+    fn texture(&mut self, path: &str) -> usize {
+
+        let images = &mut self.images;
+        let textures = &mut self.textures;
+
+        *self.texture_mapping.entry(path.to_string()).or_insert_with(|| {
+            images.push(json!({ "uri": path }));
+            textures.push(json!({ "source": images.len() - 1 }));
+            textures.len() - 1
+        })
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Convert the float vectors to little-endian bytes.
+fn flatten<T: AsRef<[f32]>>(values: &[T]) -> Vec<u8> {
+    values.iter()
+        .flat_map(|value| value.as_ref().to_vec())
+        .flat_map(|component| component.to_bits().to_le_bytes().to_vec())
+        .collect()
+}
+
+/// Pack the json and binary chunk to a GLB file.
+fn pack_glb(mut json_bytes: Vec<u8>, mut bin: Vec<u8>) -> Vec<u8> {
+
+    // the json chunk is padded with spaces and the binary chunk is padded with zeros to 4 bytes.
+    while json_bytes.len() % 4 != 0 { json_bytes.push(b' '); }
+    while bin.len() % 4 != 0 { bin.push(0); }
+
+    // 12 bytes header, and 8 bytes header for each chunk.
+    let total_length = 12 + 8 + json_bytes.len() + 8 + bin.len();
+
+    let mut glb = Vec::with_capacity(total_length);
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    glb.extend_from_slice(&(total_length as u32).to_le_bytes());
+
+    for (chunk_type, chunk_data) in [(GLB_CHUNK_JSON, json_bytes), (GLB_CHUNK_BIN, bin)].iter() {
+        glb.extend_from_slice(&(chunk_data.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&chunk_type.to_le_bytes());
+        glb.extend_from_slice(chunk_data);
+    }
+
+    glb
+}
+// --------------------------------------------------------------------------------------
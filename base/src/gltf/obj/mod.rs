@@ -0,0 +1,70 @@
+
+use crate::gltf::obj::parser::ObjData;
+use crate::gltf::obj::document::build_glb;
+use crate::gltf::loader::{GltfModelInfo, SceneSelection, load_gltf};
+use crate::gltf::source::{GltfSource, FileResolver};
+use crate::gltf::meshes::{VertexLayout, MeshOptimizeFlags};
+use crate::gltf::nodes::NodeAttachmentFlags;
+use crate::gltf::asset::VkglTFModel;
+
+use crate::context::VkDevice;
+use crate::error::VkResult;
+use crate::Mat4F;
+
+use std::path::Path;
+
+mod parser;
+mod document;
+
+
+pub struct ObjModelInfo<'a> {
+    /// The path of OBJ file. The MTL files and textures are resolved relative to its directory.
+    pub path: &'a Path,
+    /// Indicate what attributes will be read from this model file, and how they are arranged in vertex buffer.
+    ///
+    /// OBJ provides POSITION, NORMAL and TEXCOORD_0, and the other attributes are filled with zero.
+    pub attribute: VertexLayout,
+    /// Generate flat normals for the faces without normals, if NORMAL is in `attribute`.
+    pub generate_normals: bool,
+    /// Generate MikkTSpace tangents, if TANGENT is in `attribute`.
+    pub generate_tangents: bool,
+    /// Indicate which optimizations are applied to the triangles, whose results are reported by `MeshResource::optimization_report`.
+    pub optimization: MeshOptimizeFlags,
+    /// Indicate what properties will be read for Node hierarchy(etc. transform matrix).
+    pub node: NodeAttachmentFlags,
+    /// A matrix that will apply to position attribute of the model.
+    pub transform: Option<Mat4F>,
+    /// The count of node attachment copies, see `GltfModelInfo::frame_in_flight`.
+    pub frame_in_flight: usize,
+}
+
+/// Load a Wavefront OBJ file and its MTL materials as a glTF model.
+///
+/// The model contains a single node and mesh, where the faces are split into a triangle list primitive for each material.
+/// The MTL materials are converted to metallic-roughness materials(`Kd`/`d`/`map_Kd` to base color, `Ke`/`map_Ke` to emissive,
+/// `bump`/`norm` to normal texture, and `Pr`/`Pm` or `Ns` to roughness and metallic), and the faces of undefined material use the default material.
+pub fn load_obj(device: &mut VkDevice, info: ObjModelInfo) -> VkResult<VkglTFModel> {
+
+    let obj = ObjData::read(info.path)?;
+    let glb = build_glb(&obj)?;
+
+    let base = info.path.parent().unwrap_or(Path::new(""));
+    let resolver = FileResolver::new(base);
+
+    // the OBJ model is imported as an in-memory GLB, so that it goes through the same import path as glTF.
+    let gltf_info = GltfModelInfo {
+        source: GltfSource::Slice { data: &glb, resolver: &resolver },
+        scene: SceneSelection::Default,
+        attribute: info.attribute,
+        unroll_strips: false,
+        generate_normals : info.generate_normals,
+        generate_tangents: info.generate_tangents,
+        optimization: info.optimization,
+        node: info.node,
+        transform: info.transform,
+        frame_in_flight: info.frame_in_flight,
+        cache: None,
+    };
+
+    load_gltf(device, gltf_info)
+}
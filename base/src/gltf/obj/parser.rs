@@ -0,0 +1,382 @@
+
+use crate::error::{VkResult, VkError};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// --------------------------------------------------------------------------------------
+/// A corner of a triangle, referring to the vertex data of OBJ file by their positions in each list.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Corner {
+    pub position: usize,
+    pub texcoord: Option<usize>,
+    pub normal  : Option<usize>,
+}
+
+/// The triangles using the same material, in the order that the material first appears in OBJ file.
+#[derive(Debug, Default)]
+pub struct FaceGroup {
+    /// the name of material in `usemtl` statement, or None for the faces before any `usemtl`.
+    pub material: Option<String>,
+    pub triangles: Vec<[Corner; 3]>,
+}
+
+/// The material properties read from MTL file.
+#[derive(Debug, Clone)]
+pub struct MtlMaterial {
+
+    pub name: String,
+    /// the `Kd` color.
+    pub diffuse: [f32; 3],
+    /// the `d` value, or 1 minus the `Tr` value.
+    pub dissolve: f32,
+    /// the `Ke` color.
+    pub emissive: [f32; 3],
+    /// the `Pr` value, or the value converted from `Ns` specular exponent.
+    pub roughness: f32,
+    /// the `Pm` value.
+    pub metallic: f32,
+    /// the `-bm` option of bump map.
+    pub normal_scale: f32,
+
+    /// the path of `map_Kd` texture relative to the directory of OBJ file.
+    pub diffuse_map: Option<String>,
+    /// the path of `map_Ke` texture relative to the directory of OBJ file.
+    pub emissive_map: Option<String>,
+    /// the path of `norm`, `bump` or `map_Bump` texture relative to the directory of OBJ file.
+    pub normal_map: Option<String>,
+}
+
+impl MtlMaterial {
+
+    fn new(name: String) -> MtlMaterial {
+        MtlMaterial {
+            name,
+            diffuse: [1.0; 3],
+            dissolve: 1.0,
+            emissive: [0.0; 3],
+            roughness: 1.0,
+            metallic: 0.0,
+            normal_scale: 1.0,
+            diffuse_map : None,
+            emissive_map: None,
+            normal_map  : None,
+        }
+    }
+}
+
+/// The geometry and materials of an OBJ file, where all polygons are triangulated.
+#[derive(Debug, Default)]
+pub struct ObjData {
+
+    pub positions: Vec<[f32; 3]>,
+    pub texcoords: Vec<[f32; 2]>,
+    pub normals  : Vec<[f32; 3]>,
+
+    pub groups: Vec<FaceGroup>,
+    /// the materials of all MTL files referenced by `mtllib`.
+    pub materials: Vec<MtlMaterial>,
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+impl ObjData {
+
+    /// Read the OBJ file at `path` and the MTL files it references.
+    ///
+    /// Polygons are triangulated as fans, so they are expected to be convex.
+    /// Points, lines, free-form geometry, groups and smoothing groups are ignored.
+    pub fn read(path: &Path) -> VkResult<ObjData> {
+
+        let content = ::std::fs::read_to_string(path)
+            .or(Err(VkError::path(path)))?;
+        let file_name = path.to_string_lossy();
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        let mut data = ObjData::default();
+        // the position of the group of current material in `data.groups`.
+        let mut group_mapping: HashMap<Option<String>, usize> = HashMap::new();
+        let mut current_group: Option<usize> = None;
+
+        for (line_index, line) in content.lines().enumerate() {
+
+            let line_number = line_index + 1;
+            let error = |description: &str| VkError::parse_obj(&file_name, line_number, description);
+
+            let mut tokens = strip_comment(line).split_whitespace();
+            let keyword = match tokens.next() {
+                | Some(keyword) => keyword,
+                | None => continue,
+            };
+
+            match keyword {
+                | "v" => {
+                    let position = read_floats(&mut tokens, 3).ok_or_else(|| error("expect 3 coordinates for vertex position."))?;
+                    data.positions.push(position);
+                },
+                | "vt" => {
+                    // the V axis points upward in OBJ, but downward in glTF.
+                    let [u, v] = read_floats::<[f32; 2]>(&mut tokens, 1).ok_or_else(|| error("expect at least 1 coordinate for texture coordinate."))?;
+                    data.texcoords.push([u, 1.0 - v]);
+                },
+                | "vn" => {
+                    let normal = read_floats(&mut tokens, 3).ok_or_else(|| error("expect 3 components for vertex normal."))?;
+                    data.normals.push(normal);
+                },
+                | "f" => {
+                    let mut corners = Vec::new();
+                    for token in tokens {
+                        corners.push(data.read_corner(token).map_err(|description| error(&description))?);
+                    }
+
+                    if corners.len() < 3 {
+                        return Err(error("a face must contain at least 3 vertices."))
+                    }
+
+                    let group_index = match current_group {
+                        | Some(group_index) => group_index,
+                        | None => data.group_index(&mut group_mapping, None),
+                    };
+                    current_group = Some(group_index);
+
+                    let triangles = &mut data.groups[group_index].triangles;
+                    for i in 2..corners.len() {
+                        triangles.push([corners[0], corners[i - 1], corners[i]]);
+                    }
+                },
+                | "usemtl" => {
+                    let material_name = rest_of_line(tokens).ok_or_else(|| error("the material name is missing."))?;
+                    current_group = Some(data.group_index(&mut group_mapping, Some(material_name)));
+                },
+                | "mtllib" => {
+                    // the file names are separated by whitespace.
+                    for mtl_name in tokens {
+                        let materials = read_mtl(base, Path::new(mtl_name))?;
+                        data.materials.extend(materials);
+                    }
+                },
+                | _ => {},
+            }
+        }
+
+        if data.groups.iter().all(|group| group.triangles.is_empty()) {
+            return Err(VkError::custom(format!("The OBJ file '{}' does not contain any face.", file_name)))
+        }
+
+        Ok(data)
+    }
+
+    /// Parse a face vertex in the form of `v`, `v/vt`, `v//vn` or `v/vt/vn`, where negative indices are relative to the end of each list.
+    fn read_corner(&self, token: &str) -> Result<Corner, String> {
+
+        let mut elements = token.split('/');
+
+        let position = elements.next()
+            .ok_or_else(|| format!("invalid face vertex '{}'.", token))
+            .and_then(|element| resolve_index(element, self.positions.len(), "vertex position"))?;
+        let texcoord = match elements.next() {
+            | Some(element) if !element.is_empty() => Some(resolve_index(element, self.texcoords.len(), "texture coordinate")?),
+            | _ => None,
+        };
+        let normal = match elements.next() {
+            | Some(element) if !element.is_empty() => Some(resolve_index(element, self.normals.len(), "vertex normal")?),
+            | _ => None,
+        };
+
+        Ok(Corner { position, texcoord, normal })
+    }
+
+    /// Return the position of the group of `material` in `groups`, and create the group if it does not exist.
+    fn group_index(&mut self, mapping: &mut HashMap<Option<String>, usize>, material: Option<String>) -> usize {
+
+        let groups = &mut self.groups;
+        *mapping.entry(material.clone()).or_insert_with(|| {
+            groups.push(FaceGroup { material, triangles: Vec::new() });
+            groups.len() - 1
+        })
+    }
+}
+
+/// Read the materials in the MTL file at `mtl_path`, which is relative to `base`.
+fn read_mtl(base: &Path, mtl_path: &Path) -> VkResult<Vec<MtlMaterial>> {
+
+    let path = base.join(mtl_path);
+    let content = ::std::fs::read_to_string(&path)
+        .or(Err(VkError::path(&path)))?;
+    let file_name = path.to_string_lossy();
+    // the texture paths are relative to the directory of MTL file.
+    let texture_base = mtl_path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    // whether `Pr` is specified for current material, which overrides the roughness converted from `Ns`.
+    let mut is_pbr_roughness = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+
+        let line_number = line_index + 1;
+        let error = |description: &str| VkError::parse_obj(&file_name, line_number, description);
+
+        let mut tokens = strip_comment(line).split_whitespace();
+        let keyword = match tokens.next() {
+            | Some(keyword) => keyword,
+            | None => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = rest_of_line(tokens).ok_or_else(|| error("the material name is missing."))?;
+            materials.push(MtlMaterial::new(name));
+            is_pbr_roughness = false;
+            continue
+        }
+
+        let material = match materials.last_mut() {
+            | Some(material) => material,
+            // the statements before any `newmtl` are ignored.
+            | None => continue,
+        };
+
+        match keyword {
+            | "Kd" => {
+                material.diffuse = read_floats(&mut tokens, 3).ok_or_else(|| error("expect 3 components for Kd."))?;
+            },
+            | "Ke" => {
+                material.emissive = read_floats(&mut tokens, 3).ok_or_else(|| error("expect 3 components for Ke."))?;
+            },
+            | "d" => {
+                let [dissolve] = read_floats::<[f32; 1]>(&mut tokens, 1).ok_or_else(|| error("expect a value for d."))?;
+                material.dissolve = dissolve;
+            },
+            | "Tr" => {
+                let [transparency] = read_floats::<[f32; 1]>(&mut tokens, 1).ok_or_else(|| error("expect a value for Tr."))?;
+                material.dissolve = 1.0 - transparency;
+            },
+            | "Ns" if !is_pbr_roughness => {
+                let [exponent] = read_floats::<[f32; 1]>(&mut tokens, 1).ok_or_else(|| error("expect a value for Ns."))?;
+                material.roughness = roughness_from_exponent(exponent);
+            },
+            | "Pr" => {
+                let [roughness] = read_floats::<[f32; 1]>(&mut tokens, 1).ok_or_else(|| error("expect a value for Pr."))?;
+                material.roughness = roughness;
+                is_pbr_roughness = true;
+            },
+            | "Pm" => {
+                let [metallic] = read_floats::<[f32; 1]>(&mut tokens, 1).ok_or_else(|| error("expect a value for Pm."))?;
+                material.metallic = metallic;
+            },
+            | "map_Kd" => {
+                let (texture, _) = read_texture_map(tokens, &texture_base).ok_or_else(|| error("the texture file name is missing."))?;
+                material.diffuse_map = Some(texture);
+            },
+            | "map_Ke" => {
+                let (texture, _) = read_texture_map(tokens, &texture_base).ok_or_else(|| error("the texture file name is missing."))?;
+                material.emissive_map = Some(texture);
+            },
+            | "norm" | "bump" | "map_Bump" | "map_bump" => {
+                let (texture, bump_scale) = read_texture_map(tokens, &texture_base).ok_or_else(|| error("the texture file name is missing."))?;
+                material.normal_map = Some(texture);
+                material.normal_scale = bump_scale.unwrap_or(1.0);
+            },
+            | _ => {},
+        }
+    }
+
+    Ok(materials)
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        | Some(comment_start) => &line[..comment_start],
+        | None => line,
+    }
+}
+
+/// Join the remaining tokens of a statement, for the names that may contain spaces.
+fn rest_of_line<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<String> {
+
+    let rest = tokens.collect::<Vec<&str>>().join(" ");
+    if rest.is_empty() { None } else { Some(rest) }
+}
+
+/// Read up to N floats from `tokens`, where at least `required` floats must exist and the missing ones are filled with 0.
+///
+/// The tokens after the first N floats are ignored(etc. the `w` component of vertex).
+fn read_floats<'a, T: Default + AsMut<[f32]>>(tokens: &mut impl Iterator<Item = &'a str>, required: usize) -> Option<T> {
+
+    let mut result = T::default();
+    let components = result.as_mut();
+
+    for i in 0..components.len() {
+        match tokens.next().map(str::parse::<f32>) {
+            | Some(Ok(value)) => components[i] = value,
+            | Some(Err(_)) => return None,
+            | None => if i < required { return None } else { break },
+        }
+    }
+
+    Some(result)
+}
+
+/// Convert the 1-based(or negative relative) index of face vertex to the position in a list of `count` elements.
+fn resolve_index(element: &str, count: usize, element_name: &str) -> Result<usize, String> {
+
+    let index: i64 = element.parse()
+        .map_err(|_| format!("invalid {} index '{}'.", element_name, element))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        Err(format!("the {} index {} is out of range(there are {} defined).", element_name, index, count))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+/// Convert the Blinn-Phong specular exponent to the perceptual roughness of metallic-roughness model.
+fn roughness_from_exponent(exponent: f32) -> f32 {
+    // alpha = sqrt(2 / (n + 2)), and roughness = sqrt(alpha).
+    (2.0 / (exponent.max(0.0) + 2.0)).sqrt().sqrt()
+}
+
+/// Read the file name of texture map statement, skipping its options.
+///
+/// Return the path relative to the directory of OBJ file, and the value of `-bm` option if specified.
+fn read_texture_map<'a>(tokens: impl Iterator<Item = &'a str>, texture_base: &Path) -> Option<(String, Option<f32>)> {
+
+    let tokens: Vec<&str> = tokens.collect();
+    let mut bump_scale = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+
+        // the count of arguments of each option, where `-o`, `-s` and `-t` take 1 ~ 3 numbers.
+        let argument_count = match tokens[i] {
+            | "-blendu" | "-blendv" | "-boost" | "-cc" | "-clamp" | "-imfchan" | "-texres" | "-bm" | "-type" => 1,
+            | "-mm" => 2,
+            | "-o" | "-s" | "-t" => {
+                tokens[(i + 1)..].iter().take(3)
+                    .take_while(|token| token.parse::<f32>().is_ok())
+                    .count()
+            },
+            | _ => break,
+        };
+
+        if tokens[i] == "-bm" {
+            bump_scale = tokens.get(i + 1).and_then(|token| token.parse().ok());
+        }
+        i += 1 + argument_count;
+    }
+
+    let file_name = tokens.get(i..).map(|names| names.join(" "))
+        .filter(|name| !name.is_empty())?;
+    // backslashes are used as separator by the files exported on Windows.
+    let texture_path: PathBuf = texture_base.join(file_name.replace('\\', "/"));
+
+    Some((texture_path.to_string_lossy().replace('\\', "/"), bump_scale))
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,151 @@
+#version 450
+
+// Reference shader for glTF metallic-roughness material.
+
+layout (location = 0) in vec3 inWorldPos;
+layout (location = 1) in vec3 inNormal;
+layout (location = 2) in vec2 inUV;
+#ifdef HAS_TANGENT
+layout (location = 3) in vec4 inTangent;
+#endif
+
+layout (location = 0) out vec4 outFragColor;
+
+layout (set = 0, binding = 0) uniform UBO {
+	mat4 projection;
+	mat4 view;
+	mat4 model;
+	vec4 camPos;
+	vec4 lightDir;
+} ubo;
+
+layout (set = 1, binding = 0) uniform sampler2D baseColorMap;
+layout (set = 1, binding = 1) uniform sampler2D metallicRoughnessMap;
+layout (set = 1, binding = 2) uniform sampler2D normalMap;
+layout (set = 1, binding = 3) uniform sampler2D occlusionMap;
+layout (set = 1, binding = 4) uniform sampler2D emissiveMap;
+
+layout (push_constant) uniform Material {
+	vec4 base_color_factor;
+	vec3 emissive_factor;
+	float metallic_factor;
+	float roughness_factor;
+	float normal_scale;
+	float occlusion_strength;
+	float alpha_cutoff;
+	uint alpha_mode;
+} material;
+
+const uint ALPHA_MODE_MASK = 1;
+const float PI = 3.14159265359;
+const float MIN_ROUGHNESS = 0.04;
+
+// Perturb the normal with normal map, using the tangent frame built from TANGENT attribute if it is in vertex layout,
+// or the tangent frame derived from screen-space derivatives otherwise.
+vec3 perturbNormal(vec3 N) {
+
+	vec3 tangentNormal = texture(normalMap, inUV).xyz * 2.0 - 1.0;
+	tangentNormal.xy *= material.normal_scale;
+
+#ifdef HAS_TANGENT
+	// the tangents are zero for the primitives whose tangents can not be generated, fall back to the derivatives in that case.
+	if (dot(inTangent.xyz, inTangent.xyz) > 1e-12) {
+
+		vec3 T = normalize(inTangent.xyz - N * dot(N, inTangent.xyz));
+		vec3 B = cross(N, T) * inTangent.w;
+		// N has been flipped for back faces, so flip T to keep the whole tangent frame flipped.
+		if (!gl_FrontFacing) {
+			T = -T;
+		}
+
+		return normalize(mat3(T, B, N) * normalize(tangentNormal));
+	}
+#endif
+
+	vec3 q1 = dFdx(inWorldPos);
+	vec3 q2 = dFdy(inWorldPos);
+	vec2 st1 = dFdx(inUV);
+	vec2 st2 = dFdy(inUV);
+
+	vec3 T = q1 * st2.t - q2 * st1.t;
+	// the derivatives are zero for degenerate uv, keep the interpolated normal in that case.
+	if (dot(T, T) < 1e-12) {
+		return N;
+	}
+	T = normalize(T - N * dot(N, T));
+	vec3 B = -normalize(cross(N, T));
+	mat3 TBN = mat3(T, B, N);
+
+	return normalize(TBN * normalize(tangentNormal));
+}
+
+// Normal distribution function(GGX/Trowbridge-Reitz).
+float distributionGGX(float NdotH, float alphaRoughness) {
+	float a2 = alphaRoughness * alphaRoughness;
+	float f = (NdotH * a2 - NdotH) * NdotH + 1.0;
+	return a2 / (PI * f * f);
+}
+
+// Geometric occlusion(Smith joint GGX).
+float visibilitySmithGGX(float NdotL, float NdotV, float alphaRoughness) {
+	float a2 = alphaRoughness * alphaRoughness;
+	float ggxV = NdotL * sqrt(NdotV * NdotV * (1.0 - a2) + a2);
+	float ggxL = NdotV * sqrt(NdotL * NdotL * (1.0 - a2) + a2);
+	return 0.5 / max(ggxV + ggxL, 1e-5);
+}
+
+vec3 fresnelSchlick(vec3 f0, float VdotH) {
+	return f0 + (vec3(1.0) - f0) * pow(1.0 - VdotH, 5.0);
+}
+
+void main() {
+
+	vec4 baseColor = texture(baseColorMap, inUV) * material.base_color_factor;
+
+	if (material.alpha_mode == ALPHA_MODE_MASK && baseColor.a < material.alpha_cutoff) {
+		discard;
+	}
+
+	// the metallic value is stored in blue channel, and the roughness value is stored in green channel.
+	vec4 metallicRoughness = texture(metallicRoughnessMap, inUV);
+	float metallic  = clamp(metallicRoughness.b * material.metallic_factor, 0.0, 1.0);
+	float roughness = clamp(metallicRoughness.g * material.roughness_factor, MIN_ROUGHNESS, 1.0);
+	float alphaRoughness = roughness * roughness;
+
+	vec3 N = normalize(inNormal);
+	// flip the normal of back faces for double-sided materials.
+	if (!gl_FrontFacing) {
+		N = -N;
+	}
+	N = perturbNormal(N);
+
+	vec3 V = normalize(ubo.camPos.xyz - inWorldPos);
+	vec3 L = normalize(-ubo.lightDir.xyz);
+	vec3 H = normalize(L + V);
+
+	float NdotL = clamp(dot(N, L), 0.0, 1.0);
+	float NdotV = clamp(abs(dot(N, V)), 0.001, 1.0);
+	float NdotH = clamp(dot(N, H), 0.0, 1.0);
+	float VdotH = clamp(dot(V, H), 0.0, 1.0);
+
+	vec3 f0 = mix(vec3(0.04), baseColor.rgb, metallic);
+	vec3 diffuseColor = baseColor.rgb * (1.0 - metallic) * (vec3(1.0) - f0);
+
+	vec3 F = fresnelSchlick(f0, VdotH);
+	float D = distributionGGX(NdotH, alphaRoughness);
+	float Vis = visibilitySmithGGX(NdotL, NdotV, alphaRoughness);
+
+	vec3 diffuse  = (vec3(1.0) - F) * diffuseColor / PI;
+	vec3 specular = F * D * Vis;
+	vec3 color = NdotL * (diffuse + specular);
+
+	// a constant ambient term, since there is no image based lighting.
+	color += baseColor.rgb * 0.03;
+
+	float occlusion = texture(occlusionMap, inUV).r;
+	color = mix(color, color * occlusion, material.occlusion_strength);
+
+	color += texture(emissiveMap, inUV).rgb * material.emissive_factor;
+
+	outFragColor = vec4(color, baseColor.a);
+}
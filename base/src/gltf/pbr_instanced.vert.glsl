@@ -0,0 +1,55 @@
+#version 450
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec3 inNormal;
+// the locations follow the order of attributes in vertex layout, where TANGENT is placed before TEXCOORD_0.
+#ifdef HAS_TANGENT
+layout (location = 2) in vec4 inTangent;
+layout (location = 3) in vec2 inUV;
+#else
+layout (location = 2) in vec2 inUV;
+#endif
+
+layout (location = 0) out vec3 outWorldPos;
+layout (location = 1) out vec3 outNormal;
+layout (location = 2) out vec2 outUV;
+#ifdef HAS_TANGENT
+layout (location = 3) out vec4 outTangent;
+#endif
+
+layout (set = 0, binding = 0) uniform UBO {
+	mat4 projection;
+	mat4 view;
+	mat4 model;
+	vec4 camPos;
+	vec4 lightDir;
+} ubo;
+
+struct InstanceData {
+	mat4 transform;
+	uint morphIndex;
+};
+
+layout (set = 4, binding = 0) readonly buffer Instances {
+	InstanceData instances[];
+};
+
+out gl_PerVertex {
+	vec4 gl_Position;
+};
+
+void main() {
+
+	mat4 world = ubo.model * instances[gl_InstanceIndex].transform;
+	vec4 worldPos = world * vec4(inPos, 1.0);
+
+	outWorldPos = worldPos.xyz / worldPos.w;
+	outNormal = normalize(transpose(inverse(mat3(world))) * inNormal);
+	outUV = inUV;
+#ifdef HAS_TANGENT
+	// the tangent is normalized in fragment shader, since it may be zero. The handedness of bitangent is kept in w component.
+	outTangent = vec4(mat3(world) * inTangent.xyz, inTangent.w);
+#endif
+
+	gl_Position = ubo.projection * ubo.view * worldPos;
+}
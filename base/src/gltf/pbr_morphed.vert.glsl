@@ -0,0 +1,103 @@
+#version 450
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec3 inNormal;
+// the locations follow the order of attributes in vertex layout, where TANGENT is placed before TEXCOORD_0.
+#ifdef HAS_TANGENT
+layout (location = 2) in vec4 inTangent;
+layout (location = 3) in vec2 inUV;
+#else
+layout (location = 2) in vec2 inUV;
+#endif
+
+layout (location = 0) out vec3 outWorldPos;
+layout (location = 1) out vec3 outNormal;
+layout (location = 2) out vec2 outUV;
+#ifdef HAS_TANGENT
+layout (location = 3) out vec4 outTangent;
+#endif
+
+layout (set = 0, binding = 0) uniform UBO {
+	mat4 projection;
+	mat4 view;
+	mat4 model;
+	vec4 camPos;
+	vec4 lightDir;
+} ubo;
+
+layout (set = 0, binding = 1) uniform NodeAttachments {
+	mat4 transform;
+} node_attachments;
+
+struct MorphPrimitive {
+	uint deltaOffset;
+	uint firstVertex;
+	uint vertexCount;
+	uint targetCount;
+	uint weightOffset;
+};
+
+struct MorphDelta {
+	vec4 position;
+	vec4 normal;
+	vec4 tangent;
+};
+
+// the index of morph primitive of current draw, which is pushed after the material data in fragment shader.
+layout (push_constant) uniform MorphPush {
+	layout (offset = 52) uint morphIndex;
+} morph_push;
+
+layout (set = 3, binding = 0) readonly buffer MorphPrimitives {
+	MorphPrimitive primitives[];
+} morph_primitives;
+
+layout (set = 3, binding = 1) readonly buffer MorphDeltas {
+	MorphDelta deltas[];
+} morph_deltas;
+
+layout (set = 3, binding = 2) readonly buffer MorphWeights {
+	float weights[];
+} morph_weights;
+
+out gl_PerVertex {
+	vec4 gl_Position;
+};
+
+void main() {
+
+	vec3 position = inPos;
+	vec3 normal = inNormal;
+#ifdef HAS_TANGENT
+	vec3 tangent = inTangent.xyz;
+#endif
+
+	// 0 means the primitive has no morph target.
+	MorphPrimitive primitive = morph_primitives.primitives[morph_push.morphIndex];
+	uint localVertex = gl_VertexIndex - primitive.firstVertex;
+
+	for (uint target = 0; target < primitive.targetCount; target++) {
+
+		float weight = morph_weights.weights[primitive.weightOffset + target];
+		MorphDelta delta = morph_deltas.deltas[primitive.deltaOffset + target * primitive.vertexCount + localVertex];
+
+		position += weight * delta.position.xyz;
+		normal   += weight * delta.normal.xyz;
+#ifdef HAS_TANGENT
+		tangent  += weight * delta.tangent.xyz;
+#endif
+	}
+
+	mat4 world = ubo.model * node_attachments.transform;
+	vec4 worldPos = world * vec4(position, 1.0);
+
+	outWorldPos = worldPos.xyz / worldPos.w;
+	outNormal = normalize(transpose(inverse(mat3(world))) * normal);
+	outUV = inUV;
+#ifdef HAS_TANGENT
+	// the tangent is normalized in fragment shader, since it may be zero. The handedness of bitangent is kept in w component.
+	outTangent = vec4(mat3(world) * tangent, inTangent.w);
+#endif
+
+	gl_Position = ubo.projection * ubo.view * worldPos;
+}
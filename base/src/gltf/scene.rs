@@ -1,26 +1,129 @@
 
-use crate::gltf::asset::{ReferenceIndex, AssetElementList};
+use ash::vk;
+
+use crate::gltf::asset::{GltfDocument, ReferenceIndex, AssetElementList};
 use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 use crate::gltf::nodes::{Node, NodeAttachments};
+use crate::gltf::meshes::Mesh;
+use crate::gltf::material::{MaterialResource, AlphaMode};
+use crate::gltf::cameras::GltfCamera;
+use crate::gltf::lights::{GltfLight, read_lights};
+use crate::gltf::bounds::{Frustum, CullingStats};
 use crate::command::{VkCmdRecorder, IGraphics};
+use crate::error::{VkResult, VkError};
 use crate::Mat4F;
 
+use serde_derive::{Serialize, Deserialize};
+
+use std::cmp::Ordering;
+
 
+#[derive(Serialize, Deserialize)]
 pub struct Scene {
 
+    /// the root nodes of all loaded scenes, without duplication.
+    nodes: Vec<ReferenceIndex>,
+    /// the glTF scenes loaded from file.
+    scenes: Vec<SceneRoots>,
+    /// the position of the scene to render in `scenes`.
+    active_scene: usize,
+
+    /// the cameras referenced by the nodes of loaded scenes.
+    cameras: Vec<GltfCamera>,
+    /// the lights referenced by the nodes of loaded scenes.
+    lights: Vec<GltfLight>,
+    /// the transform applied to the model when loading.
+    transform: Option<Mat4F>,
+}
+
+/// A glTF scene and its primitives to draw.
+#[derive(Serialize, Deserialize)]
+struct SceneRoots {
+
+    name: Option<String>,
     /// a scene may contain multiple glTF::Node.
     nodes: Vec<ReferenceIndex>,
+    /// all primitives in this scene, ordered by opaque, mask and blend materials.
+    /// The draws are collected in allocation, so they are not cooked.
+    #[serde(skip)]
+    draws: Vec<DrawItem>,
+    /// the position of first primitive with blend material in `draws`.
+    #[serde(skip)]
+    blend_start: usize,
+}
+
+/// A primitive referenced by a node in scene.
+#[derive(Debug, Clone)]
+pub struct DrawItem {
+    /// the json index of node that references the mesh.
+    pub node: ReferenceIndex,
+    /// the json index of mesh containing the primitive.
+    pub mesh: ReferenceIndex,
+    /// the position of primitive in its mesh.
+    pub primitive: usize,
 }
 
 impl Scene {
 
-    pub fn from_doc(doc_scene: gltf::Scene) -> Scene {
+    /// Create the scene from the glTF scenes to load, where `active_scene` is the position of the scene to render in `doc_scenes`.
+    pub fn from_doc(doc_scenes: Vec<gltf::Scene>, active_scene: usize) -> Scene {
 
-        let nodes = doc_scene.nodes()
-            .map(|doc_node| doc_node.index())
+        let scenes: Vec<SceneRoots> = doc_scenes.into_iter().map(|doc_scene| {
+            SceneRoots {
+                name: doc_scene.name().map(String::from),
+                nodes: doc_scene.nodes().map(|doc_node| doc_node.index()).collect(),
+                draws: Vec::new(),
+                blend_start: 0,
+            }
+        }).collect();
+
+        // a node may be the root of multiple scenes.
+        let mut nodes: Vec<ReferenceIndex> = Vec::new();
+        for root_index in scenes.iter().flat_map(|scene| scene.nodes.iter()) {
+            if !nodes.contains(root_index) {
+                nodes.push(*root_index);
+            }
+        }
+
+        Scene {
+            nodes, scenes, active_scene,
+            cameras: Vec::new(),
+            lights: Vec::new(),
+            transform: None,
+        }
+    }
+
+    /// Read the cameras and `KHR_lights_punctual` lights referenced by the nodes of loaded scenes.
+    pub fn read_cameras_and_lights(&mut self, source: &GltfDocument) -> VkResult<()> {
+
+        let scene_nodes = self.descendant_nodes(source);
+
+        self.cameras = scene_nodes.iter()
+            .filter_map(|node_index| source.doc.nodes().nth(*node_index))
+            .filter_map(|doc_node| GltfCamera::from_doc(&doc_node))
             .collect();
+        self.lights = read_lights(&source.json, &scene_nodes)?;
+        self.transform = source.transform;
+
+        Ok(())
+    }
+
+    /// Return the json index of root nodes and all their descendants.
+    fn descendant_nodes(&self, source: &GltfDocument) -> Vec<ReferenceIndex> {
+
+        let doc_nodes: Vec<gltf::Node> = source.doc.nodes().collect();
+        let mut result = Vec::new();
+        let mut pending = self.nodes.clone();
+
+        while let Some(node_index) = pending.pop() {
+            if !result.contains(&node_index) {
+                result.push(node_index);
+                pending.extend(doc_nodes[node_index].children().map(|doc_child| doc_child.index()));
+            }
+        }
 
-        Scene { nodes }
+        result.sort();
+        result
     }
 
     pub fn read_node_attachment(&self, nodes: &AssetElementList<Node>, attachments: &mut NodeAttachments) {
@@ -31,13 +134,162 @@ impl Scene {
         }
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    /// the json index of root nodes in all loaded scenes.
+    pub fn root_nodes(&self) -> &[ReferenceIndex] {
+        &self.nodes
+    }
 
-        for node_json_index in self.nodes.iter().cloned() {
+    pub fn scene_count(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn scene_name(&self, scene_index: usize) -> Option<&str> {
+        self.scenes.get(scene_index)
+            .and_then(|scene| scene.name.as_ref().map(String::as_str))
+    }
 
-            let node = model.nodes.list.get(node_json_index);
-            node.record_command(recorder, model, params);
+    pub fn active_scene(&self) -> usize {
+        self.active_scene
+    }
+
+    pub fn set_active_scene(&mut self, scene_index: usize) -> VkResult<()> {
+
+        if scene_index >= self.scenes.len() {
+            return Err(VkError::custom(format!("The glTF model does not contain scene {}.", scene_index)))
         }
+
+        self.active_scene = scene_index;
+        Ok(())
+    }
+
+    /// the primitives of scene at `scene_index`, ordered by opaque, mask and blend materials.
+    pub fn scene_draws(&self, scene_index: usize) -> &[DrawItem] {
+        &self.scenes[scene_index].draws
+    }
+
+    pub fn cameras(&self) -> &[GltfCamera] {
+        &self.cameras
+    }
+
+    pub fn lights(&self) -> &[GltfLight] {
+        &self.lights
+    }
+
+    pub fn transform(&self) -> &Option<Mat4F> {
+        &self.transform
+    }
+
+    /// Collect the primitives of each scene, and sort them by the alpha mode of their materials.
+    pub fn read_draws(&mut self, nodes: &AssetElementList<Node>, meshes: &AssetElementList<Mesh>, materials: &MaterialResource) {
+
+        for scene in self.scenes.iter_mut() {
+            scene.read_draws(nodes, meshes, materials);
+        }
+    }
+
+    /// Record the draw commands of active scene. If `frustum` is specified, the primitives outside it are skipped.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, frustum: Option<&Frustum>) -> CullingStats {
+        self.scenes[self.active_scene].record_command(recorder, model, params, frustum)
     }
 }
 
+impl SceneRoots {
+
+    fn read_draws(&mut self, nodes: &AssetElementList<Node>, meshes: &AssetElementList<Mesh>, materials: &MaterialResource) {
+
+        let mut draws = Vec::new();
+        for node_json_index in self.nodes.iter().cloned() {
+            let node = nodes.get(node_json_index);
+            node.read_draws(nodes, meshes, &mut draws);
+        }
+
+        let alpha_mode = |draw: &DrawItem| {
+            let primitive = &meshes.get(draw.mesh).primitives()[draw.primitive];
+            materials.get_material_mode(primitive.material()).alpha_mode
+        };
+
+        // opaque primitives are drawn first, then the masked ones, and the blended ones at last.
+        // the sort is stable, so the primitives with the same alpha mode keep their scene order.
+        draws.sort_by_key(|draw| match alpha_mode(draw) {
+            | AlphaMode::Opaque => 0,
+            | AlphaMode::Mask   => 1,
+            | AlphaMode::Blend  => 2,
+        });
+
+        self.blend_start = draws.iter()
+            .position(|draw| alpha_mode(draw) == AlphaMode::Blend)
+            .unwrap_or(draws.len());
+        self.draws = draws;
+    }
+
+    fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, frustum: Option<&Frustum>) -> CullingStats {
+
+        let (solid_draws, blend_draws) = self.draws.split_at(self.blend_start);
+
+        let mut blend_draws: Vec<&DrawItem> = blend_draws.iter().collect();
+        if let Some(view_position) = params.view_position {
+
+            // the nodes may be moved by scene graph or animations, so the centers are calculated from their current world transforms.
+            let view_distance = |draw: &DrawItem| {
+                let primitive = &model.meshes.list.get(draw.mesh).primitives()[draw.primitive];
+                let transform = model.nodes.attached_transform(draw.node).unwrap_or_else(Mat4F::identity);
+                (transform.mul_point(primitive.center()) - view_position).magnitude_squared()
+            };
+
+            // blended primitives are drawn from back to front.
+            let mut distances: Vec<(f32, &DrawItem)> = blend_draws.into_iter()
+                .map(|draw| (view_distance(draw), draw))
+                .collect();
+            distances.sort_by(|(distance1, _), (distance2, _)| {
+                distance2.partial_cmp(distance1).unwrap_or(Ordering::Equal)
+            });
+            blend_draws = distances.into_iter().map(|(_, draw)| draw).collect();
+        }
+
+        let mut stats = CullingStats::default();
+        let mut bound_node = None;
+        // `MeshResource::record_command` binds the 32-bit indices.
+        let mut bound_index_type = vk::IndexType::UINT32;
+        for draw in solid_draws.iter().chain(blend_draws.into_iter()) {
+
+            let node = model.nodes.list.get(draw.node);
+            let primitive = &model.meshes.list.get(draw.mesh).primitives()[draw.primitive];
+
+            // skip the primitives whose topology does not match the bound pipeline.
+            if params.topology.map_or(false, |topology| topology != primitive.topology()) {
+                continue
+            }
+
+            if let Some(frustum) = frustum {
+
+                // the vertices of skinned or morphed primitives are moved in shader, so their bounding boxes are unknown here.
+                let is_deformed = node.is_skinned() || primitive.is_morphed();
+                let is_visible = is_deformed || model.nodes.attached_transform(draw.node)
+                    .map_or(true, |transform| frustum.intersects(&primitive.bounds().transform(&transform)));
+
+                if !is_visible {
+                    stats.culled += 1;
+                    continue
+                }
+            }
+            stats.visible += 1;
+
+            // skip rebinding the node attachment for the primitives of the same node.
+            if bound_node != Some(draw.node) {
+                node.bind_attachment(recorder, model, params);
+                bound_node = Some(draw.node);
+            }
+
+            if let Some(index_type) = primitive.render_params().index_type() {
+                if index_type != bound_index_type {
+                    model.meshes.bind_indices(recorder, index_type);
+                    bound_index_type = index_type;
+                }
+            }
+
+            primitive.record_command(recorder, model, params);
+        }
+
+        stats
+    }
+}
@@ -0,0 +1,356 @@
+
+use crate::error::{VkResult, VkError, VkErrorKind};
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+// --------------------------------------------------------------------------------------
+/// Resolve the external buffers and images referenced by URI in glTF file.
+///
+/// Closures with signature `Fn(&str) -> VkResult<Vec<u8>>` can be used as resolver directly.
+pub trait UriResolver {
+
+    /// Return the bytes of the resource at `uri`, which is the relative URI written in glTF file.
+    fn resolve(&self, uri: &str) -> VkResult<Vec<u8>>;
+}
+
+impl<F> UriResolver for F where F: Fn(&str) -> VkResult<Vec<u8>> {
+
+    fn resolve(&self, uri: &str) -> VkResult<Vec<u8>> {
+        self(uri)
+    }
+}
+
+/// Resolve the URIs relative to a directory in filesystem.
+pub struct FileResolver {
+    base: PathBuf,
+}
+
+impl FileResolver {
+
+    pub fn new(base: impl AsRef<Path>) -> FileResolver {
+        FileResolver { base: base.as_ref().to_path_buf() }
+    }
+}
+
+impl UriResolver for FileResolver {
+
+    fn resolve(&self, uri: &str) -> VkResult<Vec<u8>> {
+        let path = self.base.join(uri);
+        ::std::fs::read(&path)
+            .or(Err(VkError::path(path)))
+    }
+}
+
+/// A resolver that rejects all external resources, for the glTF files whose buffers and images are embedded.
+pub struct EmbeddedResolver;
+
+impl UriResolver for EmbeddedResolver {
+
+    fn resolve(&self, _uri: &str) -> VkResult<Vec<u8>> {
+        Err(VkError::custom("External resources are not allowed for this glTF source."))
+    }
+}
+
+/// Where to load glTF or GLB file from.
+pub enum GltfSource<'a> {
+    /// Load the file at path, and resolve the external resources relative to its directory.
+    File(&'a Path),
+    /// Load the glTF or GLB from bytes in memory, and resolve the external resources by `resolver`.
+    Slice {
+        data: &'a [u8],
+        resolver: &'a dyn UriResolver,
+    },
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Import the glTF document, its buffers and images, and the raw json from `source`.
+pub fn import(source: &GltfSource) -> VkResult<(gltf::Document, Vec<gltf::buffer::Data>, Vec<gltf::image::Data>, Value)> {
+
+    match source {
+        | GltfSource::File(path) => {
+
+            let data = ::std::fs::read(path)
+                .or(Err(VkError::path(path)))?;
+            let base = path.parent().unwrap_or(Path::new(""));
+
+            import_slice(&data, &FileResolver::new(base))
+        },
+        | GltfSource::Slice { data, resolver } => {
+            import_slice(data, *resolver)
+        },
+    }
+}
+
+fn import_slice(data: &[u8], resolver: &dyn UriResolver) -> VkResult<(gltf::Document, Vec<gltf::buffer::Data>, Vec<gltf::image::Data>, Value)> {
+
+    let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(data)
+        .map_err(translate_parse_error)?;
+
+    let json: Value = if data.starts_with(b"glTF") {
+        let glb = gltf::Glb::from_slice(data)
+            .map_err(VkErrorKind::ParseGltf)?;
+        serde_json::from_slice(&glb.json)
+    } else {
+        serde_json::from_slice(data)
+    }.map_err(|e| VkError::custom(format!("Failed to parse glTF json: {}", e)))?;
+
+    let mut buffers = Vec::new();
+    for doc_buffer in document.buffers() {
+
+        let (uri, mut bytes) = match doc_buffer.source() {
+            | gltf::buffer::Source::Bin => {
+                let bytes = blob.take()
+                    .ok_or(VkError::gltf_resource("<GLB binary chunk>", "the binary chunk is missing."))?;
+                (String::from("<GLB binary chunk>"), bytes)
+            },
+            | gltf::buffer::Source::Uri(uri) => {
+                (uri.to_string(), load_uri(uri, resolver)?)
+            },
+        };
+
+        if bytes.len() < doc_buffer.length() {
+            return Err(VkError::gltf_resource(uri, format!("expect {} bytes, but only {} bytes are loaded.", doc_buffer.length(), bytes.len())))
+        }
+
+        // the buffer data is padded to 4 bytes, as `gltf::import` does.
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        buffers.push(gltf::buffer::Data(bytes));
+    }
+
+    validate_accessors(&json, &document, &buffers)?;
+
+    let mut images = Vec::new();
+    for doc_image in document.images() {
+
+        let (uri, bytes) = match doc_image.source() {
+            | gltf::image::Source::View { view, .. } => {
+                let uri = format!("bufferView {}", view.index());
+                let buffer = &buffers[view.buffer().index()];
+                let bytes = buffer.get(view.offset()..(view.offset() + view.length()))
+                    .ok_or(VkError::gltf_resource(&uri, "the buffer view is out of buffer range."))?
+                    .to_vec();
+                (uri, bytes)
+            },
+            | gltf::image::Source::Uri { uri, .. } => {
+                (uri.to_string(), load_uri(uri, resolver)?)
+            },
+        };
+
+        images.push(decode_image(&bytes).map_err(|e| VkError::gltf_resource(uri, e.to_string()))?);
+    }
+
+    Ok((document, buffers, images, json))
+}
+
+/// Load the bytes of a data URI or an external resource.
+fn load_uri(uri: &str, resolver: &dyn UriResolver) -> VkResult<Vec<u8>> {
+
+    if uri.starts_with("data:") {
+
+        // the data URI is in the form of `data:[<media type>][;base64],<data>`.
+        let (header, content) = match uri.find(',') {
+            | Some(split) => (&uri[..split], &uri[(split + 1)..]),
+            | None => return Err(VkError::gltf_resource(abbreviate_uri(uri), "the data URI is malformed.")),
+        };
+
+        if header.ends_with(";base64") {
+            base64::decode(content)
+                .map_err(|e| VkError::gltf_resource(abbreviate_uri(uri), e.to_string()))
+        } else {
+            Err(VkError::gltf_resource(abbreviate_uri(uri), "only base64 data URI is supported."))
+        }
+    } else {
+        resolver.resolve(uri)
+            .map_err(|e| VkError::gltf_resource(uri, e.to_string()))
+    }
+}
+
+/// Shorten the data URI in error message.
+fn abbreviate_uri(uri: &str) -> String {
+    uri.chars().take(32).collect::<String>() + "..."
+}
+
+fn decode_image(bytes: &[u8]) -> VkResult<gltf::image::Data> {
+
+    use image::DynamicImage;
+    use gltf::image::Format;
+
+    let image = image::load_from_memory(bytes)
+        .map_err(VkErrorKind::Image)?;
+
+    let (pixels, format, width, height) = match image {
+        | DynamicImage::ImageLuma8(pixels) => {
+            let (width, height) = pixels.dimensions();
+            (pixels.into_raw(), Format::R8, width, height)
+        },
+        | DynamicImage::ImageLumaA8(pixels) => {
+            let (width, height) = pixels.dimensions();
+            (pixels.into_raw(), Format::R8G8, width, height)
+        },
+        | DynamicImage::ImageRgb8(pixels) => {
+            let (width, height) = pixels.dimensions();
+            (pixels.into_raw(), Format::R8G8B8, width, height)
+        },
+        | other => {
+            let pixels = other.to_rgba();
+            let (width, height) = pixels.dimensions();
+            (pixels.into_raw(), Format::R8G8B8A8, width, height)
+        },
+    };
+
+    Ok(gltf::image::Data { pixels, format, width, height })
+}
+
+/// Check that the data read by each accessor lies in its buffer view and buffer, so that the invalid accessors are reported before reading.
+fn validate_accessors(json: &Value, document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> VkResult<()> {
+
+    let empty = Vec::new();
+    let json_views = json.get("bufferViews").and_then(Value::as_array).unwrap_or(&empty);
+    let json_accessors = json.get("accessors").and_then(Value::as_array).unwrap_or(&empty);
+
+    let number = |value: &Value, key: &str| value.get(key).and_then(Value::as_u64).map(|v| v as usize);
+
+    for (accessor_index, json_accessor) in json_accessors.iter().enumerate() {
+
+        // the accessors without buffer view are filled with zeros.
+        let view_index = match number(json_accessor, "bufferView") {
+            | Some(view_index) => view_index,
+            | None => continue,
+        };
+
+        let json_view = json_views.get(view_index)
+            .ok_or(VkError::gltf_accessor(accessor_index, format!("buffer view {} does not exist.", view_index)))?;
+
+        let component_size = component_size(number(json_accessor, "componentType").unwrap_or(0));
+        let component_count = component_count(json_accessor.get("type").and_then(Value::as_str).unwrap_or("SCALAR"));
+
+        let element_size = component_size * component_count;
+        let count = number(json_accessor, "count").unwrap_or(0);
+        let stride = number(json_view, "byteStride").unwrap_or(element_size);
+        let accessor_offset = number(json_accessor, "byteOffset").unwrap_or(0);
+
+        let view_offset = number(json_view, "byteOffset").unwrap_or(0);
+        let view_length = number(json_view, "byteLength").unwrap_or(0);
+        let buffer_index = number(json_view, "buffer").unwrap_or(0);
+
+        if count > 0 && accessor_offset + stride * (count - 1) + element_size > view_length {
+            return Err(VkError::gltf_accessor(accessor_index, format!("reading {} elements exceeds the length of buffer view {}.", count, view_index)))
+        }
+
+        let buffer_length = buffers.get(buffer_index).map(|buffer| buffer.len())
+            .ok_or(VkError::gltf_accessor(accessor_index, format!("buffer {} does not exist.", buffer_index)))?;
+
+        if view_offset + view_length > buffer_length {
+            let buffer_uri = match document.buffers().nth(buffer_index).map(|doc_buffer| doc_buffer.source()) {
+                | Some(gltf::buffer::Source::Uri(uri)) => uri.to_string(),
+                | _ => String::from("<GLB binary chunk>"),
+            };
+            return Err(VkError::gltf_accessor(accessor_index, format!("buffer view {} exceeds the length of buffer '{}'.", view_index, buffer_uri)))
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the elements of accessor as floats, converting the normalized integers to [0, 1] or [-1, 1].
+///
+/// Return the flat component values and the count of components of each element.
+/// This is used for the accessors referenced by extensions, which the glTF crate does not read.
+pub fn read_accessor_floats(json: &Value, buffers: &[gltf::buffer::Data], accessor_index: usize) -> VkResult<(Vec<f32>, usize)> {
+
+    let number = |value: &Value, key: &str| value.get(key).and_then(Value::as_u64).map(|v| v as usize);
+
+    let json_accessor = json.get("accessors").and_then(|accessors| accessors.get(accessor_index))
+        .ok_or(VkError::gltf_accessor(accessor_index, "the accessor does not exist."))?;
+
+    let component_type = number(json_accessor, "componentType").unwrap_or(0);
+    let component_size = component_size(component_type);
+    let component_count = component_count(json_accessor.get("type").and_then(Value::as_str).unwrap_or("SCALAR"));
+    let count = number(json_accessor, "count").unwrap_or(0);
+    let is_normalized = json_accessor.get("normalized").and_then(Value::as_bool).unwrap_or(false);
+
+    // the accessors without buffer view are filled with zeros.
+    let view_index = match number(json_accessor, "bufferView") {
+        | Some(view_index) => view_index,
+        | None => return Ok((vec![0.0; count * component_count], component_count)),
+    };
+
+    let json_view = json.get("bufferViews").and_then(|views| views.get(view_index))
+        .ok_or(VkError::gltf_accessor(accessor_index, format!("buffer view {} does not exist.", view_index)))?;
+    let buffer = number(json_view, "buffer").and_then(|buffer_index| buffers.get(buffer_index))
+        .ok_or(VkError::gltf_accessor(accessor_index, "the buffer of accessor does not exist."))?;
+
+    let start = number(json_view, "byteOffset").unwrap_or(0) + number(json_accessor, "byteOffset").unwrap_or(0);
+    let stride = number(json_view, "byteStride").unwrap_or(component_size * component_count);
+
+    let mut values = Vec::with_capacity(count * component_count);
+    for element_index in 0..count {
+        for component_index in 0..component_count {
+
+            let offset = start + stride * element_index + component_size * component_index;
+            let bytes = buffer.get(offset..(offset + component_size))
+                .ok_or(VkError::gltf_accessor(accessor_index, "reading the element exceeds the length of buffer."))?;
+
+            let value = match component_type {
+                | 5126 => f32::from_bits(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+                | 5120 => (bytes[0] as i8) as f32,
+                | 5121 => bytes[0] as f32,
+                | 5122 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                | 5123 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                | _ => return Err(VkError::gltf_accessor(accessor_index, format!("component type {} is not supported.", component_type))),
+            };
+
+            let value = match component_type {
+                | 5120 if is_normalized => (value / 127.0).max(-1.0),
+                | 5121 if is_normalized => value / 255.0,
+                | 5122 if is_normalized => (value / 32767.0).max(-1.0),
+                | 5123 if is_normalized => value / 65535.0,
+                | _ => value,
+            };
+            values.push(value);
+        }
+    }
+
+    Ok((values, component_count))
+}
+
+/// The byte size of accessor component type.
+fn component_size(component_type: usize) -> usize {
+    match component_type {
+        | 5120 | 5121 => 1, // BYTE, UNSIGNED_BYTE
+        | 5122 | 5123 => 2, // SHORT, UNSIGNED_SHORT
+        | _ => 4,           // UNSIGNED_INT, FLOAT
+    }
+}
+
+/// The count of components of accessor type.
+fn component_count(accessor_type: &str) -> usize {
+    match accessor_type {
+        | "VEC2" => 2,
+        | "VEC3" => 3,
+        | "VEC4" | "MAT2" => 4,
+        | "MAT3" => 9,
+        | "MAT4" => 16,
+        | _ => 1,
+    }
+}
+
+/// Report the json path of each validation error instead of a generic parse error.
+fn translate_parse_error(error: gltf::Error) -> VkError {
+
+    match error {
+        | gltf::Error::Validation(errors) => {
+            let description = errors.iter()
+                .map(|(path, error)| format!("{}: {}", path, error))
+                .collect::<Vec<String>>()
+                .join("; ");
+            VkError::custom(format!("Invalid glTF document: {}", description))
+        },
+        | _ => VkError::from(VkErrorKind::ParseGltf(error)),
+    }
+}
+// --------------------------------------------------------------------------------------
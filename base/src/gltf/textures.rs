@@ -0,0 +1,346 @@
+
+use ash::vk;
+
+use crate::gltf::asset::{GltfDocument, AssetAbstract, ReferenceIndex};
+use crate::gltf::scene::Scene;
+
+use crate::ci::image::SamplerCI;
+use crate::ci::VkObjectBuildableCI;
+use crate::context::VkDevice;
+use crate::texture::{TextureLoadOptions, TextureImage, upload_pixels};
+use crate::error::{VkResult, VkError};
+use crate::vkfloat;
+
+use serde_derive::{Serialize, Deserialize};
+
+use std::collections::HashSet;
+
+/// The byte size of a RGBA8 pixel.
+const RGBA_PIXEL_SIZE: usize = 4;
+/// The pixel of the default texture for missing color, metallic-roughness, occlusion and emissive textures.
+const DEFAULT_WHITE_PIXEL : [u8; 4] = [255, 255, 255, 255];
+/// The pixel of the default texture for missing normal texture, which is a flat normal in tangent space.
+const DEFAULT_NORMAL_PIXEL: [u8; 4] = [128, 128, 255, 255];
+
+// ------------------------------------------------------------------------------------
+/// The texture to sample from when a material does not reference any texture for a slot.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DefaultTexture {
+    White,
+    Normal,
+}
+
+/// The pixels of a glTF image, expanded to RGBA8.
+#[derive(Serialize, Deserialize)]
+struct ImagePixels {
+    pixels: Vec<u8>,
+    #[serde(with = "crate::gltf::cache::vk_serde::extent_2d")]
+    extent: vk::Extent2D,
+    /// whether this image is referenced as color data(base color or emissive texture) by any material.
+    is_srgb: bool,
+}
+
+/// The sampler parameters translated from glTF sampler.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct SamplerParams {
+    #[serde(with = "crate::gltf::cache::vk_serde::filter")]
+    mag_filter: vk::Filter,
+    #[serde(with = "crate::gltf::cache::vk_serde::filter")]
+    min_filter: vk::Filter,
+    #[serde(with = "crate::gltf::cache::vk_serde::sampler_mipmap_mode")]
+    mipmap_mode: vk::SamplerMipmapMode,
+    #[serde(with = "crate::gltf::cache::vk_serde::sampler_address_mode")]
+    address_u: vk::SamplerAddressMode,
+    #[serde(with = "crate::gltf::cache::vk_serde::sampler_address_mode")]
+    address_v: vk::SamplerAddressMode,
+    /// indicate if the min filter of glTF sampler reads mipmaps.
+    is_use_mipmap: bool,
+}
+
+/// The reference of glTF texture to its image and sampler.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct TextureRef {
+    image: ReferenceIndex,
+    sampler: Option<ReferenceIndex>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TextureAsset {
+
+    /// the images indexed by their json index.
+    images: Vec<ImagePixels>,
+    /// the samplers indexed by their json index.
+    samplers: Vec<SamplerParams>,
+    /// the textures indexed by their json index.
+    textures: Vec<TextureRef>,
+}
+
+impl TextureAsset {
+
+    pub fn new() -> TextureAsset {
+        TextureAsset {
+            images  : Vec::new(),
+            samplers: Vec::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    pub fn allocate(self, device: &mut VkDevice) -> VkResult<TextureResource> {
+
+        let mut images = Vec::with_capacity(self.images.len());
+        for image in self.images.iter() {
+            let format = if image.is_srgb { vk::Format::R8G8B8A8_SRGB } else { vk::Format::R8G8B8A8_UNORM };
+            images.push(upload_rgba_image(device, &image.pixels, image.extent, format)?);
+        }
+
+        let mut samplers = Vec::with_capacity(self.samplers.len());
+        for sampler in self.samplers.iter() {
+            samplers.push(create_sampler(device, sampler)?);
+        }
+
+        let single_pixel = vk::Extent2D { width: 1, height: 1 };
+
+        let result = TextureResource {
+            images, samplers,
+            textures: self.textures,
+            default_sampler: create_sampler(device, &SamplerParams::default())?,
+            default_white : upload_rgba_image(device, &DEFAULT_WHITE_PIXEL, single_pixel, vk::Format::R8G8B8A8_UNORM)?,
+            default_normal: upload_rgba_image(device, &DEFAULT_NORMAL_PIXEL, single_pixel, vk::Format::R8G8B8A8_UNORM)?,
+        };
+        Ok(result)
+    }
+}
+
+impl AssetAbstract for TextureAsset {
+    const ASSET_NAME: &'static str = "Textures";
+
+    fn read_doc(&mut self, source: &GltfDocument, _scene: &Scene) -> VkResult<()> {
+
+        // the images referenced as color data are stored in sRGB color space.
+        let mut srgb_images = HashSet::new();
+        for doc_material in source.doc.materials() {
+
+            let base_color = doc_material.pbr_metallic_roughness().base_color_texture();
+            let emissive = doc_material.emissive_texture();
+
+            for texture_info in base_color.iter().chain(emissive.iter()) {
+                srgb_images.insert(texture_info.texture().source().index());
+            }
+        }
+
+        for doc_image in source.doc.images() {
+
+            let image_data = source.images.get(doc_image.index())
+                .ok_or(VkError::custom("glTF image data is missing."))?;
+
+            self.images.push(ImagePixels {
+                pixels: expand_to_rgba(image_data),
+                extent: vk::Extent2D { width: image_data.width, height: image_data.height },
+                is_srgb: srgb_images.contains(&doc_image.index()),
+            });
+        }
+
+        self.samplers = source.doc.samplers()
+            .map(SamplerParams::from)
+            .collect();
+
+        self.textures = source.doc.textures()
+            .map(|doc_texture| TextureRef {
+                image: doc_texture.source().index(),
+                sampler: doc_texture.sampler().index(),
+            })
+            .collect();
+
+        Ok(())
+    }
+}
+// ------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------
+pub struct TextureResource {
+
+    images: Vec<TextureImage>,
+    samplers: Vec<vk::Sampler>,
+    textures: Vec<TextureRef>,
+
+    /// the sampler for textures which do not specify sampler.
+    default_sampler: vk::Sampler,
+    default_white : TextureImage,
+    default_normal: TextureImage,
+}
+
+impl TextureResource {
+
+    /// Return the combined image sampler of the glTF texture at `texture_index`, or the default texture if it is None.
+    pub fn descriptor(&self, texture_index: Option<ReferenceIndex>, default: DefaultTexture) -> vk::DescriptorImageInfo {
+
+        let texture = texture_index.and_then(|index| self.textures.get(index));
+
+        let (image_view, sampler) = match texture {
+            | Some(texture) => {
+                let sampler = texture.sampler
+                    .and_then(|sampler_index| self.samplers.get(sampler_index).cloned())
+                    .unwrap_or(self.default_sampler);
+                (self.images[texture.image].view, sampler)
+            },
+            | None => {
+                let default_image = match default {
+                    | DefaultTexture::White  => &self.default_white,
+                    | DefaultTexture::Normal => &self.default_normal,
+                };
+                (default_image.view, self.default_sampler)
+            },
+        };
+
+        vk::DescriptorImageInfo {
+            sampler, image_view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        for sampler in self.samplers.into_iter() {
+            device.discard(sampler);
+        }
+        device.discard(self.default_sampler);
+
+        let all_images = self.images.into_iter()
+            .chain(Some(self.default_white))
+            .chain(Some(self.default_normal));
+
+        for image in all_images {
+            device.discard(image.view);
+            device.vma_discard(image.image)?;
+        }
+
+        Ok(())
+    }
+}
+// ------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------
+impl Default for SamplerParams {
+
+    /// glTF uses auto filtering and repeat wrapping for textures without sampler.
+    fn default() -> SamplerParams {
+        SamplerParams {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_u: vk::SamplerAddressMode::REPEAT,
+            address_v: vk::SamplerAddressMode::REPEAT,
+            is_use_mipmap: true,
+        }
+    }
+}
+
+impl From<gltf::texture::Sampler<'_>> for SamplerParams {
+
+    fn from(doc_sampler: gltf::texture::Sampler) -> SamplerParams {
+
+        use gltf::texture::{MagFilter, MinFilter};
+
+        let default = SamplerParams::default();
+
+        let mag_filter = match doc_sampler.mag_filter() {
+            | Some(MagFilter::Nearest) => vk::Filter::NEAREST,
+            | Some(MagFilter::Linear)  => vk::Filter::LINEAR,
+            | None => default.mag_filter,
+        };
+
+        let (min_filter, mipmap_mode, is_use_mipmap) = match doc_sampler.min_filter() {
+            | Some(MinFilter::Nearest) => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST, false),
+            | Some(MinFilter::Linear)  => (vk::Filter::LINEAR,  vk::SamplerMipmapMode::NEAREST, false),
+            | Some(MinFilter::NearestMipmapNearest) => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST, true),
+            | Some(MinFilter::LinearMipmapNearest)  => (vk::Filter::LINEAR,  vk::SamplerMipmapMode::NEAREST, true),
+            | Some(MinFilter::NearestMipmapLinear)  => (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR,  true),
+            | Some(MinFilter::LinearMipmapLinear)   => (vk::Filter::LINEAR,  vk::SamplerMipmapMode::LINEAR,  true),
+            | None => (default.min_filter, default.mipmap_mode, default.is_use_mipmap),
+        };
+
+        SamplerParams {
+            mag_filter, min_filter, mipmap_mode, is_use_mipmap,
+            address_u: translate_wrapping_mode(doc_sampler.wrap_s()),
+            address_v: translate_wrapping_mode(doc_sampler.wrap_t()),
+        }
+    }
+}
+
+fn translate_wrapping_mode(mode: gltf::texture::WrappingMode) -> vk::SamplerAddressMode {
+
+    use gltf::texture::WrappingMode;
+
+    match mode {
+        | WrappingMode::ClampToEdge    => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+        | WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        | WrappingMode::Repeat         => vk::SamplerAddressMode::REPEAT,
+    }
+}
+
+fn create_sampler(device: &VkDevice, params: &SamplerParams) -> VkResult<vk::Sampler> {
+
+    // the samplers without mipmap filter only sample the base level.
+    let max_lod = if params.is_use_mipmap { vk::LOD_CLAMP_NONE } else { 0.0 };
+
+    let mut sampler_ci = SamplerCI::new()
+        .filter(params.mag_filter, params.min_filter)
+        .mipmap(params.mipmap_mode)
+        .address(params.address_u, params.address_v, vk::SamplerAddressMode::REPEAT)
+        .lod(0.0, 0.0, max_lod as vkfloat)
+        .compare_op(Some(vk::CompareOp::NEVER))
+        .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
+
+    // Only enable anisotropic filtering if enabled on the device.
+    sampler_ci = if device.phy.features_enabled().sampler_anisotropy == vk::TRUE {
+        sampler_ci.anisotropy(Some(device.phy.limits.max_sampler_anisotropy))
+    } else {
+        sampler_ci.anisotropy(None)
+    };
+
+    sampler_ci.build(device)
+}
+
+fn upload_rgba_image(device: &mut VkDevice, pixels: &[u8], extent: vk::Extent2D, format: vk::Format) -> VkResult<TextureImage> {
+
+    // generate the mip chain on device if possible, since glTF images contain only the base level.
+    let options = TextureLoadOptions {
+        generate_mipmaps: device.mipmap_method(format).is_some(),
+        ..Default::default()
+    };
+
+    upload_pixels(device, pixels, extent, format, &options)
+}
+
+/// Expand the pixels of glTF image to RGBA8, where the missing color channels are filled with 0(or the red channel for grayscale image) and alpha is filled with 255.
+fn expand_to_rgba(image: &gltf::image::Data) -> Vec<u8> {
+
+    use gltf::image::Format;
+
+    let pixel_count = (image.width as usize) * (image.height as usize);
+    let mut rgba = Vec::with_capacity(pixel_count * RGBA_PIXEL_SIZE);
+
+    match image.format {
+        | Format::R8 => {
+            for r in image.pixels.iter().cloned() {
+                rgba.extend_from_slice(&[r, r, r, 255]);
+            }
+        },
+        | Format::R8G8 => {
+            for rg in image.pixels.chunks(2) {
+                rgba.extend_from_slice(&[rg[0], rg[1], 0, 255]);
+            }
+        },
+        | Format::R8G8B8 => {
+            for rgb in image.pixels.chunks(3) {
+                rgba.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        },
+        | Format::R8G8B8A8 => {
+            rgba.extend_from_slice(&image.pixels);
+        },
+    }
+
+    rgba
+}
+// ------------------------------------------------------------------------------------
@@ -4,7 +4,7 @@ pub use self::workflow::ProcPipeline;
 pub use self::error::{VkResult, VkError, VkErrorKind};
 pub use self::utils::frame::FrameAction;
 pub use self::input::EventController;
-pub use self::camera::FlightCamera;
+pub use self::camera::{FlightCamera, FlightCameraBuilder};
 
 pub mod context;
 pub mod ci;
@@ -13,6 +13,7 @@ pub mod command;
 pub mod platforms;
 pub mod gltf;
 pub mod texture;
+pub mod shapes;
 pub mod ui;
 
 mod error;
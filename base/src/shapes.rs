@@ -0,0 +1,17 @@
+//! Procedural meshes generated on CPU and uploaded in the vertex layout of caller.
+//!
+//! ```ignore
+//! let sphere = ShapeGeometry::uv_sphere(1.0, 32, 16)
+//!     .upload(device, AttributeFlags::ATTR_PNTE0)?;
+//! device.wait_uploads()?;
+//!
+//! // use `sphere.vertex_input` to create the pipeline, then in command buffer:
+//! sphere.record_command(&recorder, 1);
+//! ```
+
+pub use self::geometry::ShapeGeometry;
+pub use self::mesh::ShapeMesh;
+
+mod geometry;
+mod generators;
+mod mesh;
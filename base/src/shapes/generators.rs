@@ -0,0 +1,309 @@
+
+use crate::shapes::geometry::ShapeGeometry;
+use crate::{Vec2F, Vec3F};
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// --------------------------------------------------------------------------------------
+impl ShapeGeometry {
+
+    /// A cube of `size`, where each face is divided into `segments` x `segments` quads and mapped to the whole texture.
+    pub fn cube(size: f32, segments: u32) -> ShapeGeometry {
+
+        let segments = segments.max(1);
+        let half = size * 0.5;
+
+        // the normal, the direction of increasing u(right) and the direction of increasing v(down) of each face.
+        let faces = [
+            (Vec3F::new( 1.0, 0.0, 0.0), Vec3F::new(0.0, 0.0, -1.0), Vec3F::new(0.0, -1.0,  0.0)),
+            (Vec3F::new(-1.0, 0.0, 0.0), Vec3F::new(0.0, 0.0,  1.0), Vec3F::new(0.0, -1.0,  0.0)),
+            (Vec3F::new(0.0,  1.0, 0.0), Vec3F::new(1.0, 0.0,  0.0), Vec3F::new(0.0,  0.0,  1.0)),
+            (Vec3F::new(0.0, -1.0, 0.0), Vec3F::new(1.0, 0.0,  0.0), Vec3F::new(0.0,  0.0, -1.0)),
+            (Vec3F::new(0.0, 0.0,  1.0), Vec3F::new( 1.0, 0.0, 0.0), Vec3F::new(0.0, -1.0,  0.0)),
+            (Vec3F::new(0.0, 0.0, -1.0), Vec3F::new(-1.0, 0.0, 0.0), Vec3F::new(0.0, -1.0,  0.0)),
+        ];
+
+        let mut geometry = ShapeGeometry::default();
+        for &(normal, u_axis, v_axis) in faces.iter() {
+            let origin = (normal - u_axis - v_axis) * half;
+            geometry.push_plane(origin, u_axis * size, v_axis * size, normal, segments, segments);
+        }
+
+        geometry.finish()
+    }
+
+    /// A plane of `width`(along X axis) and `depth`(along Z axis) facing +Y, divided into `segments_x` x `segments_z` quads.
+    pub fn plane(width: f32, depth: f32, segments_x: u32, segments_z: u32) -> ShapeGeometry {
+
+        let origin = Vec3F::new(-width * 0.5, 0.0, -depth * 0.5);
+        let mut geometry = ShapeGeometry::default();
+        geometry.push_plane(origin, Vec3F::new(width, 0.0, 0.0), Vec3F::new(0.0, 0.0, depth), Vec3F::unit_y(), segments_x.max(1), segments_z.max(1));
+
+        geometry.finish()
+    }
+
+    /// A sphere of `radius` divided into `sectors`(at least 3) around Y axis and `stacks`(at least 2) from top to bottom.
+    ///
+    /// The texture is wrapped around the sphere once, and the vertices on the seam are duplicated.
+    pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> ShapeGeometry {
+
+        let (sectors, stacks) = (sectors.max(3), stacks.max(2));
+        let mut geometry = ShapeGeometry::default();
+
+        for stack in 0..=stacks {
+
+            let v = stack as f32 / stacks as f32;
+            let theta = v * PI;
+
+            for sector in 0..=sectors {
+
+                let u = sector as f32 / sectors as f32;
+                let normal = spherical_direction(theta, u * 2.0 * PI);
+                geometry.push_vertex(normal * radius, normal, Vec2F::new(u, v));
+            }
+        }
+
+        geometry.push_grid(0, sectors, stacks);
+        geometry.finish()
+    }
+
+    /// A sphere of `radius` made by subdividing each triangle of icosahedron into 4 triangles for `subdivisions` times.
+    ///
+    /// The texture is mapped in the same way as `uv_sphere`, where the vertices on the seam and poles are duplicated.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> ShapeGeometry {
+
+        let t = (1.0 + 5.0_f32.sqrt()) * 0.5;
+        let mut directions: Vec<Vec3F> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].iter().map(|&(x, y, z)| Vec3F::new(x, y, z).normalized()).collect();
+
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+
+            // the midpoint of each edge is shared by the two triangles on both sides.
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| -> u32 {
+                let key = (a.min(b), a.max(b));
+                *midpoints.entry(key).or_insert_with(|| {
+                    let direction = (directions[a as usize] + directions[b as usize]).normalized();
+                    directions.push(direction);
+                    (directions.len() - 1) as u32
+                })
+            };
+
+            triangles = triangles.iter().flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                vec![[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            }).collect();
+        }
+
+        let mut geometry = ShapeGeometry::default();
+        // the vertex of each direction and texture coordinate, since the directions on the seam and poles need different texture coordinates.
+        let mut vertices: HashMap<(u32, u32, u32), u32> = HashMap::new();
+
+        for &[a, b, c] in triangles.iter() {
+
+            // the triangles are counter-clockwise when viewed from outside.
+            let (da, db, dc) = (directions[a as usize], directions[b as usize], directions[c as usize]);
+            let corners = if (db - da).cross(dc - da).dot(da) < 0.0 { [a, c, b] } else { [a, b, c] };
+
+            let mut texcoords: Vec<Vec2F> = corners.iter()
+                .map(|&corner| spherical_texcoord(directions[corner as usize]))
+                .collect();
+
+            // the triangles crossing the seam use u greater than 1 instead of wrapping back to 0.
+            let max_u = texcoords.iter().fold(0.0_f32, |max_u, texcoord| max_u.max(texcoord.x));
+            for texcoord in texcoords.iter_mut() {
+                if max_u - texcoord.x > 0.5 {
+                    texcoord.x += 1.0;
+                }
+            }
+
+            // the u of pole is undefined, so use the average u of the other corners.
+            for i in 0..3 {
+                if directions[corners[i] as usize].y.abs() > 1.0 - ::std::f32::EPSILON {
+                    texcoords[i].x = (texcoords[(i + 1) % 3].x + texcoords[(i + 2) % 3].x) * 0.5;
+                }
+            }
+
+            let mut indices = [0; 3];
+            for i in 0..3 {
+                let (direction, texcoord) = (directions[corners[i] as usize], texcoords[i]);
+                let key = (corners[i], texcoord.x.to_bits(), texcoord.y.to_bits());
+                indices[i] = match vertices.get(&key) {
+                    | Some(&vertex) => vertex,
+                    | None => {
+                        let vertex = geometry.push_vertex(direction * radius, direction, texcoord);
+                        vertices.insert(key, vertex);
+                        vertex
+                    },
+                };
+            }
+
+            geometry.push_triangle(indices[0], indices[1], indices[2]);
+        }
+
+        geometry.finish()
+    }
+
+    /// A cylinder of `radius` and `height` along Y axis, divided into `radial_segments`(at least 3) around Y axis and `height_segments` from top to bottom.
+    ///
+    /// The top and bottom caps are added if `is_capped` is true, whose textures are mapped from above and below respectively.
+    pub fn cylinder(radius: f32, height: f32, radial_segments: u32, height_segments: u32, is_capped: bool) -> ShapeGeometry {
+
+        let (radial_segments, height_segments) = (radial_segments.max(3), height_segments.max(1));
+        let half_height = height * 0.5;
+        let mut geometry = ShapeGeometry::default();
+
+        for row in 0..=height_segments {
+
+            let v = row as f32 / height_segments as f32;
+            let y = half_height - v * height;
+
+            for column in 0..=radial_segments {
+
+                let u = column as f32 / radial_segments as f32;
+                let normal = spherical_direction(PI * 0.5, u * 2.0 * PI);
+                geometry.push_vertex(Vec3F::new(normal.x * radius, y, normal.z * radius), normal, Vec2F::new(u, v));
+            }
+        }
+        geometry.push_grid(0, radial_segments, height_segments);
+
+        if is_capped {
+            geometry.push_cap(radius,  half_height, radial_segments, true);
+            geometry.push_cap(radius, -half_height, radial_segments, false);
+        }
+
+        geometry.finish()
+    }
+
+    /// A cone of base `radius` and `height` along Y axis with the apex at top, divided into `radial_segments`(at least 3) around Y axis and `height_segments` from apex to base.
+    ///
+    /// The base cap is added if `is_capped` is true, whose texture is mapped from below.
+    pub fn cone(radius: f32, height: f32, radial_segments: u32, height_segments: u32, is_capped: bool) -> ShapeGeometry {
+
+        let (radial_segments, height_segments) = (radial_segments.max(3), height_segments.max(1));
+        let half_height = height * 0.5;
+        let mut geometry = ShapeGeometry::default();
+
+        for row in 0..=height_segments {
+
+            let v = row as f32 / height_segments as f32;
+            let y = half_height - v * height;
+
+            for column in 0..=radial_segments {
+
+                let u = column as f32 / radial_segments as f32;
+                let direction = spherical_direction(PI * 0.5, u * 2.0 * PI);
+                // the normal is perpendicular to the slope from apex to base.
+                let normal = Vec3F::new(direction.x * height, radius, direction.z * height).normalized();
+                geometry.push_vertex(Vec3F::new(direction.x * radius * v, y, direction.z * radius * v), normal, Vec2F::new(u, v));
+            }
+        }
+        geometry.push_grid(0, radial_segments, height_segments);
+
+        if is_capped {
+            geometry.push_cap(radius, -half_height, radial_segments, false);
+        }
+
+        geometry.finish()
+    }
+
+    /// A torus around Y axis, where `major_radius` is the distance from the center of tube to origin and `minor_radius` is the radius of tube.
+    ///
+    /// It is divided into `radial_segments`(at least 3) around Y axis and `tubular_segments`(at least 3) around the tube.
+    pub fn torus(major_radius: f32, minor_radius: f32, radial_segments: u32, tubular_segments: u32) -> ShapeGeometry {
+
+        let (radial_segments, tubular_segments) = (radial_segments.max(3), tubular_segments.max(3));
+        let mut geometry = ShapeGeometry::default();
+
+        for row in 0..=tubular_segments {
+
+            let v = row as f32 / tubular_segments as f32;
+            // start from the outer equator and go down, so that the texture is not mirrored from outside.
+            let psi = -v * 2.0 * PI;
+
+            for column in 0..=radial_segments {
+
+                let u = column as f32 / radial_segments as f32;
+                let ring = spherical_direction(PI * 0.5, u * 2.0 * PI);
+                let normal = Vec3F::new(ring.x * psi.cos(), psi.sin(), ring.z * psi.cos());
+                let position = ring * major_radius + normal * minor_radius;
+                geometry.push_vertex(position, normal, Vec2F::new(u, v));
+            }
+        }
+
+        geometry.push_grid(0, radial_segments, tubular_segments);
+        geometry.finish()
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+impl ShapeGeometry {
+
+    /// Append a plane starting at `origin` and spanning `u_axis` and `v_axis`, divided into `columns` x `rows` quads.
+    fn push_plane(&mut self, origin: Vec3F, u_axis: Vec3F, v_axis: Vec3F, normal: Vec3F, columns: u32, rows: u32) {
+
+        let first_vertex = self.vertex_count() as u32;
+
+        for row in 0..=rows {
+            let v = row as f32 / rows as f32;
+            for column in 0..=columns {
+                let u = column as f32 / columns as f32;
+                self.push_vertex(origin + u_axis * u + v_axis * v, normal, Vec2F::new(u, v));
+            }
+        }
+
+        self.push_grid(first_vertex, columns, rows);
+    }
+
+    /// Append a disk cap at `y`, facing +Y if `is_top` is true or -Y otherwise, with the texture mapped from the side it faces.
+    fn push_cap(&mut self, radius: f32, y: f32, segments: u32, is_top: bool) {
+
+        let (normal, v_sign) = if is_top { (Vec3F::unit_y(), 1.0) } else { (-Vec3F::unit_y(), -1.0) };
+
+        let center = self.push_vertex(Vec3F::new(0.0, y, 0.0), normal, Vec2F::new(0.5, 0.5));
+        let first_rim = self.vertex_count() as u32;
+
+        for segment in 0..=segments {
+            let direction = spherical_direction(PI * 0.5, segment as f32 / segments as f32 * 2.0 * PI);
+            let texcoord = Vec2F::new(0.5 + direction.x * 0.5, 0.5 + direction.z * 0.5 * v_sign);
+            self.push_vertex(Vec3F::new(direction.x * radius, y, direction.z * radius), normal, texcoord);
+        }
+
+        for segment in 0..segments {
+            let (rim, next_rim) = (first_rim + segment, first_rim + segment + 1);
+            if is_top {
+                self.push_triangle(center, rim, next_rim);
+            } else {
+                self.push_triangle(center, next_rim, rim);
+            }
+        }
+    }
+}
+
+/// The unit direction at polar angle `theta` from +Y and azimuth `phi` from +X, where the azimuth increases counter-clockwise when viewed from above.
+fn spherical_direction(theta: f32, phi: f32) -> Vec3F {
+    Vec3F::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin())
+}
+
+/// The texture coordinate of a unit direction mapped as `uv_sphere` does.
+fn spherical_texcoord(direction: Vec3F) -> Vec2F {
+
+    let phi = (-direction.z).atan2(direction.x);
+    let u = if phi < 0.0 { phi / (2.0 * PI) + 1.0 } else { phi / (2.0 * PI) };
+    let v = direction.y.max(-1.0).min(1.0).acos() / PI;
+    Vec2F::new(u, v)
+}
+
+// --------------------------------------------------------------------------------------
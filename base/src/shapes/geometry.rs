@@ -0,0 +1,111 @@
+
+use crate::{Vec2F, Vec3F, Vec4F};
+
+// --------------------------------------------------------------------------------------
+/// The vertices and triangle list indices of a procedural mesh.
+///
+/// The shapes are centered at origin with Y axis up, their triangles are counter-clockwise when viewed from outside,
+/// and the texture coordinates start from the top-left corner of texture as glTF does.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeGeometry {
+
+    pub positions: Vec<Vec3F>,
+    pub normals  : Vec<Vec3F>,
+    pub texcoords: Vec<Vec2F>,
+    /// the tangent in xyz, and the handedness of bitangent in w.
+    pub tangents : Vec<Vec4F>,
+    pub indices  : Vec<u32>,
+}
+
+impl ShapeGeometry {
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Append a vertex and return its index, where the tangent is calculated later in `finish`.
+    pub(super) fn push_vertex(&mut self, position: Vec3F, normal: Vec3F, texcoord: Vec2F) -> u32 {
+
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.texcoords.push(texcoord);
+        (self.positions.len() - 1) as u32
+    }
+
+    /// Append the triangle, unless its corners are degenerated to a point or line(etc. the triangles at the poles of sphere).
+    pub(super) fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+
+        let (pa, pb, pc) = (self.positions[a as usize], self.positions[b as usize], self.positions[c as usize]);
+        if (pb - pa).cross(pc - pa).magnitude_squared() > ::std::f32::EPSILON * ::std::f32::EPSILON {
+            self.indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    /// Append the triangles of a grid whose vertices are pushed row by row, starting at `first_vertex`.
+    ///
+    /// The texture coordinate increases along the columns(right) and rows(down), so the normal is `row direction x column direction`.
+    pub(super) fn push_grid(&mut self, first_vertex: u32, columns: u32, rows: u32) {
+
+        let row_size = columns + 1;
+        for row in 0..rows {
+            for column in 0..columns {
+
+                let corner = first_vertex + row * row_size + column;
+                let (right, down) = (corner + 1, corner + row_size);
+                let diagonal = down + 1;
+
+                self.push_triangle(corner, down, right);
+                self.push_triangle(right, down, diagonal);
+            }
+        }
+    }
+
+    /// Calculate the tangents from the texture coordinates of triangles.
+    ///
+    /// The tangents of the triangles sharing a vertex are averaged, and orthogonalized to the vertex normal.
+    pub(super) fn finish(mut self) -> ShapeGeometry {
+
+        let vertex_count = self.positions.len();
+        let mut tangents = vec![Vec3F::zero(); vertex_count];
+        let mut bitangents = vec![Vec3F::zero(); vertex_count];
+
+        for triangle in self.indices.chunks(3) {
+
+            let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+            let (edge1, edge2) = (self.positions[b] - self.positions[a], self.positions[c] - self.positions[a]);
+            let (duv1, duv2) = (self.texcoords[b] - self.texcoords[a], self.texcoords[c] - self.texcoords[a]);
+
+            let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+            if determinant.abs() <= ::std::f32::EPSILON {
+                continue
+            }
+
+            let tangent = (edge1 * duv2.y - edge2 * duv1.y) / determinant;
+            let bitangent = (edge2 * duv1.x - edge1 * duv2.x) / determinant;
+            for &vertex in [a, b, c].iter() {
+                tangents[vertex] += tangent;
+                bitangents[vertex] += bitangent;
+            }
+        }
+
+        self.tangents = (0..vertex_count).map(|vertex| {
+
+            let normal = self.normals[vertex];
+            let tangent = tangents[vertex] - normal * normal.dot(tangents[vertex]);
+
+            let tangent = if tangent.magnitude_squared() > ::std::f32::EPSILON {
+                tangent.normalized()
+            } else {
+                // the tangent is undefined without texture mapping, so pick any direction perpendicular to normal.
+                let axis = if normal.x.abs() < 0.9 { Vec3F::unit_x() } else { Vec3F::unit_y() };
+                normal.cross(axis).normalized()
+            };
+
+            let handedness = if normal.cross(tangent).dot(bitangents[vertex]) < 0.0 { -1.0 } else { 1.0 };
+            Vec4F::new(tangent.x, tangent.y, tangent.z, handedness)
+        }).collect();
+
+        self
+    }
+}
+// --------------------------------------------------------------------------------------
@@ -0,0 +1,136 @@
+
+use ash::vk;
+
+use crate::shapes::geometry::ShapeGeometry;
+use crate::gltf::meshes::{AttributesData, SHORT_INDEX_VERTEX_LIMIT};
+use crate::gltf::{AttributeFlags, VertexLayout, RenderParams};
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+use crate::ci::pipeline::VertexInputSCI;
+
+use crate::context::{VkDevice, BufferUploadInfo};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+
+use crate::error::{VkResult, VkErrorKind};
+use crate::{vkuint, vkbytes};
+
+use std::convert::TryFrom;
+
+// --------------------------------------------------------------------------------------
+/// The device local vertex and index buffer of a procedural mesh.
+pub struct ShapeMesh {
+
+    pub vertices: VmaBuffer,
+    pub indices : VmaBuffer,
+    /// the offset of each vertex stream in vertex buffer, where each stream is bound to the binding of its index.
+    pub vertex_offsets: Vec<vkbytes>,
+    /// the vertex input state matching the layout passed to `ShapeGeometry::upload`.
+    pub vertex_input: VertexInputSCI,
+    /// the parameters for `CmdGraphicsApi::draw_indexed`, which is always `RenderParams::DrawIndex`.
+    pub params: RenderParams,
+}
+
+impl ShapeGeometry {
+
+    /// Encode the vertices in `layout` and upload them to device local buffers.
+    ///
+    /// POSITION, NORMAL, TANGENT and TEXCOORD_0 are generated by the shape, and the other attributes in layout are filled with zero.
+    /// The indices are stored as 16-bit integers if the vertex count fits in them.
+    /// The buffers are available after the next `submit_uploads` of device has completed.
+    pub fn upload(&self, device: &mut VkDevice, layout: impl Into<VertexLayout>) -> VkResult<ShapeMesh> {
+
+        let mut attributes = AttributesData::try_from(layout.into())?;
+
+        let positions: Vec<[f32; 4]> = self.positions.iter().map(|p| [p.x, p.y, p.z, 0.0]).collect();
+        let normals  : Vec<[f32; 4]> = self.normals.iter().map(|n| [n.x, n.y, n.z, 0.0]).collect();
+        let tangents : Vec<[f32; 4]> = self.tangents.iter().map(|t| [t.x, t.y, t.z, t.w]).collect();
+        let texcoords: Vec<[f32; 4]> = self.texcoords.iter().map(|t| [t.x, t.y, 0.0, 0.0]).collect();
+
+        attributes.extend_values(self.vertex_count(), |attribute| match attribute {
+            | AttributeFlags::POSITION   => Some(positions.as_slice()),
+            | AttributeFlags::NORMAL     => Some(normals.as_slice()),
+            | AttributeFlags::TANGENT    => Some(tangents.as_slice()),
+            | AttributeFlags::TEXCOORD_0 => Some(texcoords.as_slice()),
+            | _ => None,
+        });
+
+        let (index_bytes, index_type) = if self.vertex_count() <= SHORT_INDEX_VERTEX_LIMIT {
+            let bytes: Vec<u8> = self.indices.iter().flat_map(|&index| (index as u16).to_ne_bytes().to_vec()).collect();
+            (bytes, vk::IndexType::UINT16)
+        } else {
+            let bytes: Vec<u8> = self.indices.iter().flat_map(|index| index.to_ne_bytes().to_vec()).collect();
+            (bytes, vk::IndexType::UINT32)
+        };
+
+        let vertices = create_buffer(device, attributes.buffer_size_estimated(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+        let indices  = create_buffer(device, index_bytes.len() as vkbytes, vk::BufferUsageFlags::INDEX_BUFFER)?;
+
+        // copy the data of each vertex stream to vertex buffer, one stream after another.
+        let vertex_offsets = attributes.stream_offsets();
+        for (stream, offset) in attributes.streams().iter().zip(vertex_offsets.iter().cloned()) {
+
+            if stream.is_empty() { continue }
+
+            device.upload_buffer(stream, BufferUploadInfo {
+                buffer: vertices.handle,
+                offset,
+                dst_access: vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            })?;
+        }
+
+        device.upload_buffer(&index_bytes, BufferUploadInfo {
+            buffer: indices.handle,
+            offset: 0,
+            dst_access: vk::AccessFlags::INDEX_READ,
+        })?;
+
+        let result = ShapeMesh {
+            vertices, indices, vertex_offsets,
+            vertex_input: attributes.input_descriptions(),
+            params: RenderParams::DrawIndex {
+                index_count: self.indices.len() as vkuint,
+                first_index: 0,
+                vertex_offset: 0,
+                index_type,
+            },
+        };
+        Ok(result)
+    }
+}
+
+impl ShapeMesh {
+
+    /// Bind the vertex and index buffer, and draw `instance_count` instances of the mesh.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, instance_count: vkuint) {
+
+        let vertex_buffers = vec![self.vertices.handle; self.vertex_offsets.len()];
+        recorder.bind_vertex_buffers(0, &vertex_buffers, &self.vertex_offsets);
+
+        if let RenderParams::DrawIndex { index_count, first_index, vertex_offset, index_type } = self.params {
+            recorder.bind_index_buffer(self.indices.handle, index_type, 0);
+            recorder.draw_indexed(index_count, instance_count, first_index, vertex_offset, 0);
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.vma_discard(self.vertices)?;
+        device.vma_discard(self.indices)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+fn create_buffer(device: &mut VkDevice, size: vkbytes, usage: vk::BufferUsageFlags) -> VkResult<VmaBuffer> {
+
+    let buffer_ci = BufferCI::new(size)
+        .usage(usage | vk::BufferUsageFlags::TRANSFER_DST);
+    let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+    let buffer_allocation = device.vma.create_buffer(
+        buffer_ci.as_ref(), allocate_ci.as_ref())
+        .map_err(VkErrorKind::Vma)?;
+
+    Ok(VmaBuffer::from(buffer_allocation))
+}
+// --------------------------------------------------------------------------------------
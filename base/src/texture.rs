@@ -3,21 +3,78 @@ use ash::vk;
 
 use gli::GliTexture;
 
-use crate::ci::vma::{VmaImage, VmaBuffer, VmaAllocationCI};
-use crate::ci::image::{ImageCI, ImageViewCI, ImageBarrierCI, SamplerCI};
-use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaImage, VmaAllocationCI};
+use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI};
 use crate::ci::VkObjectBuildableCI;
 
-use crate::command::CmdTransferApi;
-use crate::context::VkDevice;
+use crate::context::{VkDevice, ImageUploadInfo};
+use crate::context::{MipmapMethod, MipmapGenerateInfo, mip_level_count};
 
-use crate::{VkResult, VkErrorKind};
+use crate::{VkResult, VkError, VkErrorKind};
 use crate::{vkuint, vkbytes, vkfloat};
 
 use std::path::Path;
-use std::ptr;
 
+pub use self::variant::{TextureSet, TextureVariant};
+
+mod pixels;
+mod dds;
+mod variant;
+mod decompress;
+
+/// There are 6 faces for each cube.
+const CUBE_FACES_COUNT: vkuint = 6;
+
+
+/// The options shared by all texture loaders.
+#[derive(Debug, Clone)]
+pub struct TextureLoadOptions {
+    /// generate the full mip chain on device from the base level, instead of using the mip levels stored in the file.
+    ///
+    /// The mip chain is generated by image blit if the format supports linear blit, or by compute shader otherwise.
+    pub generate_mipmaps: bool,
+    /// interpret the color data as sRGB, or as UNORM(linear) otherwise.
+    ///
+    /// It is only used by loaders that select the texture format themselves(`load_image`, `load_dds`),
+    /// and should be disabled for non-color data such as normal maps.
+    pub srgb: bool,
+}
+
+impl Default for TextureLoadOptions {
+
+    fn default() -> TextureLoadOptions {
+        TextureLoadOptions {
+            generate_mipmaps: false,
+            srgb: true,
+        }
+    }
+}
+
+impl TextureLoadOptions {
+
+    /// Return the method to generate mipmaps for `format` if mipmaps generation is requested.
+    fn mipmap_method(&self, device: &VkDevice, format: vk::Format) -> VkResult<Option<MipmapMethod>> {
+
+        if self.generate_mipmaps {
+            let method = device.mipmap_method(format)
+                .ok_or(VkError::unsupported("Mipmap generation for this texture format"))?;
+            Ok(Some(method))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check if `format` can be sampled on device(and mipmaps can be generated for it if requested).
+    fn is_format_supported(&self, device: &VkDevice, format: vk::Format) -> bool {
+
+        let is_sampleable = device.phy.format_properties(format).optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE);
 
+        is_sampleable && (!self.generate_mipmaps || device.mipmap_method(format).is_some())
+    }
+}
+
+// ----------------------------------------------------------------------------------------------
 /// 2D texture.
 pub struct Texture2D {
 
@@ -34,197 +91,583 @@ pub struct Texture2D {
 
 impl Texture2D {
 
-    pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format) -> VkResult<Texture2D> {
+    pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format, options: TextureLoadOptions) -> VkResult<Texture2D> {
 
-        let tex_2d: gli::Texture2D = gli::load_ktx(path)
-            .map_err(VkErrorKind::Gli)?;
+        let ktx = KtxTexture::load(path, TextureKind::Texture2D)?;
+        let texture = upload_texture(device, ktx.data(format), TextureKind::Texture2D, &options)?;
+        Ok(Texture2D::from_resource(texture))
+    }
 
-        debug_assert!(!tex_2d.empty());
+    /// Load the first variant in `set` that can be sampled on device, or decompress a variant on CPU if none of them can be sampled.
+    pub fn load_set(device: &mut VkDevice, set: &TextureSet, options: TextureLoadOptions) -> VkResult<Texture2D> {
 
-        let (width, height) = {
-            let base_image = tex_2d.get_level(0);
-            (base_image.extent().width, base_image.extent().height)
-        };
+        let texture = load_texture_set(device, set, TextureKind::Texture2D, &options)?;
+        Ok(Texture2D::from_resource(texture))
+    }
 
-        // Only use linear tiling if requested (and supported by the device).
-        // Support for linear tiling is mostly limited, so prefer to use optimal tiling instead.
-        // On most implementations linear tiling will only support a very limited amount of formats and features (mip maps, cubemap, arrays, etc.).
+    /// Load texture from an uncompressed image file(PNG, JPEG, TGA or Radiance HDR).
+    ///
+    /// The texture format is selected from the pixel layout of file, where 3-channel pixels are expanded to RGBA
+    /// if the 3-channel format is not supported on device.
+    pub fn load_image(device: &mut VkDevice, path: impl AsRef<Path>, options: TextureLoadOptions) -> VkResult<Texture2D> {
 
-        let staging_buffer = {
+        let pixels = pixels::decode(device, path.as_ref(), &options)?;
 
-            // create a host-visible staging buffer that contains the raw image data.
-            // This buffer is used as a transfer source for the buffer copy.
+        let data = TextureData {
+            bytes: &pixels.bytes,
+            format: pixels.format,
+            levels: vec![TextureLevel::new(pixels.width, pixels.height, pixels.bytes.len())],
+            layers: 1,
+        };
 
-            let staging_ci = BufferCI::new(tex_2d.size() as vkbytes)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let staging_allocation = device.vma.create_buffer(
-                staging_ci.as_ref(), allocation_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+        let texture = upload_texture(device, data, TextureKind::Texture2D, &options)?;
+        Ok(Texture2D::from_resource(texture))
+    }
 
-            // Copy texture data into staging buffer.
-            let data_ptr = device.vma.map_memory(&staging_allocation.1)
-                .map_err(VkErrorKind::Vma)?;
-            debug_assert_ne!(data_ptr, ptr::null_mut());
+    /// Load texture from the first array layer of a DDS file, including all of its mip levels.
+    pub fn load_dds(device: &mut VkDevice, path: impl AsRef<Path>, options: TextureLoadOptions) -> VkResult<Texture2D> {
 
-            unsafe {
-                data_ptr.copy_from(tex_2d.data() as *const u8, tex_2d.size());
-            }
+        let dds = dds::load(path.as_ref(), &options)?;
 
-            device.vma.unmap_memory(&staging_allocation.1)
-                .map_err(VkErrorKind::Vma)?;
+        if !options.is_format_supported(device, dds.format) {
+            return Err(VkError::unsupported("Texture format of DDS file"))
+        }
 
-            VmaBuffer::from(staging_allocation)
+        let data = TextureData {
+            bytes: &dds.bytes,
+            format: dds.format,
+            levels: dds.levels,
+            layers: 1,
         };
 
-        // setup buffer copy regions for each mip level.
-        let mut buffer_copy_regions = Vec::with_capacity(tex_2d.levels());
-        let mut staging_offset = 0;
+        let texture = upload_texture(device, data, TextureKind::Texture2D, &options)?;
+        Ok(Texture2D::from_resource(texture))
+    }
+
+    fn from_resource(texture: TextureResource) -> Texture2D {
+        Texture2D {
+            descriptor: texture.descriptor(),
+            image: texture.image,
+            view : texture.view,
+            sampler: texture.sampler,
+            width : texture.width,
+            height: texture.height,
+            mip_levels: texture.mip_levels,
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-        for i in 0..tex_2d.levels() {
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+// ----------------------------------------------------------------------------------------------
 
-            let image_level_i = tex_2d.get_level(i);
+// ----------------------------------------------------------------------------------------------
+/// Cube map texture, whose 6 faces are stored as the array layers of image.
+pub struct TextureCube {
 
-            let copy_region = vk::BufferImageCopy {
-                buffer_offset: staging_offset,
-                buffer_row_length  : 0,
-                buffer_image_height: 0,
-                image_subresource: vk::ImageSubresourceLayers {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    mip_level: i as vkuint,
-                    base_array_layer: 0,
-                    layer_count     : 1,
-                },
-                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-                image_extent: vk::Extent3D {
-                    width : image_level_i.extent().width,
-                    height: image_level_i.extent().height,
-                    depth : 1,
-                },
-            };
+    pub image: VmaImage,
+    pub view : vk::ImageView,
 
-            buffer_copy_regions.push(copy_region);
-            staging_offset += image_level_i.size() as vkbytes;
+    pub width      : vkuint,
+    pub height     : vkuint,
+    pub mip_levels : vkuint,
+
+    pub sampler: vk::Sampler,
+    pub descriptor: vk::DescriptorImageInfo,
+}
+
+impl TextureCube {
+
+    pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format, options: TextureLoadOptions) -> VkResult<TextureCube> {
+
+        let ktx = KtxTexture::load(path, TextureKind::Cube)?;
+        let texture = upload_texture(device, ktx.data(format), TextureKind::Cube, &options)?;
+        Ok(TextureCube::from_resource(texture))
+    }
+
+    /// Load the first variant in `set` that can be sampled on device, or decompress a variant on CPU if none of them can be sampled.
+    pub fn load_set(device: &mut VkDevice, set: &TextureSet, options: TextureLoadOptions) -> VkResult<TextureCube> {
+
+        let texture = load_texture_set(device, set, TextureKind::Cube, &options)?;
+        Ok(TextureCube::from_resource(texture))
+    }
+
+    fn from_resource(texture: TextureResource) -> TextureCube {
+        TextureCube {
+            descriptor: texture.descriptor(),
+            image: texture.image,
+            view : texture.view,
+            sampler: texture.sampler,
+            width : texture.width,
+            height: texture.height,
+            mip_levels: texture.mip_levels,
         }
+    }
 
-        // create optimal tiled target image on the device.
-        let dst_image = {
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-            let image_ci = ImageCI::new_2d(format, vk::Extent2D { width, height })
-                .mip_levels(tex_2d.levels() as vkuint)
-                .array_layers(1)
-                .samples(vk::SampleCountFlags::TYPE_1)
-                .tiling(vk::ImageTiling::OPTIMAL)
-                .initial_layout(vk::ImageLayout::UNDEFINED)
-                .usages(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED);
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+// ----------------------------------------------------------------------------------------------
 
-            let allocation_ci = VmaAllocationCI::new(
-                vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
-            let image_allocation = device.vma.create_image(
-                image_ci.as_ref(), allocation_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+// ----------------------------------------------------------------------------------------------
+/// 2D array texture.
+pub struct Texture2DArray {
 
-            VmaImage::from(image_allocation)
-        };
+    pub image: VmaImage,
+    pub view : vk::ImageView,
 
+    pub width      : vkuint,
+    pub height     : vkuint,
+    pub mip_levels : vkuint,
+    pub layer_count: vkuint,
+
+    pub sampler: vk::Sampler,
+    pub descriptor: vk::DescriptorImageInfo,
+}
 
-        { // transfer image data from staging buffer to dst image.
+impl Texture2DArray {
 
-            let sub_range = vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: tex_2d.levels() as vkuint,
-                base_array_layer: 0,
-                layer_count: 1,
-            };
+    pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format, options: TextureLoadOptions) -> VkResult<Texture2DArray> {
+
+        let ktx = KtxTexture::load(path, TextureKind::Array2D)?;
+        let texture = upload_texture(device, ktx.data(format), TextureKind::Array2D, &options)?;
+        Ok(Texture2DArray::from_resource(texture))
+    }
+
+    /// Load the first variant in `set` that can be sampled on device, or decompress a variant on CPU if none of them can be sampled.
+    pub fn load_set(device: &mut VkDevice, set: &TextureSet, options: TextureLoadOptions) -> VkResult<Texture2DArray> {
 
-            // Image barrier for optimal image (target).
-            // Optimal image will be used as destination for the copy.
-            let barrier1 = ImageBarrierCI::new(dst_image.handle, sub_range)
-                .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
-                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        let texture = load_texture_set(device, set, TextureKind::Array2D, &options)?;
+        Ok(Texture2DArray::from_resource(texture))
+    }
 
-            // Change texture image layout to shader read after all mip levels have been copied.
-            let barrier2 = ImageBarrierCI::new(dst_image.handle, sub_range)
-                .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
-                .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+    fn from_resource(texture: TextureResource) -> Texture2DArray {
+        Texture2DArray {
+            descriptor: texture.descriptor(),
+            image: texture.image,
+            view : texture.view,
+            sampler: texture.sampler,
+            width : texture.width,
+            height: texture.height,
+            mip_levels : texture.mip_levels,
+            layer_count: texture.layer_count,
+        }
+    }
 
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-            let cmd_recorder = device.get_transfer_recorder();
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum TextureKind {
+    Texture2D,
+    Cube,
+    Array2D,
+}
 
-            cmd_recorder.begin_record()?
-                .image_pipeline_barrier(vk::PipelineStageFlags::HOST, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[barrier1.into()])
-                // Copy mip levels from staging buffer.
-                .copy_buf2img(staging_buffer.handle, dst_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &buffer_copy_regions)
-                .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[barrier2.into()])
-                .end_record()?;
+impl TextureKind {
 
-            device.flush_transfer(cmd_recorder)?;
+    fn view_type(&self) -> vk::ImageViewType {
+        match self {
+            | TextureKind::Texture2D => vk::ImageViewType::TYPE_2D,
+            | TextureKind::Cube      => vk::ImageViewType::CUBE,
+            | TextureKind::Array2D   => vk::ImageViewType::TYPE_2D_ARRAY,
         }
+    }
 
+    fn create_flags(&self) -> vk::ImageCreateFlags {
+        match self {
+            // This flag is required for cube map images.
+            | TextureKind::Cube => vk::ImageCreateFlags::CUBE_COMPATIBLE,
+            | _ => vk::ImageCreateFlags::empty(),
+        }
+    }
 
-        { // clean up staging resources.
-            device.vma_discard(staging_buffer)?;
+    fn address_mode(&self) -> vk::SamplerAddressMode {
+        match self {
+            | TextureKind::Texture2D => vk::SamplerAddressMode::REPEAT,
+            | TextureKind::Cube
+            | TextureKind::Array2D   => vk::SamplerAddressMode::CLAMP_TO_EDGE,
         }
+    }
+}
 
-        let dst_sampler = {
-
-            // Create a default sampler.
-            let mut sampler_ci = SamplerCI::new()
-                .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
-                .mipmap(vk::SamplerMipmapMode::LINEAR)
-                .address(vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT)
-                // max level-of-detail should match mip level count.
-                .lod(0.0, 0.0, tex_2d.levels() as vkfloat)
-                .compare_op(Some(vk::CompareOp::NEVER))
-                .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
-
-            // Only enable anisotropic filtering if enabled on the device.
-            sampler_ci = if device.phy.features_enabled().sampler_anisotropy == vk::TRUE {
-                sampler_ci.anisotropy(Some(device.phy.limits.max_sampler_anisotropy))
-            } else {
-                sampler_ci.anisotropy(None)
-            };
+/// The dimension and byte size of a mip level.
+#[derive(Debug, Clone, Copy)]
+struct TextureLevel {
+    extent: vk::Extent2D,
+    size: vkbytes,
+}
+
+impl TextureLevel {
+
+    fn new(width: vkuint, height: vkuint, size: usize) -> TextureLevel {
+        TextureLevel {
+            extent: vk::Extent2D { width, height },
+            size: size as vkbytes,
+        }
+    }
+}
+
+/// The texture loaded from KTX file by gli.
+enum KtxTexture {
+    Texture2D(gli::Texture2D),
+    Cube(gli::TextureCube),
+    Array2D(gli::Texture2DArray),
+}
+
+impl KtxTexture {
+
+    fn load(path: impl AsRef<Path>, kind: TextureKind) -> VkResult<KtxTexture> {
 
-            sampler_ci.build(device)?
+        let texture = match kind {
+            | TextureKind::Texture2D => KtxTexture::Texture2D(gli::load_ktx(path).map_err(VkErrorKind::Gli)?),
+            | TextureKind::Cube      => KtxTexture::Cube(gli::load_ktx(path).map_err(VkErrorKind::Gli)?),
+            | TextureKind::Array2D   => KtxTexture::Array2D(gli::load_ktx(path).map_err(VkErrorKind::Gli)?),
         };
+        Ok(texture)
+    }
+
+    /// Borrow the texture data, interpreted as `format`.
+    fn data(&self, format: vk::Format) -> TextureData {
+
+        let (bytes, levels, layers) = match self {
+            | KtxTexture::Texture2D(tex_2d) => {
+                debug_assert!(!tex_2d.empty());
+
+                let levels = (0..tex_2d.levels()).map(|i| {
+                    let image_level_i = tex_2d.get_level(i);
+                    TextureLevel::new(image_level_i.extent().width, image_level_i.extent().height, image_level_i.size())
+                }).collect();
+
+                (texture_bytes(tex_2d), levels, 1)
+            },
+            | KtxTexture::Cube(tex_cube) => {
+                debug_assert!(!tex_cube.empty());
 
-        let dst_image_view = ImageViewCI::new(dst_image.handle, vk::ImageViewType::TYPE_2D, format)
-            .components(vk::ComponentMapping {
-                r: vk::ComponentSwizzle::R,
-                g: vk::ComponentSwizzle::G,
-                b: vk::ComponentSwizzle::B,
-                a: vk::ComponentSwizzle::A,
-            })
-            .sub_range(vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: tex_2d.levels() as vkuint,
-                base_array_layer: 0,
-                layer_count: 1,
-            })
-            .build(device)?;
-
-
-        let result = Texture2D {
-            image: dst_image,
-            view : dst_image_view,
-            mip_levels: tex_2d.levels() as vkuint,
-            sampler: dst_sampler,
-            descriptor: vk::DescriptorImageInfo {
-                sampler: dst_sampler,
-                image_view: dst_image_view,
-                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                // all faces share the same level layout.
+                let base_face = tex_cube.get_face(0);
+                let levels = (0..tex_cube.levels()).map(|i| {
+                    let face_level_i = base_face.get_level(i);
+                    TextureLevel::new(face_level_i.extent().width, face_level_i.extent().height, face_level_i.size())
+                }).collect();
+
+                (texture_bytes(tex_cube), levels, CUBE_FACES_COUNT)
+            },
+            | KtxTexture::Array2D(tex_2d_array) => {
+                debug_assert!(!tex_2d_array.empty());
+
+                // all layers share the same level layout.
+                let base_layer: gli::Texture2D = tex_2d_array.get_layer(0);
+                let levels = (0..tex_2d_array.levels()).map(|i| {
+                    let layer_level_i = base_layer.get_level(i);
+                    TextureLevel::new(layer_level_i.extent().width, layer_level_i.extent().height, layer_level_i.size())
+                }).collect();
+
+                (texture_bytes(tex_2d_array), levels, tex_2d_array.layers() as vkuint)
             },
-            width, height,
         };
-        Ok(result)
+
+        TextureData { bytes, format, levels, layers }
     }
+}
 
-    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+/// View all the data of a gli texture as bytes.
+fn texture_bytes(texture: &impl GliTexture) -> &[u8] {
+    unsafe {
+        ::std::slice::from_raw_parts(texture.data() as *const u8, texture.size())
+    }
+}
 
-        device.discard(self.sampler);
-        device.discard(self.view);
-        device.vma_discard(self.image)
+/// The texture data in host memory, where all the levels of each layer(or face) are tightly packed one layer after another.
+struct TextureData<'a> {
+
+    bytes: &'a [u8],
+    format: vk::Format,
+    /// the levels stored for each layer.
+    levels: Vec<TextureLevel>,
+    /// the count of array layers, where each face of cube map counts as a layer.
+    layers: vkuint,
+}
+
+/// The Vulkan objects shared by all kinds of textures.
+struct TextureResource {
+
+    image: VmaImage,
+    view : vk::ImageView,
+    sampler: vk::Sampler,
+
+    width : vkuint,
+    height: vkuint,
+    mip_levels : vkuint,
+    layer_count: vkuint,
+}
+
+impl TextureResource {
+
+    fn descriptor(&self) -> vk::DescriptorImageInfo {
+        vk::DescriptorImageInfo {
+            sampler: self.sampler,
+            image_view: self.view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+/// The image and view of a texture, which may be sampled by samplers created elsewhere.
+pub(crate) struct TextureImage {
+
+    pub image: VmaImage,
+    pub view : vk::ImageView,
+
+    pub width : vkuint,
+    pub height: vkuint,
+    pub mip_levels : vkuint,
+    pub layer_count: vkuint,
+}
+
+/// Load the selected variant of `set`, and decompress it on CPU if the device can not sample any variant.
+fn load_texture_set(device: &mut VkDevice, set: &TextureSet, kind: TextureKind, options: &TextureLoadOptions) -> VkResult<TextureResource> {
+
+    use self::variant::VariantSelection;
+
+    match set.select(device, options)? {
+        | VariantSelection::Native(variant) => {
+            let ktx = KtxTexture::load(&variant.path, kind)?;
+            upload_texture(device, ktx.data(variant.format), kind, options)
+        },
+        | VariantSelection::Decompress(variant) => {
+            let ktx = KtxTexture::load(&variant.path, kind)?;
+            let compressed = ktx.data(variant.format);
+            let decompressed = decompress::decompress(&compressed)?;
+
+            let data = TextureData {
+                bytes: &decompressed.bytes,
+                format: decompressed.format,
+                levels: decompressed.levels,
+                layers: compressed.layers,
+            };
+            upload_texture(device, data, kind, options)
+        },
     }
 }
+
+/// Create the image, view and sampler of a texture, and upload `data` to it through the staging ring of device.
+///
+/// The texture is available after the next `submit_uploads` of device has completed.
+fn upload_texture(device: &mut VkDevice, data: TextureData, kind: TextureKind, options: &TextureLoadOptions) -> VkResult<TextureResource> {
+
+    let texture_image = upload_texture_image(device, data, kind, options)?;
+
+    let dst_sampler = {
+
+        let address_mode = kind.address_mode();
+
+        // Create a default sampler.
+        let mut sampler_ci = SamplerCI::new()
+            .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
+            .mipmap(vk::SamplerMipmapMode::LINEAR)
+            .address(address_mode, address_mode, address_mode)
+            // max level-of-detail should match mip level count.
+            .lod(0.0, 0.0, texture_image.mip_levels as vkfloat)
+            .compare_op(Some(vk::CompareOp::NEVER))
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
+
+        // Only enable anisotropic filtering if enabled on the device.
+        sampler_ci = if device.phy.features_enabled().sampler_anisotropy == vk::TRUE {
+            sampler_ci.anisotropy(Some(device.phy.limits.max_sampler_anisotropy))
+        } else {
+            sampler_ci.anisotropy(None)
+        };
+
+        sampler_ci.build(device)?
+    };
+
+    let result = TextureResource {
+        image: texture_image.image,
+        view : texture_image.view,
+        sampler: dst_sampler,
+        width : texture_image.width,
+        height: texture_image.height,
+        mip_levels : texture_image.mip_levels,
+        layer_count: texture_image.layer_count,
+    };
+    Ok(result)
+}
+
+/// Upload 2D texture from tightly packed `pixels` of a single mip level, and create its image and view without sampler.
+///
+/// The full mip chain is generated on device if `generate_mipmaps` of `options` is enabled.
+pub(crate) fn upload_pixels(device: &mut VkDevice, pixels: &[u8], extent: vk::Extent2D, format: vk::Format, options: &TextureLoadOptions) -> VkResult<TextureImage> {
+
+    let data = TextureData {
+        bytes: pixels,
+        levels: vec![TextureLevel::new(extent.width, extent.height, pixels.len())],
+        layers: 1,
+        format,
+    };
+
+    upload_texture_image(device, data, TextureKind::Texture2D, options)
+}
+
+/// Create the image and view of a texture, and upload `data` to it through the staging ring of device.
+fn upload_texture_image(device: &mut VkDevice, data: TextureData, kind: TextureKind, options: &TextureLoadOptions) -> VkResult<TextureImage> {
+
+    debug_assert!(!data.levels.is_empty());
+
+    let format = data.format;
+    let base_extent = data.levels[0].extent;
+    let layer_size: vkbytes = data.levels.iter().map(|level| level.size).sum();
+
+    let mipmap_method = options.mipmap_method(device, format)?;
+    // only the base level is uploaded if the mip chain will be generated on device.
+    let (mip_levels, upload_levels) = if mipmap_method.is_some() {
+        (mip_level_count(base_extent), 1)
+    } else {
+        (data.levels.len() as vkuint, data.levels.len() as vkuint)
+    };
+
+    // setup buffer copy regions for each layer including all of it's uploaded mip level.
+    let mut buffer_copy_regions = Vec::with_capacity((upload_levels * data.layers) as usize);
+
+    for layer in 0..data.layers {
+
+        let mut staging_offset = layer_size * (layer as vkbytes);
+
+        for (i, level) in data.levels.iter().take(upload_levels as usize).enumerate() {
+
+            let copy_region = vk::BufferImageCopy {
+                buffer_offset: staging_offset,
+                // specify the following two member to 0 to tell vulkan the image is tightly packed.
+                buffer_row_length  : 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: i as vkuint,
+                    base_array_layer: layer,
+                    layer_count     : 1,
+                },
+                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                image_extent: vk::Extent3D {
+                    width : level.extent.width,
+                    height: level.extent.height,
+                    depth : 1,
+                },
+            };
+
+            buffer_copy_regions.push(copy_region);
+            // Increase offset into staging buffer for next level/layer.
+            staging_offset += level.size;
+        }
+    }
+
+    // Only use linear tiling if requested (and supported by the device).
+    // Support for linear tiling is mostly limited, so prefer to use optimal tiling instead.
+    // On most implementations linear tiling will only support a very limited amount of formats and features (mip maps, cubemap, arrays, etc.).
+
+    // create optimal tiled target image on the device.
+    let dst_image = {
+
+        let mipmap_usage = mipmap_method
+            .map(|method| method.image_usage())
+            .unwrap_or(vk::ImageUsageFlags::empty());
+
+        let image_ci = ImageCI::new_2d(format, base_extent)
+            .flags(kind.create_flags())
+            .mip_levels(mip_levels)
+            // Cube faces count as array layers in Vulkan.
+            .array_layers(data.layers)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usages(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED | mipmap_usage);
+
+        let allocation_ci = VmaAllocationCI::new(
+            vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        let image_allocation = device.vma.create_image(
+            image_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        VmaImage::from(image_allocation)
+    };
+
+
+    { // upload image data to dst image through the staging ring of device.
+
+        let sub_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: upload_levels,
+            base_array_layer: 0,
+            layer_count: data.layers,
+        };
+
+        // the image will be transitioned to shader read layout after all mip levels have been copied,
+        // or to the layout that mipmap generation requires.
+        let (upload_layout, upload_access) = mipmap_method
+            .map(|method| method.base_level_state())
+            .unwrap_or((vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::AccessFlags::SHADER_READ));
+
+        let upload_size = layer_size * (data.layers as vkbytes);
+        device.upload_image(&data.bytes[..(upload_size as usize)], ImageUploadInfo {
+            image: dst_image.handle,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            regions: buffer_copy_regions,
+            sub_range,
+            final_layout: upload_layout,
+            dst_access: upload_access,
+        })?;
+
+        if let Some(method) = mipmap_method {
+            device.generate_mipmaps(MipmapGenerateInfo {
+                image: dst_image.handle,
+                extent: base_extent,
+                layer_count: data.layers,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                dst_access: vk::AccessFlags::SHADER_READ,
+                format, mip_levels, method,
+            })?;
+        }
+    }
+
+    let dst_image_view = ImageViewCI::new(dst_image.handle, kind.view_type(), format)
+        .components(vk::ComponentMapping {
+            r: vk::ComponentSwizzle::R,
+            g: vk::ComponentSwizzle::G,
+            b: vk::ComponentSwizzle::B,
+            a: vk::ComponentSwizzle::A,
+        })
+        .sub_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: mip_levels,
+            base_array_layer: 0,
+            layer_count: data.layers,
+        })
+        .build(device)?;
+
+    let result = TextureImage {
+        image: dst_image,
+        view : dst_image_view,
+        width : base_extent.width,
+        height: base_extent.height,
+        layer_count: data.layers,
+        mip_levels,
+    };
+    Ok(result)
+}
+// ----------------------------------------------------------------------------------------------
@@ -0,0 +1,139 @@
+//! Parse DDS files to texture data.
+
+use ash::vk;
+use ddsfile::{Dds, DxgiFormat, D3DFormat};
+
+use crate::texture::{TextureLevel, TextureLoadOptions};
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::{vkuint, vkbytes};
+
+use std::path::Path;
+use std::fs::File;
+use std::io::BufReader;
+
+/// The texture data of the first array layer of a DDS file.
+pub(super) struct DdsTexture {
+
+    pub bytes: Vec<u8>,
+    pub format: vk::Format,
+    pub levels: Vec<TextureLevel>,
+}
+
+/// Load the first array layer(including all of its mip levels) of a DDS file.
+///
+/// Formats declared in DXGI header are used as they are, while the legacy D3D formats carry no color space info,
+/// so `TextureLoadOptions::srgb` decides whether they are interpreted as sRGB or UNORM.
+pub(super) fn load(path: &Path, options: &TextureLoadOptions) -> VkResult<DdsTexture> {
+
+    let file = File::open(path)
+        .map_err(|_| VkError::path(path))?;
+    let dds = Dds::read(&mut BufReader::new(file))
+        .map_err(VkErrorKind::Dds)?;
+
+    let (format, block) = if let Some(dxgi_format) = dds.get_dxgi_format() {
+        dxgi_to_vk(dxgi_format)
+    } else if let Some(d3d_format) = dds.get_d3d_format() {
+        d3d_to_vk(d3d_format, options.srgb)
+    } else {
+        None
+    }.ok_or(VkError::unsupported("Texture format of DDS file"))?;
+
+    let mut width  = dds.get_width();
+    let mut height = dds.get_height();
+    let mip_levels = dds.get_num_mipmap_levels().max(1);
+
+    let mut levels = Vec::with_capacity(mip_levels as usize);
+    for _ in 0..mip_levels {
+
+        levels.push(TextureLevel {
+            extent: vk::Extent2D { width, height },
+            size: block.level_size(width, height),
+        });
+
+        width  = (width  / 2).max(1);
+        height = (height / 2).max(1);
+    }
+
+    // the data of DDS is stored layer by layer, so the first layer is at the beginning.
+    let layer_size: vkbytes = levels.iter().map(|level| level.size).sum();
+    if (dds.data.len() as vkbytes) < layer_size {
+        return Err(VkError::custom(format!("The data of DDS file is incomplete: {:?}", path)))
+    }
+
+    let mut bytes = dds.data;
+    bytes.truncate(layer_size as usize);
+
+    let result = DdsTexture { bytes, format, levels };
+    Ok(result)
+}
+
+/// The memory layout of a format, where uncompressed formats are treated as 1x1 blocks.
+#[derive(Debug, Clone, Copy)]
+struct BlockLayout {
+    width : vkuint,
+    height: vkuint,
+    bytes : vkbytes,
+}
+
+impl BlockLayout {
+
+    fn compressed(bytes: vkbytes) -> BlockLayout {
+        BlockLayout { width: 4, height: 4, bytes }
+    }
+
+    fn pixel(bytes: vkbytes) -> BlockLayout {
+        BlockLayout { width: 1, height: 1, bytes }
+    }
+
+    fn level_size(&self, width: vkuint, height: vkuint) -> vkbytes {
+
+        let blocks_x = (width  + self.width  - 1) / self.width;
+        let blocks_y = (height + self.height - 1) / self.height;
+        (blocks_x as vkbytes) * (blocks_y as vkbytes) * self.bytes
+    }
+}
+
+fn dxgi_to_vk(format: DxgiFormat) -> Option<(vk::Format, BlockLayout)> {
+
+    let result = match format {
+        | DxgiFormat::BC1_UNorm      => (vk::Format::BC1_RGBA_UNORM_BLOCK, BlockLayout::compressed(8)),
+        | DxgiFormat::BC1_UNorm_sRGB => (vk::Format::BC1_RGBA_SRGB_BLOCK,  BlockLayout::compressed(8)),
+        | DxgiFormat::BC2_UNorm      => (vk::Format::BC2_UNORM_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC2_UNorm_sRGB => (vk::Format::BC2_SRGB_BLOCK,  BlockLayout::compressed(16)),
+        | DxgiFormat::BC3_UNorm      => (vk::Format::BC3_UNORM_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC3_UNorm_sRGB => (vk::Format::BC3_SRGB_BLOCK,  BlockLayout::compressed(16)),
+        | DxgiFormat::BC4_UNorm      => (vk::Format::BC4_UNORM_BLOCK, BlockLayout::compressed(8)),
+        | DxgiFormat::BC4_SNorm      => (vk::Format::BC4_SNORM_BLOCK, BlockLayout::compressed(8)),
+        | DxgiFormat::BC5_UNorm      => (vk::Format::BC5_UNORM_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC5_SNorm      => (vk::Format::BC5_SNORM_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC6H_UF16      => (vk::Format::BC6H_UFLOAT_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC6H_SF16      => (vk::Format::BC6H_SFLOAT_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC7_UNorm      => (vk::Format::BC7_UNORM_BLOCK, BlockLayout::compressed(16)),
+        | DxgiFormat::BC7_UNorm_sRGB => (vk::Format::BC7_SRGB_BLOCK,  BlockLayout::compressed(16)),
+        | DxgiFormat::R8G8B8A8_UNorm      => (vk::Format::R8G8B8A8_UNORM, BlockLayout::pixel(4)),
+        | DxgiFormat::R8G8B8A8_UNorm_sRGB => (vk::Format::R8G8B8A8_SRGB,  BlockLayout::pixel(4)),
+        | DxgiFormat::B8G8R8A8_UNorm      => (vk::Format::B8G8R8A8_UNORM, BlockLayout::pixel(4)),
+        | DxgiFormat::B8G8R8A8_UNorm_sRGB => (vk::Format::B8G8R8A8_SRGB,  BlockLayout::pixel(4)),
+        | DxgiFormat::R16G16B16A16_Float  => (vk::Format::R16G16B16A16_SFLOAT, BlockLayout::pixel(8)),
+        | DxgiFormat::R32G32B32A32_Float  => (vk::Format::R32G32B32A32_SFLOAT, BlockLayout::pixel(16)),
+        | _ => return None,
+    };
+    Some(result)
+}
+
+fn d3d_to_vk(format: D3DFormat, srgb: bool) -> Option<(vk::Format, BlockLayout)> {
+
+    let color_format = |srgb_format: vk::Format, unorm_format: vk::Format| {
+        if srgb { srgb_format } else { unorm_format }
+    };
+
+    let result = match format {
+        | D3DFormat::DXT1 => (color_format(vk::Format::BC1_RGBA_SRGB_BLOCK, vk::Format::BC1_RGBA_UNORM_BLOCK), BlockLayout::compressed(8)),
+        | D3DFormat::DXT3 => (color_format(vk::Format::BC2_SRGB_BLOCK, vk::Format::BC2_UNORM_BLOCK), BlockLayout::compressed(16)),
+        | D3DFormat::DXT5 => (color_format(vk::Format::BC3_SRGB_BLOCK, vk::Format::BC3_UNORM_BLOCK), BlockLayout::compressed(16)),
+        | D3DFormat::A8B8G8R8 => (color_format(vk::Format::R8G8B8A8_SRGB, vk::Format::R8G8B8A8_UNORM), BlockLayout::pixel(4)),
+        | D3DFormat::A8R8G8B8 => (color_format(vk::Format::B8G8R8A8_SRGB, vk::Format::B8G8R8A8_UNORM), BlockLayout::pixel(4)),
+        | _ => return None,
+    };
+    Some(result)
+}
@@ -0,0 +1,260 @@
+//! Decompress block compressed texture data on CPU, for the devices that can not sample the compressed formats.
+//!
+//! Only BC1, BC2 and BC3 formats are supported, and they are decompressed to 8-bit RGBA pixels.
+
+use ash::vk;
+
+use crate::texture::{TextureData, TextureLevel};
+use crate::{VkResult, VkError};
+use crate::{vkuint, vkbytes};
+
+/// The byte size of a RGBA8 pixel.
+const RGBA_PIXEL_SIZE: usize = 4;
+/// The dimension of a compressed block in pixels.
+const BLOCK_DIMENSION: usize = 4;
+
+/// The texture data after decompression, stored in the same layer and level order as the compressed data.
+pub(super) struct DecompressedTexture {
+
+    pub bytes: Vec<u8>,
+    pub format: vk::Format,
+    pub levels: Vec<TextureLevel>,
+}
+
+/// Return the uncompressed format that `format` will be decompressed to, or None if it can not be decompressed on CPU.
+pub(super) fn decompressed_format(format: vk::Format) -> Option<vk::Format> {
+
+    match format {
+        | vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK => Some(vk::Format::R8G8B8A8_UNORM),
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK => Some(vk::Format::R8G8B8A8_SRGB),
+        | _ => None,
+    }
+}
+
+pub(super) fn decompress(data: &TextureData) -> VkResult<DecompressedTexture> {
+
+    let format = decompressed_format(data.format)
+        .ok_or(VkError::unsupported("CPU decompression for this texture format"))?;
+    let block_kind = BlockKind::from_format(data.format);
+
+    let levels: Vec<TextureLevel> = data.levels.iter().map(|level| {
+        let size = (level.extent.width as usize) * (level.extent.height as usize) * RGBA_PIXEL_SIZE;
+        TextureLevel::new(level.extent.width, level.extent.height, size)
+    }).collect();
+
+    let layer_size: vkbytes = levels.iter().map(|level| level.size).sum();
+    let mut bytes = Vec::with_capacity((layer_size * data.layers as vkbytes) as usize);
+
+    let mut src_offset = 0;
+    for _layer in 0..data.layers {
+        for level in data.levels.iter() {
+
+            let src_end = src_offset + level.size as usize;
+            let src = data.bytes.get(src_offset..src_end)
+                .ok_or(VkError::custom("The compressed texture data is incomplete."))?;
+
+            decompress_level(src, level.extent.width, level.extent.height, block_kind, &mut bytes)?;
+            src_offset = src_end;
+        }
+    }
+
+    let result = DecompressedTexture { bytes, format, levels };
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum BlockKind {
+    /// BC1 without alpha.
+    Bc1Rgb,
+    /// BC1 with 1-bit alpha.
+    Bc1Rgba,
+    /// BC1 color block with explicit 4-bit alpha.
+    Bc2,
+    /// BC1 color block with interpolated alpha.
+    Bc3,
+}
+
+impl BlockKind {
+
+    fn from_format(format: vk::Format) -> BlockKind {
+        match format {
+            | vk::Format::BC1_RGB_UNORM_BLOCK
+            | vk::Format::BC1_RGB_SRGB_BLOCK  => BlockKind::Bc1Rgb,
+            | vk::Format::BC1_RGBA_UNORM_BLOCK
+            | vk::Format::BC1_RGBA_SRGB_BLOCK => BlockKind::Bc1Rgba,
+            | vk::Format::BC2_UNORM_BLOCK
+            | vk::Format::BC2_SRGB_BLOCK => BlockKind::Bc2,
+            | _ => BlockKind::Bc3,
+        }
+    }
+
+    fn block_size(&self) -> usize {
+        match self {
+            | BlockKind::Bc1Rgb
+            | BlockKind::Bc1Rgba => 8,
+            | BlockKind::Bc2
+            | BlockKind::Bc3 => 16,
+        }
+    }
+}
+
+/// Decompress a mip level of `width` x `height` pixels, and append the RGBA pixels to `dst`.
+fn decompress_level(src: &[u8], width: vkuint, height: vkuint, kind: BlockKind, dst: &mut Vec<u8>) -> VkResult<()> {
+
+    let width  = width  as usize;
+    let height = height as usize;
+    let blocks_x = (width  + BLOCK_DIMENSION - 1) / BLOCK_DIMENSION;
+    let blocks_y = (height + BLOCK_DIMENSION - 1) / BLOCK_DIMENSION;
+
+    if src.len() < blocks_x * blocks_y * kind.block_size() {
+        return Err(VkError::custom("The compressed texture data is incomplete."))
+    }
+
+    let dst_start = dst.len();
+    dst.resize(dst_start + width * height * RGBA_PIXEL_SIZE, 0);
+    let level_pixels = &mut dst[dst_start..];
+
+    for (block_index, block) in src.chunks(kind.block_size()).take(blocks_x * blocks_y).enumerate() {
+
+        let texels = decode_block(block, kind);
+
+        let block_x = (block_index % blocks_x) * BLOCK_DIMENSION;
+        let block_y = (block_index / blocks_x) * BLOCK_DIMENSION;
+
+        // the blocks on the right or bottom edge may be clipped by the level dimension.
+        for y in 0..BLOCK_DIMENSION.min(height - block_y) {
+            for x in 0..BLOCK_DIMENSION.min(width - block_x) {
+
+                let pixel_offset = ((block_y + y) * width + block_x + x) * RGBA_PIXEL_SIZE;
+                level_pixels[pixel_offset..(pixel_offset + RGBA_PIXEL_SIZE)]
+                    .copy_from_slice(&texels[y * BLOCK_DIMENSION + x]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode a compressed block to 4x4 RGBA texels in row-major order.
+fn decode_block(block: &[u8], kind: BlockKind) -> [[u8; 4]; 16] {
+
+    let (alpha_bytes, color_bytes) = match kind {
+        | BlockKind::Bc1Rgb
+        | BlockKind::Bc1Rgba => (&block[0..0], &block[0..8]),
+        | BlockKind::Bc2
+        | BlockKind::Bc3 => (&block[0..8], &block[8..16]),
+    };
+
+    // the 3-color mode with transparent black is only available for standalone BC1 blocks.
+    let is_standalone = kind == BlockKind::Bc1Rgb || kind == BlockKind::Bc1Rgba;
+    let mut texels = decode_color_block(color_bytes, is_standalone);
+
+    match kind {
+        | BlockKind::Bc1Rgb => {
+            // BC1 without alpha treats the transparent black as opaque black.
+            for texel in texels.iter_mut() {
+                texel[3] = 255;
+            }
+        },
+        | BlockKind::Bc1Rgba => {},
+        | BlockKind::Bc2 => {
+            // 4-bit alpha for each texel, the lower nibble comes first.
+            for (i, texel) in texels.iter_mut().enumerate() {
+                let nibble = (alpha_bytes[i / 2] >> ((i % 2) * 4)) & 0x0F;
+                texel[3] = nibble * 17;
+            }
+        },
+        | BlockKind::Bc3 => {
+            let alphas = decode_alpha_block(alpha_bytes);
+            for (texel, alpha) in texels.iter_mut().zip(alphas.iter()) {
+                texel[3] = *alpha;
+            }
+        },
+    }
+
+    texels
+}
+
+fn decode_color_block(bytes: &[u8], is_standalone: bool) -> [[u8; 4]; 16] {
+
+    let c0 = u16::from(bytes[0]) | (u16::from(bytes[1]) << 8);
+    let c1 = u16::from(bytes[2]) | (u16::from(bytes[3]) << 8);
+    let indices = u32::from(bytes[4])
+        | (u32::from(bytes[5]) << 8)
+        | (u32::from(bytes[6]) << 16)
+        | (u32::from(bytes[7]) << 24);
+
+    let rgb0 = rgb565_to_rgb888(c0);
+    let rgb1 = rgb565_to_rgb888(c1);
+
+    let lerp = |weight0: u32, weight1: u32, total: u32| -> [u8; 4] {
+        let mut color = [0, 0, 0, 255];
+        for (channel, (v0, v1)) in color.iter_mut().zip(rgb0.iter().zip(rgb1.iter())) {
+            *channel = ((u32::from(*v0) * weight0 + u32::from(*v1) * weight1) / total) as u8;
+        }
+        color
+    };
+
+    let palette = if c0 > c1 || !is_standalone {
+        [lerp(1, 0, 1), lerp(0, 1, 1), lerp(2, 1, 3), lerp(1, 2, 3)]
+    } else {
+        [lerp(1, 0, 1), lerp(0, 1, 1), lerp(1, 1, 2), [0, 0, 0, 0]]
+    };
+
+    let mut texels = [[0; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[((indices >> (i * 2)) & 0b11) as usize];
+    }
+    texels
+}
+
+fn decode_alpha_block(bytes: &[u8]) -> [u8; 16] {
+
+    let a0 = u32::from(bytes[0]);
+    let a1 = u32::from(bytes[1]);
+
+    let mut palette = [0_u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+
+    if a0 > a1 {
+        for (i, value) in (1..).zip(palette[2..8].iter_mut()) {
+            *value = (((7 - i) * a0 + i * a1) / 7) as u8;
+        }
+    } else {
+        for (i, value) in (1..).zip(palette[2..6].iter_mut()) {
+            *value = (((5 - i) * a0 + i * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    // 48 bits of 3-bit indices.
+    let indices = bytes[2..8].iter().enumerate()
+        .fold(0_u64, |acc, (i, byte)| acc | (u64::from(*byte) << (i * 8)));
+
+    let mut alphas = [0; 16];
+    for (i, alpha) in alphas.iter_mut().enumerate() {
+        *alpha = palette[((indices >> (i * 3)) & 0b111) as usize];
+    }
+    alphas
+}
+
+fn rgb565_to_rgb888(color: u16) -> [u8; 3] {
+
+    let r = ((color >> 11) & 0x1F) as u32;
+    let g = ((color >> 5)  & 0x3F) as u32;
+    let b = ( color        & 0x1F) as u32;
+
+    [
+        ((r * 255 + 15) / 31) as u8,
+        ((g * 255 + 31) / 63) as u8,
+        ((b * 255 + 15) / 31) as u8,
+    ]
+}
@@ -0,0 +1,147 @@
+//! Decode uncompressed image files(PNG, JPEG, TGA, Radiance HDR) to texture data.
+
+use ash::vk;
+
+use crate::context::VkDevice;
+use crate::texture::TextureLoadOptions;
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::vkuint;
+
+use std::path::Path;
+use std::fs::File;
+use std::io::BufReader;
+
+/// The decoded pixels of an image file, tightly packed in the layout of `format`.
+pub(super) struct DecodedPixels {
+
+    pub bytes: Vec<u8>,
+    pub format: vk::Format,
+
+    pub width : vkuint,
+    pub height: vkuint,
+}
+
+/// Decode an image file and select a vk::Format for it.
+///
+/// Radiance HDR files(with `.hdr` extension) are decoded to 32-bit float pixels, and other files are decoded to 8-bit pixels.
+pub(super) fn decode(device: &VkDevice, path: &Path, options: &TextureLoadOptions) -> VkResult<DecodedPixels> {
+
+    let is_hdr = path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("hdr"))
+        .unwrap_or(false);
+
+    if is_hdr {
+        decode_hdr(device, path, options)
+    } else {
+        decode_ldr(device, path, options)
+    }
+}
+
+fn decode_ldr(device: &VkDevice, path: &Path, options: &TextureLoadOptions) -> VkResult<DecodedPixels> {
+
+    use image::{DynamicImage, GenericImageView};
+
+    let image = image::open(path)
+        .map_err(VkErrorKind::Image)?;
+    let (width, height) = image.dimensions();
+
+    let color_format = |srgb: vk::Format, unorm: vk::Format| {
+        if options.srgb { srgb } else { unorm }
+    };
+
+    let (bytes, format) = match image {
+        | DynamicImage::ImageRgba8(pixels) => {
+            (pixels.into_raw(), color_format(vk::Format::R8G8B8A8_SRGB, vk::Format::R8G8B8A8_UNORM))
+        },
+        | DynamicImage::ImageBgra8(pixels) => {
+            (pixels.into_raw(), color_format(vk::Format::B8G8R8A8_SRGB, vk::Format::B8G8R8A8_UNORM))
+        },
+        | DynamicImage::ImageRgb8(pixels) => {
+            let rgb_format = color_format(vk::Format::R8G8B8_SRGB, vk::Format::R8G8B8_UNORM);
+            if options.is_format_supported(device, rgb_format) {
+                (pixels.into_raw(), rgb_format)
+            } else {
+                // 3-channel formats are rarely supported for sampling, so expand the pixels to 4 channels.
+                (expand_rgb(&pixels.into_raw(), 255_u8), color_format(vk::Format::R8G8B8A8_SRGB, vk::Format::R8G8B8A8_UNORM))
+            }
+        },
+        | DynamicImage::ImageBgr8(pixels) => {
+            let bgr_format = color_format(vk::Format::B8G8R8_SRGB, vk::Format::B8G8R8_UNORM);
+            if options.is_format_supported(device, bgr_format) {
+                (pixels.into_raw(), bgr_format)
+            } else {
+                (expand_rgb(&pixels.into_raw(), 255_u8), color_format(vk::Format::B8G8R8A8_SRGB, vk::Format::B8G8R8A8_UNORM))
+            }
+        },
+        // grayscale images are expanded to RGBA, so that they can be sampled the same way as color images in shader.
+        | DynamicImage::ImageLuma8(_)
+        | DynamicImage::ImageLumaA8(_) => {
+            (image.to_rgba().into_raw(), color_format(vk::Format::R8G8B8A8_SRGB, vk::Format::R8G8B8A8_UNORM))
+        },
+    };
+
+    check_format_supported(device, format, options)?;
+
+    let result = DecodedPixels { bytes, format, width, height };
+    Ok(result)
+}
+
+fn decode_hdr(device: &VkDevice, path: &Path, options: &TextureLoadOptions) -> VkResult<DecodedPixels> {
+
+    let file = File::open(path)
+        .map_err(|_| VkError::path(path))?;
+    let decoder = image::hdr::HDRDecoder::new(BufReader::new(file))
+        .map_err(VkErrorKind::Image)?;
+
+    let metadata = decoder.metadata();
+    let pixels = decoder.read_image_hdr()
+        .map_err(VkErrorKind::Image)?;
+
+    // HDR pixels are always stored in linear color space.
+    let components: Vec<f32> = pixels.iter()
+        .flat_map(|pixel| pixel.data.iter().cloned())
+        .collect();
+
+    let (components, format) = if options.is_format_supported(device, vk::Format::R32G32B32_SFLOAT) {
+        (components, vk::Format::R32G32B32_SFLOAT)
+    } else {
+        (expand_rgb(&components, 1.0_f32), vk::Format::R32G32B32A32_SFLOAT)
+    };
+
+    check_format_supported(device, format, options)?;
+
+    let mut bytes = Vec::with_capacity(components.len() * ::std::mem::size_of::<f32>());
+    for component in components {
+        bytes.extend_from_slice(&component.to_bits().to_ne_bytes());
+    }
+
+    let result = DecodedPixels {
+        bytes, format,
+        width : metadata.width,
+        height: metadata.height,
+    };
+    Ok(result)
+}
+
+/// Append `alpha` to each RGB(or BGR) pixel in `pixels`.
+fn expand_rgb<T: Copy>(pixels: &[T], alpha: T) -> Vec<T> {
+
+    debug_assert_eq!(pixels.len() % 3, 0);
+
+    let mut expanded = Vec::with_capacity(pixels.len() / 3 * 4);
+    for rgb in pixels.chunks(3) {
+        expanded.extend_from_slice(rgb);
+        expanded.push(alpha);
+    }
+    expanded
+}
+
+fn check_format_supported(device: &VkDevice, format: vk::Format, options: &TextureLoadOptions) -> VkResult<()> {
+
+    if options.is_format_supported(device, format) {
+        Ok(())
+    } else {
+        Err(VkError::unsupported("Texture format of image file"))
+    }
+}
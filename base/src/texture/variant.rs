@@ -0,0 +1,185 @@
+//! Select the compressed variant of a texture that the device supports.
+
+use ash::vk;
+
+use crate::context::VkDevice;
+use crate::texture::TextureLoadOptions;
+use crate::texture::decompress;
+use crate::{VkResult, VkError};
+
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::Read;
+
+/// The format names of variants that are tried when the variants are located by base name, in order of preference.
+const DEFAULT_VARIANTS: [&'static str; 3] = ["bc3_unorm", "astc_8x8_unorm", "etc2_unorm"];
+/// The file extension of texture variants.
+const VARIANT_EXTENSION: &'static str = "ktx";
+
+/// A KTX file that stores one compressed variant of a texture.
+#[derive(Debug, Clone)]
+pub struct TextureVariant {
+    pub path: PathBuf,
+    pub format: vk::Format,
+}
+
+/// A texture that is stored in several compressed formats, where each device loads the first variant it can sample.
+#[derive(Debug, Clone, Default)]
+pub struct TextureSet {
+    variants: Vec<TextureVariant>,
+}
+
+/// The way to load the variant selected from a `TextureSet`.
+pub(super) enum VariantSelection<'a> {
+    /// the variant can be sampled on device directly.
+    Native(&'a TextureVariant),
+    /// the variant will be decompressed on CPU, since none of the variants can be sampled on device.
+    Decompress(&'a TextureVariant),
+}
+
+impl TextureSet {
+
+    pub fn new() -> TextureSet {
+        TextureSet::default()
+    }
+
+    /// Locate the variants of a texture by its base name.
+    ///
+    /// The variants are named as `<base_name>_<format>.ktx`, for example, `texturearray_bc3_unorm.ktx`,
+    /// and BC3, ASTC 8x8 and ETC2 variants are tried in order.
+    pub fn from_base_name(base_name: impl AsRef<Path>) -> TextureSet {
+
+        let base_name = base_name.as_ref().to_string_lossy().into_owned();
+
+        let variants = DEFAULT_VARIANTS.iter().map(|format_name| {
+            TextureVariant {
+                path: PathBuf::from(format!("{}_{}.{}", base_name, format_name, VARIANT_EXTENSION)),
+                // the format names in DEFAULT_VARIANTS are always valid.
+                format: format_from_name(format_name).unwrap(),
+            }
+        }).collect();
+
+        TextureSet { variants }
+    }
+
+    /// Read the variants of a texture from a manifest file.
+    ///
+    /// Each line of manifest contains the format name and the file path of a variant, in order of preference.
+    /// The file path is relative to the directory of manifest. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// ```text
+    /// # format         file
+    /// bc3_unorm        texturearray_bc3_unorm.ktx
+    /// astc_8x8_unorm   texturearray_astc_8x8_unorm.ktx
+    /// etc2_unorm       texturearray_etc2_unorm.ktx
+    /// ```
+    pub fn from_manifest(manifest_path: impl AsRef<Path>) -> VkResult<TextureSet> {
+
+        let manifest_path = manifest_path.as_ref();
+        let mut manifest = String::new();
+        File::open(manifest_path)
+            .and_then(|mut file| file.read_to_string(&mut manifest))
+            .map_err(|_| VkError::path(manifest_path))?;
+
+        let manifest_dir = manifest_path.parent().unwrap_or(Path::new(""));
+        let mut set = TextureSet::new();
+
+        for line in manifest.lines().map(str::trim) {
+
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let mut columns = line.split_whitespace();
+            let (format_name, file_name) = match (columns.next(), columns.next()) {
+                | (Some(format_name), Some(file_name)) => (format_name, file_name),
+                | _ => return Err(VkError::custom(format!("Invalid line in texture manifest {:?}: {}", manifest_path, line))),
+            };
+
+            let format = format_from_name(format_name)
+                .ok_or(VkError::custom(format!("Unknown texture format in texture manifest {:?}: {}", manifest_path, format_name)))?;
+
+            set = set.add_variant(manifest_dir.join(file_name), format);
+        }
+
+        Ok(set)
+    }
+
+    /// Append a variant with lower preference than the existing variants.
+    pub fn add_variant(mut self, path: impl AsRef<Path>, format: vk::Format) -> TextureSet {
+        self.variants.push(TextureVariant { path: path.as_ref().to_path_buf(), format });
+        self
+    }
+
+    pub fn variants(&self) -> &[TextureVariant] {
+        &self.variants
+    }
+
+    /// Select the first existing variant that the device can sample, or the first variant that can be decompressed on CPU otherwise.
+    pub(super) fn select(&self, device: &VkDevice, options: &TextureLoadOptions) -> VkResult<VariantSelection> {
+
+        let existing_variants = || self.variants.iter().filter(|variant| variant.path.is_file());
+
+        if let Some(variant) = existing_variants().find(|variant| is_compression_enabled(device, variant.format) && options.is_format_supported(device, variant.format)) {
+            return Ok(VariantSelection::Native(variant))
+        }
+
+        existing_variants()
+            .find(|variant| {
+                decompress::decompressed_format(variant.format)
+                    .map(|format| options.is_format_supported(device, format))
+                    .unwrap_or(false)
+            })
+            .map(VariantSelection::Decompress)
+            .ok_or(VkError::unsupported("Any variant of texture set"))
+    }
+}
+
+/// Check if the compression feature that `format` belongs to is enabled on device.
+fn is_compression_enabled(device: &VkDevice, format: vk::Format) -> bool {
+
+    let features = device.phy.features_enabled();
+    let raw_format = format.as_raw();
+
+    let is_format_in = |first: vk::Format, last: vk::Format| {
+        first.as_raw() <= raw_format && raw_format <= last.as_raw()
+    };
+
+    if is_format_in(vk::Format::BC1_RGB_UNORM_BLOCK, vk::Format::BC7_SRGB_BLOCK) {
+        features.texture_compression_bc == vk::TRUE
+    } else if is_format_in(vk::Format::ETC2_R8G8B8_UNORM_BLOCK, vk::Format::EAC_R11G11_SNORM_BLOCK) {
+        features.texture_compression_etc2 == vk::TRUE
+    } else if is_format_in(vk::Format::ASTC_4X4_UNORM_BLOCK, vk::Format::ASTC_12X12_SRGB_BLOCK) {
+        features.texture_compression_astc_ldr == vk::TRUE
+    } else {
+        // uncompressed formats do not require any feature.
+        true
+    }
+}
+
+/// Translate the format name used in file names and manifests to vk::Format.
+fn format_from_name(name: &str) -> Option<vk::Format> {
+
+    let format = match name {
+        | "bc1_unorm"      => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        | "bc1_srgb"       => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        | "bc2_unorm"      => vk::Format::BC2_UNORM_BLOCK,
+        | "bc2_srgb"       => vk::Format::BC2_SRGB_BLOCK,
+        | "bc3_unorm"      => vk::Format::BC3_UNORM_BLOCK,
+        | "bc3_srgb"       => vk::Format::BC3_SRGB_BLOCK,
+        | "bc7_unorm"      => vk::Format::BC7_UNORM_BLOCK,
+        | "bc7_srgb"       => vk::Format::BC7_SRGB_BLOCK,
+        | "astc_4x4_unorm" => vk::Format::ASTC_4X4_UNORM_BLOCK,
+        | "astc_4x4_srgb"  => vk::Format::ASTC_4X4_SRGB_BLOCK,
+        | "astc_8x8_unorm" => vk::Format::ASTC_8X8_UNORM_BLOCK,
+        | "astc_8x8_srgb"  => vk::Format::ASTC_8X8_SRGB_BLOCK,
+        | "etc2_unorm"     => vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+        | "etc2_srgb"      => vk::Format::ETC2_R8G8B8_SRGB_BLOCK,
+        | "etc2_rgba_unorm" => vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+        | "etc2_rgba_srgb"  => vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+        | "rgba8_unorm"    => vk::Format::R8G8B8A8_UNORM,
+        | "rgba8_srgb"     => vk::Format::R8G8B8A8_SRGB,
+        | _ => return None,
+    };
+    Some(format)
+}
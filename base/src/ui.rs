@@ -1,7 +1,11 @@
 
-pub use self::text::{TextInfo, TextID, TextType, TextHAlign};
+pub use self::text::{TextInfo, TextID, TextType, TextHAlign, TextOutline, TextShadow};
+pub use self::font::{FontSource, FontMetrics, FontID, GlyphMode, DEFAULT_FONT};
 
+mod atlas;
+mod font;
 mod pipeline;
+mod sdf;
 mod text;
 
 
@@ -27,29 +31,43 @@ impl UIRenderer {
 
     pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<UIRenderer> {
 
-        let text_pool = TextPool::new(device, swapchain.dimension)?;
+        let text_pool = TextPool::new(device, swapchain.dimension, swapchain.frame_in_flight())?;
         let pipeline_asset = pipeline::UIPipelineAsset::new(device, swapchain, renderpass, text_pool.glyphs_ref())?;
 
         let renderer = UIRenderer { pipeline_asset, text_pool };
         Ok(renderer)
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
+    /// Record the commands to draw all texts with the vertex buffer of `frame_index`.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, frame_index: usize) {
 
         recorder.bind_pipeline(self.pipeline_asset.pipeline)
             .bind_descriptor_sets(self.pipeline_asset.pipeline_layout, 0, &[self.pipeline_asset.descriptor_set], &[]);
 
-        self.text_pool.record_command(recorder);
+        self.text_pool.record_command(recorder, frame_index);
     }
 
     pub fn swapchain_reload(&mut self, device: &VkDevice, new_chain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<()> {
 
         self.pipeline_asset.swapchain_reload(device, new_chain, renderpass)?;
-        self.text_pool.swapchain_reload();
+        self.text_pool.swapchain_reload(new_chain.dimension);
 
         Ok(())
     }
 
+    /// Register a font face, whose glyphs are rasterized at `size` pixels and scaled to the `scale` of texts.
+    ///
+    /// The same font may be added in several sizes, where the size close to the displayed size gives sharper texts.
+    /// A face in `GlyphMode::DistanceField` stays crisp at any scale, and supports the outline and soft shadow of texts.
+    pub fn add_font(&mut self, source: FontSource, size: f32, mode: GlyphMode) -> VkResult<FontID> {
+        self.text_pool.add_font(source, size, mode)
+    }
+
+    /// Return the vertical metrics of `font` for the texts of `scale` on current screen, which helps to place the lines of multi-line text.
+    pub fn font_metrics(&self, font: FontID, scale: f32) -> VkResult<FontMetrics> {
+        self.text_pool.font_metrics(font, scale)
+    }
+
     pub fn add_text(&mut self, text: TextInfo) -> VkResult<TextID> {
         self.text_pool.add_text(text)
     }
@@ -58,6 +76,27 @@ impl UIRenderer {
         self.text_pool.change_text(content, update_text);
     }
 
+    pub fn remove_text(&mut self, remove_text: TextID) {
+        self.text_pool.remove_text(remove_text);
+    }
+
+    /// Upload the new glyphs and the vertices of texts that are added, changed or removed since last update of `frame_index`.
+    ///
+    /// It should be called before submitting the frame of `frame_index`, after GPU has finished the previous submission of this frame.
+    /// The device is waited to be idle if the glyph atlas is rebuilt or grows, or the vertex buffers need to grow.
+    /// Return true if the atlas image or vertex buffers have been recreated, in which case the command buffers recorded by `record_command` must be recorded again.
+    pub fn update(&mut self, device: &mut VkDevice, frame_index: usize) -> VkResult<bool> {
+
+        let is_atlas_recreated = self.text_pool.flush_glyphs(device)?;
+        if is_atlas_recreated {
+            self.pipeline_asset.update_glyphs(device, self.text_pool.glyphs_ref());
+        }
+
+        let is_buffer_recreated = self.text_pool.update(device, frame_index)?;
+
+        Ok(is_atlas_recreated || is_buffer_recreated)
+    }
+
     pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
         self.pipeline_asset.discard(device);
@@ -0,0 +1,446 @@
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use rusttype::{Rect, VMetrics, HMetrics, GlyphId, point};
+
+use std::collections::HashMap;
+
+use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI};
+use crate::ci::vma::{VmaImage, VmaAllocationCI};
+use crate::ci::VkObjectBuildableCI;
+
+use crate::context::{VkDevice, ImageUploadInfo};
+use crate::ui::font::{FontFace, FontID, GlyphMode};
+use crate::ui::sdf::distance_field;
+
+use crate::utils::memory::IntegerAlignable;
+use crate::{vkuint, vkbytes};
+use crate::{VkResult, VkError, VkErrorKind};
+
+
+/// The width and height of atlas image when it is created.
+const ATLAS_INITIAL_SIZE: vkuint = 512;
+/// The maximum width and height that atlas image can grow to(also limited by the device).
+const ATLAS_MAXIMUM_SIZE: vkuint = 4096;
+/// The empty pixels between adjacent glyphs to avoid sampling the neighbours.
+const GLYPH_PADDING: vkuint = 2;
+/// The height of shelves is rounded up to the multiple of this value, so that glyphs of similar heights share the same shelf.
+const SHELF_HEIGHT_ALIGNMENT: vkuint = 4;
+
+/// The placement of a rasterized glyph.
+#[derive(Debug, Clone)]
+pub struct GlyphLayout {
+
+    /// the pixel rectangle of glyph in atlas, or None if the glyph has no outline(e.g. space).
+    pub region: Option<Rect<vkuint>>,
+
+    pub h_metrics: HMetrics,
+    /// the pixel bounding box of glyph, relative to the top of line at the pen position.
+    pub bounding_box: Rect<f32>,
+}
+
+/// A horizontal strip of atlas, where glyphs are placed from left to right.
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: vkuint,
+    height: vkuint,
+    /// the x coordinate of the next glyph placed in this shelf.
+    cursor: vkuint,
+}
+
+/// `GlyphAtlas` rasterizes the glyphs of the registered font faces into a single channel image on demand.
+///
+/// The glyphs of all faces are packed into shelves of a CPU copy of image, and the regions of new glyphs are uploaded in `flush`.
+/// If there is no space for new glyphs, the glyphs not used by any text are evicted, and the atlas grows if it is still not enough.
+pub struct GlyphAtlas {
+
+    pub text_sampler: vk::Sampler,
+    pub glyph_image: VmaImage,
+    pub glyph_view : vk::ImageView,
+    /// the dimension of `glyph_image`, which may be behind `dimension` until next flush.
+    image_dimension: vk::Extent2D,
+
+    faces: Vec<FontFace>,
+
+    /// the dimension of the CPU copy of atlas.
+    dimension: vk::Extent2D,
+    max_size: vkuint,
+    pixels: Vec<u8>,
+    shelves: Vec<Shelf>,
+    layouts: HashMap<(FontID, GlyphId), GlyphLayout>,
+    /// the regions of `pixels` written by the glyphs rasterized since the last flush.
+    dirty_regions: Vec<Rect<vkuint>>,
+    /// whether all the glyphs have been evicted since the last flush, so the whole image must be uploaded.
+    is_rebuilt: bool,
+}
+
+impl GlyphAtlas {
+
+    pub fn new(device: &mut VkDevice) -> VkResult<GlyphAtlas> {
+
+        let max_size = device.phy.limits.max_image_dimension2_d.min(ATLAS_MAXIMUM_SIZE);
+        let dimension = vk::Extent2D {
+            width : ATLAS_INITIAL_SIZE.min(max_size),
+            height: ATLAS_INITIAL_SIZE.min(max_size),
+        };
+
+        let (glyph_image, glyph_view) = create_atlas_image(device, dimension)?;
+        let text_sampler = SamplerCI::new()
+            .build(device)?;
+
+        let atlas = GlyphAtlas {
+            text_sampler, glyph_image, glyph_view,
+            image_dimension: dimension,
+            faces: Vec::new(),
+            dimension, max_size,
+            pixels: vec![0; (dimension.width * dimension.height) as usize],
+            shelves: Vec::new(),
+            layouts: HashMap::new(),
+            dirty_regions: Vec::new(),
+            is_rebuilt: false,
+        };
+        Ok(atlas)
+    }
+
+    /// Register a font face, whose glyphs are rasterized when they are first used.
+    pub fn add_face(&mut self, face: FontFace) -> FontID {
+
+        self.faces.push(face);
+        self.faces.len() - 1
+    }
+
+    pub fn face(&self, font: FontID) -> Option<&FontFace> {
+        self.faces.get(font)
+    }
+
+    /// Return the layout of the glyph of `character` in `font`, or None if it has not been rasterized into atlas.
+    pub fn layout(&self, font: FontID, character: char) -> Option<&GlyphLayout> {
+        self.layouts.get(&(font, self.faces[font].font.glyph(character).id()))
+    }
+
+    /// Return the horizontal kerning between two adjacent characters of `font`, in pixel of atlas.
+    pub fn kerning(&self, font: FontID, first: char, second: char) -> f32 {
+        let face = &self.faces[font];
+        face.font.pair_kerning(face.scale, first, second)
+    }
+
+    /// The dimension of atlas image, used to convert the glyph regions to texture coordinates.
+    pub fn dimension(&self) -> vk::Extent2D {
+        self.dimension
+    }
+
+    /// Rasterize the glyphs of `characters` that are not in atlas yet, where each character is paired with its font face.
+    ///
+    /// Return false if there is not enough space for them, in which case `rebuild` should be called.
+    pub fn cache(&mut self, characters: impl Iterator<Item=(FontID, char)>) -> bool {
+
+        for (font, character) in characters {
+
+            let glyph_id = self.faces[font].font.glyph(character).id();
+            if self.layouts.contains_key(&(font, glyph_id)) {
+                continue
+            }
+
+            if !self.rasterize(font, glyph_id) {
+                return false
+            }
+        }
+
+        true
+    }
+
+    /// Evict all the glyphs from atlas, and rasterize the glyphs of `characters` again.
+    ///
+    /// The atlas grows until all the glyphs fit in, or its maximum size is reached, then the rest glyphs are left out.
+    /// All the regions returned by `layout` are invalidated after this call.
+    pub fn rebuild(&mut self, characters: impl Iterator<Item=(FontID, char)> + Clone) {
+
+        loop {
+
+            self.pixels.clear();
+            self.pixels.resize((self.dimension.width * self.dimension.height) as usize, 0);
+            self.shelves.clear();
+            self.layouts.clear();
+            self.dirty_regions.clear();
+            self.is_rebuilt = true;
+
+            if self.cache(characters.clone()) {
+                break
+            }
+
+            if self.dimension.width >= self.max_size && self.dimension.height >= self.max_size {
+                break
+            }
+
+            // grow the shorter side first, to keep the atlas close to square.
+            if self.dimension.height < self.dimension.width {
+                self.dimension.height = (self.dimension.height * 2).min(self.max_size);
+            } else {
+                self.dimension.width = (self.dimension.width * 2).min(self.max_size);
+            }
+        }
+    }
+
+    /// Upload the glyphs rasterized since last flush to image.
+    ///
+    /// The device is waited to be idle only if the atlas has been rebuilt or has grown.
+    /// Return true if the image has been recreated, then the descriptor referring `glyph_view` must be updated.
+    pub fn flush(&mut self, device: &mut VkDevice) -> VkResult<bool> {
+
+        let is_recreated = self.image_dimension.width != self.dimension.width || self.image_dimension.height != self.dimension.height;
+
+        if self.is_rebuilt || is_recreated {
+            self.upload_all(device, is_recreated)?;
+        } else if !self.dirty_regions.is_empty() {
+            self.upload_dirty_regions(device)?;
+        } else {
+            return Ok(false)
+        }
+
+        self.dirty_regions.clear();
+        self.is_rebuilt = false;
+        Ok(is_recreated)
+    }
+
+    /// Upload the whole atlas, and recreate the image first if `is_recreated` is true.
+    fn upload_all(&mut self, device: &mut VkDevice, is_recreated: bool) -> VkResult<()> {
+
+        // the glyphs sampled by the frames in flight may be overwritten, and it rarely happens after the first frames.
+        unsafe {
+            device.logic.handle.device_wait_idle()
+                .map_err(|_| VkError::device("Device Wait Idle"))?;
+        }
+
+        if is_recreated {
+
+            let (new_image, new_view) = create_atlas_image(device, self.dimension)?;
+
+            device.discard(self.glyph_view);
+            let old_image = ::std::mem::replace(&mut self.glyph_image, new_image);
+            device.vma_discard(old_image)?;
+
+            self.glyph_view = new_view;
+            self.image_dimension = self.dimension;
+        }
+
+        // the whole image is overwritten, so that its previous content can be discarded during the layout transition.
+        let copy_region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length  : 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0, layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: vk::Extent3D { width: self.dimension.width, height: self.dimension.height, depth: 1 },
+        };
+
+        device.upload_image(&self.pixels, ImageUploadInfo {
+            image: self.glyph_image.handle,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            regions: vec![copy_region],
+            sub_range: atlas_sub_range(),
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            dst_access: vk::AccessFlags::SHADER_READ,
+        })?;
+        device.wait_uploads()
+    }
+
+    /// Upload the regions of the glyphs rasterized since last flush, and keep the rest of image.
+    fn upload_dirty_regions(&mut self, device: &mut VkDevice) -> VkResult<()> {
+
+        let atlas_width = self.dimension.width as usize;
+        let mut data: Vec<u8> = Vec::new();
+        let mut copy_regions = Vec::with_capacity(self.dirty_regions.len());
+
+        for region in self.dirty_regions.iter() {
+
+            // the offset of each region must be a multiple of 4 for the copy on transfer queue.
+            data.resize((data.len() as vkbytes).align_to(4) as usize, 0);
+            let buffer_offset = data.len() as vkbytes;
+
+            let (width, height) = (region.width() as usize, region.height() as usize);
+            for row in 0..height {
+                let start = region.min.x as usize + (region.min.y as usize + row) * atlas_width;
+                data.extend_from_slice(&self.pixels[start..(start + width)]);
+            }
+
+            copy_regions.push(vk::BufferImageCopy {
+                buffer_offset,
+                buffer_row_length  : 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: 0,
+                    base_array_layer: 0, layer_count: 1,
+                },
+                image_offset: vk::Offset3D { x: region.min.x as i32, y: region.min.y as i32, z: 0 },
+                image_extent: vk::Extent3D { width: region.width(), height: region.height(), depth: 1 },
+            });
+        }
+
+        // the image is still in use by the frames in flight, so its layout transitions are recorded on graphics queue after them(or on the same queue),
+        // and the new glyphs are placed in the space not sampled by those frames.
+        device.upload_image(&data, ImageUploadInfo {
+            image: self.glyph_image.handle,
+            initial_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            regions: copy_regions,
+            sub_range: atlas_sub_range(),
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            dst_access: vk::AccessFlags::SHADER_READ,
+        })?;
+        // only wait for the copy, so that the new glyphs are ready for the next frame.
+        device.wait_uploads()
+    }
+
+    pub fn discard(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.text_sampler);
+        device.discard(self.glyph_view);
+        device.vma_discard(self.glyph_image)
+    }
+
+    /// Draw the glyph into atlas, and return false if there is no space for it.
+    fn rasterize(&mut self, font: FontID, glyph_id: GlyphId) -> bool {
+
+        let face = &self.faces[font];
+        let v_metrics = face.v_metrics;
+        let glyph = face.font.glyph(glyph_id).scaled(face.scale);
+        let h_metrics = glyph.h_metrics();
+        let glyph = glyph.positioned(point(0.0, 0.0));
+
+        let (region, bounding_box) = match glyph.pixel_bounding_box() {
+            | Some(pixel_box) => {
+
+                // the distance field extends beyond the outline of glyph, so reserve the spread around it.
+                let spread = face.spread();
+                let margin = spread as vkuint;
+                let (glyph_width, glyph_height) = (pixel_box.width() as vkuint, pixel_box.height() as vkuint);
+                let (width, height) = (glyph_width + margin * 2, glyph_height + margin * 2);
+
+                let mut coverage = vec![0.0_f32; (width * height) as usize];
+                glyph.draw(|glyph_x, glyph_y, v| {
+                    let pos = ((glyph_x + margin) + (glyph_y + margin) * width) as usize;
+                    coverage[pos] = v;
+                });
+
+                let bitmap: Vec<u8> = match face.mode {
+                    | GlyphMode::Bitmap => coverage.iter().map(|&v| (v * 255.0) as u8).collect(),
+                    | GlyphMode::DistanceField => distance_field(&coverage, width as usize, height as usize, spread),
+                };
+
+                let (x, y) = match self.allocate(width, height) {
+                    | Some(position) => position,
+                    | None => return false,
+                };
+
+                let atlas_width = self.dimension.width as usize;
+                for (row, row_pixels) in bitmap.chunks(width as usize).enumerate() {
+                    let start = x as usize + (y as usize + row) * atlas_width;
+                    self.pixels[start..(start + width as usize)].copy_from_slice(row_pixels);
+                }
+
+                let region = Rect {
+                    min: point(x, y),
+                    max: point(x + width, y + height),
+                };
+                let margin = margin as f32;
+                let bounding_box = Rect {
+                    min: point(pixel_box.min.x as f32 - margin, pixel_box.min.y as f32 - margin),
+                    max: point(pixel_box.max.x as f32 + margin, pixel_box.max.y as f32 + margin),
+                };
+                (Some(region), fix_bounding_box_positive(bounding_box, &v_metrics))
+            },
+            | None => {
+                // nothing to draw for the glyph(e.g. space), so only its advance is used.
+                let empty_box = Rect { min: point(0.0, 0.0), max: point(0.0, 0.0) };
+                (None, empty_box)
+            },
+        };
+
+        if let Some(ref region) = region {
+            self.dirty_regions.push(region.clone());
+        }
+        self.layouts.insert((font, glyph_id), GlyphLayout { region, h_metrics, bounding_box });
+
+        true
+    }
+
+    /// Find a place for a glyph of `width` x `height` pixels, and return its top-left position in atlas.
+    fn allocate(&mut self, width: vkuint, height: vkuint) -> Option<(vkuint, vkuint)> {
+
+        let padded_width = width + GLYPH_PADDING;
+        let padded_height = height + GLYPH_PADDING;
+        let shelf_height = (padded_height + SHELF_HEIGHT_ALIGNMENT - 1) / SHELF_HEIGHT_ALIGNMENT * SHELF_HEIGHT_ALIGNMENT;
+        let atlas_width = self.dimension.width;
+
+        let shelves_bottom = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        let is_new_shelf_available = shelves_bottom + shelf_height <= self.dimension.height && padded_width <= atlas_width;
+
+        // use the lowest shelf that the glyph fits in.
+        let best_shelf = self.shelves.iter().enumerate()
+            .filter(|(_, shelf)| shelf.height >= padded_height && shelf.cursor + padded_width <= atlas_width)
+            .min_by_key(|(_, shelf)| shelf.height)
+            .map(|(index, _)| index);
+
+        match best_shelf {
+            // avoid wasting a tall shelf for a short glyph, unless there is no space for a new shelf.
+            | Some(index) if self.shelves[index].height <= shelf_height * 2 || !is_new_shelf_available => {
+                let shelf = &mut self.shelves[index];
+                let position = (shelf.cursor, shelf.y);
+                shelf.cursor += padded_width;
+                Some(position)
+            },
+            | _ if is_new_shelf_available => {
+                self.shelves.push(Shelf {
+                    y: shelves_bottom,
+                    height: shelf_height,
+                    cursor: padded_width,
+                });
+                Some((0, shelves_bottom))
+            },
+            | _ => None,
+        }
+    }
+}
+
+fn create_atlas_image(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<(VmaImage, vk::ImageView)> {
+
+    let glyph_image = {
+
+        let image_ci = ImageCI::new_2d(vk::Format::R8_UNORM, dimension)
+            .usages(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        let image_allocation = device.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+        VmaImage::from(image_allocation)
+    };
+
+    // Just store alpha value in the image.
+    let glyph_view = ImageViewCI::new(glyph_image.handle, vk::ImageViewType::TYPE_2D, vk::Format::R8_UNORM)
+        .sub_range(atlas_sub_range())
+        .build(device)?;
+
+    Ok((glyph_image, glyph_view))
+}
+
+fn atlas_sub_range() -> vk::ImageSubresourceRange {
+
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level  : 0, level_count: 1,
+        base_array_layer: 0, layer_count: 1,
+    }
+}
+
+// TODO: Fix and remove this magic function.
+fn fix_bounding_box_positive(mut rect: Rect<f32>, v_metrics: &VMetrics) -> Rect<f32> {
+
+    rect.min.y += v_metrics.ascent as f32;
+    rect.max.y += v_metrics.ascent as f32;
+
+    rect
+}
@@ -0,0 +1,102 @@
+
+use rusttype::{Font, Scale, VMetrics};
+
+use std::path::Path;
+
+use crate::{VkResult, VkError};
+
+
+pub type FontID = usize;
+
+/// The font face registered in `UIRenderer::new`, which is Roboto Regular rasterized at 48 pixels in `GlyphMode::Bitmap`.
+pub const DEFAULT_FONT: FontID = 0;
+
+/// Where to load a TrueType or OpenType font from.
+pub enum FontSource<'a> {
+    /// Load the font file at path.
+    File(&'a Path),
+    /// Load the font from bytes in memory.
+    Slice(&'a [u8]),
+}
+
+/// How the glyphs of a font face are stored in the glyph atlas.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GlyphMode {
+    /// store the coverage of glyphs, which looks best when text is displayed near the size of face.
+    Bitmap,
+    /// store the signed distance to the outline of glyphs, which keeps the edges crisp at any text scale,
+    /// and supports `TextOutline` and `TextShadow`.
+    DistanceField,
+}
+
+/// The vertical metrics of a font face at a specific text scale, in pixels of screen.
+#[derive(Debug, Clone, Copy)]
+pub struct FontMetrics {
+    /// the distance from the top of line to the baseline.
+    pub ascent: f32,
+    /// the distance from the baseline to the bottom of line, which is usually negative.
+    pub descent: f32,
+    /// the recommended additional space between two lines.
+    pub line_gap: f32,
+    /// the distance between the tops of two adjacent lines, which is `ascent - descent + line_gap`.
+    pub line_height: f32,
+}
+
+/// A font registered with the size that its glyphs are rasterized in.
+pub(super) struct FontFace {
+
+    pub font: Font<'static>,
+    pub scale: Scale,
+    pub v_metrics: VMetrics,
+    pub mode: GlyphMode,
+}
+
+impl FontFace {
+
+    pub fn load(source: FontSource, size: f32, mode: GlyphMode) -> VkResult<FontFace> {
+
+        let bytes = match source {
+            | FontSource::File(path) => {
+                ::std::fs::read(path)
+                    .or(Err(VkError::path(path)))?
+            },
+            | FontSource::Slice(data) => data.to_vec(),
+        };
+
+        let font = Font::from_bytes(bytes)
+            .map_err(|e| VkError::custom(e.to_string()))?;
+        let scale = Scale::uniform(size);
+        let v_metrics = font.v_metrics(scale);
+
+        let face = FontFace { font, scale, v_metrics, mode };
+        Ok(face)
+    }
+
+    /// The size of font in pixels that the glyphs are rasterized in.
+    pub fn size(&self) -> f32 {
+        self.scale.y
+    }
+
+    /// The distance in pixels around glyphs that the distance field covers, or 0 for bitmap glyphs.
+    ///
+    /// It also limits the width of outlines and the softness of shadows.
+    pub fn spread(&self) -> f32 {
+        match self.mode {
+            | GlyphMode::Bitmap => 0.0,
+            | GlyphMode::DistanceField => (self.size() * 0.125).ceil().max(2.0),
+        }
+    }
+
+    /// Return the metrics of this face when its glyphs are displayed at `pixel_size`.
+    pub fn metrics(&self, pixel_size: f32) -> FontMetrics {
+
+        let v_metrics = self.font.v_metrics(Scale::uniform(pixel_size));
+
+        FontMetrics {
+            ascent  : v_metrics.ascent,
+            descent : v_metrics.descent,
+            line_gap: v_metrics.line_gap,
+            line_height: v_metrics.ascent - v_metrics.descent + v_metrics.line_gap,
+        }
+    }
+}
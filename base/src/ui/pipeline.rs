@@ -5,7 +5,7 @@ use ash::vk;
 use crate::context::{VkDevice, VkSwapchain};
 use crate::ci::shader::{ShaderModuleCI, ShaderStageCI};
 use crate::ci::VkObjectBuildableCI;
-use crate::ui::text::GlyphImages;
+use crate::ui::atlas::GlyphAtlas;
 use crate::VkResult;
 
 
@@ -21,7 +21,7 @@ pub(super) struct UIPipelineAsset {
 
 impl UIPipelineAsset {
 
-    pub fn new(device: &VkDevice, swapchain: &VkSwapchain, render_pass: vk::RenderPass, glyphs: &GlyphImages) -> VkResult<UIPipelineAsset> {
+    pub fn new(device: &VkDevice, swapchain: &VkSwapchain, render_pass: vk::RenderPass, glyphs: &GlyphAtlas) -> VkResult<UIPipelineAsset> {
 
         let (desc_pool, desc_set, desc_set_layout) = setup_descriptor(device, glyphs)?;
         let (pipeline, pipeline_layout) = prepare_pipelines(device, swapchain.dimension, render_pass, desc_set_layout)?;
@@ -47,6 +47,11 @@ impl UIPipelineAsset {
         Ok(())
     }
 
+    /// Point the descriptor to the recreated atlas image.
+    pub fn update_glyphs(&self, device: &VkDevice, glyphs: &GlyphAtlas) {
+        write_glyphs_descriptor(device, self.descriptor_set, glyphs);
+    }
+
     pub fn discard(&self, device: &VkDevice) {
 
         device.discard(self.descriptor_set_layout);
@@ -57,10 +62,9 @@ impl UIPipelineAsset {
     }
 }
 
-fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::DescriptorPool, vk::DescriptorSet, vk::DescriptorSetLayout)> {
+fn setup_descriptor(device: &VkDevice, glyphs: &GlyphAtlas) -> VkResult<(vk::DescriptorPool, vk::DescriptorSet, vk::DescriptorSetLayout)> {
 
-    use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
-    use crate::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+    use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI, DescriptorSetAI};
 
     // Descriptor Pool.
     let descriptor_pool = DescriptorPoolCI::new(1)
@@ -88,6 +92,15 @@ fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::De
         .build(device)?;
     let descriptor_set = descriptor_sets.remove(0);
 
+    write_glyphs_descriptor(device, descriptor_set, glyphs);
+
+    Ok((descriptor_pool, descriptor_set, set_layout))
+}
+
+fn write_glyphs_descriptor(device: &VkDevice, descriptor_set: vk::DescriptorSet, glyphs: &GlyphAtlas) {
+
+    use crate::ci::descriptor::{DescriptorImageSetWI, DescriptorSetsUpdateCI};
+
     // update descriptorsets.
     let sampled_image_write_info = DescriptorImageSetWI::new(descriptor_set, 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
         .add_image(vk::DescriptorImageInfo {
@@ -99,8 +112,6 @@ fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::De
     DescriptorSetsUpdateCI::new()
         .add_write(&sampled_image_write_info)
         .update(device);
-
-    Ok((descriptor_pool, descriptor_set, set_layout))
 }
 
 fn prepare_pipelines(device: &VkDevice, dimension: vk::Extent2D, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<(vk::Pipeline, vk::PipelineLayout)> {
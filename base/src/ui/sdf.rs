@@ -0,0 +1,106 @@
+
+/// A distance larger than any distance in glyph image, which marks the pixels without feature.
+const INFINITE_DISTANCE: f32 = 1e20;
+
+/// Convert the coverage of glyph to the signed distance to its outline.
+///
+/// The distance is mapped to [0, 255], where 128 lies on the outline, and the pixels `spread` pixels away outside or inside map to 0 or 255.
+pub fn distance_field(coverage: &[f32], width: usize, height: usize, spread: f32) -> Vec<u8> {
+
+    // the squared distance from each pixel to the nearest pixel inside glyph, and to the nearest pixel outside glyph.
+    let to_inside  = squared_distance_transform(coverage.iter().map(|&c| c >= 0.5), width, height);
+    let to_outside = squared_distance_transform(coverage.iter().map(|&c| c < 0.5), width, height);
+
+    coverage.iter().enumerate().map(|(i, &c)| {
+
+        // the distance to outline in pixels, which is positive outside glyph.
+        let distance = if c > 0.0 && c < 1.0 {
+            // the outline crosses the pixel, so estimate the distance from its coverage.
+            0.5 - c
+        } else if c >= 0.5 {
+            // the outline lies half a pixel away from the center of the nearest pixel on the other side.
+            0.5 - to_outside[i].sqrt()
+        } else {
+            to_inside[i].sqrt() - 0.5
+        };
+
+        let value = 0.5 - distance / (2.0 * spread);
+        (value.max(0.0).min(1.0) * 255.0).round() as u8
+    }).collect()
+}
+
+/// Calculate the squared euclidean distance from each pixel to the nearest feature pixel.
+///
+/// See "Distance Transforms of Sampled Functions" by Felzenszwalb and Huttenlocher for the algorithm.
+fn squared_distance_transform(is_feature: impl Iterator<Item=bool>, width: usize, height: usize) -> Vec<f32> {
+
+    let mut grid: Vec<f32> = is_feature
+        .map(|feature| if feature { 0.0 } else { INFINITE_DISTANCE })
+        .collect();
+
+    let length = width.max(height);
+    let mut source = vec![0.0; length];
+    let mut target = vec![0.0; length];
+    let mut parabolas = vec![0; length];
+    let mut boundaries = vec![0.0; length + 1];
+
+    // transform along columns, and then along rows.
+    for x in 0..width {
+
+        for y in 0..height {
+            source[y] = grid[y * width + x];
+        }
+        transform_1d(&source[..height], &mut target, &mut parabolas, &mut boundaries);
+        for y in 0..height {
+            grid[y * width + x] = target[y];
+        }
+    }
+
+    for y in 0..height {
+
+        let row = &mut grid[(y * width)..((y + 1) * width)];
+        source[..width].copy_from_slice(row);
+        transform_1d(&source[..width], &mut target, &mut parabolas, &mut boundaries);
+        row.copy_from_slice(&target[..width]);
+    }
+
+    grid
+}
+
+/// The one dimensional distance transform, computed as the lower envelope of parabolas rooted at each sample.
+fn transform_1d(source: &[f32], target: &mut [f32], parabolas: &mut [usize], boundaries: &mut [f32]) {
+
+    let intersection = |q: usize, p: usize| -> f32 {
+        ((source[q] + (q * q) as f32) - (source[p] + (p * p) as f32)) / (2 * q - 2 * p) as f32
+    };
+
+    let mut k = 0;
+    parabolas[0] = 0;
+    boundaries[0] = -INFINITE_DISTANCE;
+    boundaries[1] = INFINITE_DISTANCE;
+
+    for q in 1..source.len() {
+
+        let mut s = intersection(q, parabolas[k]);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+
+        k += 1;
+        parabolas[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = INFINITE_DISTANCE;
+    }
+
+    k = 0;
+    for q in 0..source.len() {
+
+        while boundaries[k + 1] < q as f32 {
+            k += 1;
+        }
+
+        let offset = q as f32 - parabolas[k] as f32;
+        target[q] = offset * offset + source[parabolas[k]];
+    }
+}
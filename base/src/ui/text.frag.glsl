@@ -4,6 +4,9 @@
 
 layout (location = 0) in vec2 inUV;
 layout (location = 1) in vec4 inColor;
+layout (location = 2) in vec4 inOutlineColor;
+// x: whether the glyph is distance field, y: outline width, z: edge softness.
+layout (location = 3) in vec4 inParams;
 
 layout (location = 0) out vec4 outColor;
 
@@ -11,9 +14,32 @@ layout (binding = 0) uniform sampler2D font_glyphs;
 
 void main() {
 
-    vec4 color = vec4(inColor.xyz, inColor.w * texture(font_glyphs, inUV).r);
+    float value = texture(font_glyphs, inUV).r;
 
-    if (color.a <= 0.3) {
+    if (inParams.x < 0.5) {
+        // the glyph is bitmap, where the value is the coverage of glyph.
+        vec4 color = vec4(inColor.xyz, inColor.w * value);
+
+        if (color.a <= 0.3) {
+            discard;
+        }
+
+        outColor = color;
+        return;
+    }
+
+    // the value is the signed distance to outline, where 0.5 lies on the outline and is larger inside glyph.
+    float signed_distance = value - 0.5;
+    // keep the edge about one pixel wide on screen, whatever the text scale is.
+    float smoothing = max(fwidth(signed_distance) * 0.5, max(inParams.z, 0.0001));
+
+    float fill   = smoothstep(-smoothing, smoothing, signed_distance);
+    float border = smoothstep(-smoothing, smoothing, signed_distance + inParams.y);
+
+    vec4 color = mix(inOutlineColor, inColor, fill);
+    color.a *= border;
+
+    if (color.a <= 0.0) {
         discard;
     }
 
@@ -1,44 +1,38 @@
 
 use ash::vk;
+use ash::version::DeviceV1_0;
 use memoffset::offset_of;
 
-use rusttype::{Rect, VMetrics, HMetrics};
-
-use std::ops::Range;
-use std::collections::HashMap;
 use std::iter::Iterator;
+use std::str::Chars;
 
 use crate::ci::buffer::BufferCI;
 use crate::ci::memory::MemoryAI;
-use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI, ImageBarrierCI};
-use crate::ci::vma::{VmaBuffer, VmaImage, VmaAllocationCI};
 use crate::ci::pipeline::VertexInputSCI;
 use crate::ci::VkObjectBuildableCI;
 
 use crate::context::VkDevice;
-use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi, CmdTransferApi};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::ui::atlas::GlyphAtlas;
+use crate::ui::font::{FontFace, FontSource, FontMetrics, FontID, GlyphMode, DEFAULT_FONT};
 
 use crate::utils::color::VkColor;
 use crate::{vkuint, vkbytes, vkptr};
-use crate::{VkResult, VkError, VkErrorKind};
+use crate::{VkResult, VkError};
 
 
 /// each character use 6 vertices to draw.
 const VERTEX_PER_CHARACTER: usize = 6;
-/// the maximum sentence count that the buffer can contain.
-const MAXIMUM_SENTENCE_COUNT: usize = 10;
-/// the maximum character count that a sentence may contain.
-const MAXIMUM_SENTENCE_TEXT_COUNT: usize = 100;
-/// Control the font size of sampled glyph.
-const FONT_SCALE: f32 = 48.0;
+/// the character count that the vertex buffer of each frame can contain before its first reallocation.
+const INITIAL_CHARACTER_CAPACITY: usize = 1024;
+/// the vertices are placed after the indirect draw command in the buffer of each frame.
+const VERTEX_OFFSET: vkbytes = ::std::mem::size_of::<vk::DrawIndirectCommand>() as vkbytes;
+/// Control the font size of sampled glyph of the default font.
+const DEFAULT_FONT_SCALE: f32 = 48.0;
 /// A magic number.
 const DISPLAY_SCALE_FIX: f32 = 1.0 / 768.0;
-/// The padding attach to sampled glyph image.
-const IMAGE_PADDING: usize = 20;
 
 pub type TextID = usize;
-type CharacterID = char;
-type GlyphLayouts = HashMap<CharacterID, GlyphLayout>;
 
 /// The vertices attributes for each character.
 #[repr(C)]
@@ -47,88 +41,66 @@ struct CharacterVertex {
     pos   : [f32; 2],
     uv    : [f32; 2],
     color : [f32; 4],
+    outline_color: [f32; 4],
+    /// x is 1.0 for the glyphs in distance field, y is the outline width and z is the softness of edge,
+    /// where the distances are in the unit of the value sampled from distance field.
+    params: [f32; 4],
 }
 
-#[derive(Debug, Clone)]
-struct GlyphLayout {
-
-    min_uv: [f32; 2],
-    max_uv: [f32; 2],
-
-    h_metrics: HMetrics,
-    bounding_box: Rect<f32>,
-}
-
-pub struct GlyphImages {
-
-    pub text_sampler: vk::Sampler,
-    pub glyph_image: VmaImage,
-    pub glyph_view : vk::ImageView,
-
-    layouts: GlyphLayouts,
-}
-
-impl GlyphImages {
-
-    pub fn from_font(device: &mut VkDevice, bytes: &[u8]) -> VkResult<GlyphImages> {
-
-        let (layouts, image_bytes, image_dimension) =
-            generate_ascii_glyphs_bytes(bytes, FONT_SCALE)?;
-        let glyph_image = allocate_glyph_image(device, image_bytes, image_dimension)?;
-
-        // Just store alpha value in the image.
-        let glyph_view = ImageViewCI::new(glyph_image.handle, vk::ImageViewType::TYPE_2D, vk::Format::R8_UNORM)
-            .sub_range(vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count   : 1,
-                base_array_layer: 0,
-                layer_count     : 1,
-            }).build(device)?;
-
-        let text_sampler = SamplerCI::new()
-            .build(device)?;
-
-        let result = GlyphImages { text_sampler, glyph_image, glyph_view, layouts };
-        Ok(result)
-    }
-
-    pub fn discard(self, device: &mut VkDevice) -> VkResult<()> {
-
-        device.discard(self.text_sampler);
-        device.discard(self.glyph_view);
-        device.vma_discard(self.glyph_image)
-    }
-}
-
+/// The vertex buffer of a frame, which begins with a `vk::DrawIndirectCommand` to draw all the vertices in it.
 struct TextAttrStorage {
     /// the starting pointer of the memory of text attributes.
     data_ptr: vkptr,
     /// the buffer which store the text attributes.
     buffer: vk::Buffer,
     memory: vk::DeviceMemory,
+    /// whether the texts have changed since the buffer was last written.
+    is_outdated: bool,
 }
 
 impl TextAttrStorage {
 
-    fn new(device: &VkDevice) -> VkResult<TextAttrStorage> {
+    fn new(device: &VkDevice, vertex_capacity: usize) -> VkResult<TextAttrStorage> {
 
-        let pool_size = (::std::mem::size_of::<CharacterVertex>() * MAXIMUM_SENTENCE_COUNT * MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER) as vkbytes;
+        let pool_size = VERTEX_OFFSET + (::std::mem::size_of::<CharacterVertex>() * vertex_capacity) as vkbytes;
         let (buffer, requirement) = BufferCI::new(pool_size)
-            .usage(vk::BufferUsageFlags::VERTEX_BUFFER)
+            .usage(vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::INDIRECT_BUFFER)
             .build(device)?;
 
         let memory_type = device.get_memory_type(requirement.memory_type_bits, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
         let memory = MemoryAI::new(requirement.size, memory_type)
             .build(device)?;
         device.bind_memory(buffer, memory, 0)?;
-        // keep the memory mapping during the whole program running.
+        // keep the memory mapping during the whole lifetime of buffer.
         let data_ptr = device.map_memory(memory, 0, vk::WHOLE_SIZE)?;
 
-        let result = TextAttrStorage { data_ptr, buffer, memory };
+        let result = TextAttrStorage { data_ptr, buffer, memory, is_outdated: true };
         Ok(result)
     }
 
+    /// Write the indirect draw command and the vertices of `texts` to buffer.
+    fn write<'a>(&mut self, texts: impl Iterator<Item=&'a TextEntry>, vertex_count: usize) {
+
+        let draw_command = vk::DrawIndirectCommand {
+            vertex_count: vertex_count as vkuint,
+            instance_count: 1,
+            first_vertex  : 0,
+            first_instance: 0,
+        };
+
+        unsafe {
+            (self.data_ptr as vkptr<vk::DrawIndirectCommand>).write(draw_command);
+
+            let mut target_ptr = (self.data_ptr as vkptr<u8>).offset(VERTEX_OFFSET as isize) as vkptr<CharacterVertex>;
+            for text in texts {
+                target_ptr.copy_from_nonoverlapping(text.vertices.as_ptr(), text.vertices.len());
+                target_ptr = target_ptr.add(text.vertices.len());
+            }
+        }
+
+        self.is_outdated = false;
+    }
+
     fn discard(self, device: &VkDevice) {
 
         device.unmap_memory(self.memory);
@@ -137,6 +109,11 @@ impl TextAttrStorage {
     }
 }
 
+/// A text added to pool and its vertices, which are generated when the text changes.
+struct TextEntry {
+    info: TextInfo,
+    vertices: Vec<CharacterVertex>,
+}
 
 pub struct TextPool {
 
@@ -145,12 +122,14 @@ pub struct TextPool {
     // the aspect ratio of current screen dimension.
     aspect_ratio: f32,
 
-    /// all the texts to be rendered.
-    texts: Vec<TextInfo>,
-    /// `attributes` contains the resource for rendering texts.
-    attributes: TextAttrStorage,
-    /// `glyph_layouts` records the layout information to generate text attributes.
-    glyphs: GlyphImages,
+    /// all the texts to be rendered, where the slots of removed texts are None and reused by the later added texts.
+    texts: Vec<Option<TextEntry>>,
+    /// `attributes` contains the vertex buffer of each frame in flight, so that a buffer is never written while GPU is reading it.
+    attributes: Vec<TextAttrStorage>,
+    /// the vertex count that the buffer of every frame can contain.
+    vertex_capacity: usize,
+    /// `glyphs` rasterizes the characters of texts and records their layout to generate text attributes.
+    glyphs: GlyphAtlas,
 }
 
 pub struct TextInfo {
@@ -158,32 +137,59 @@ pub struct TextInfo {
     pub content: String,
     /// `scale` defines the font size of this text.
     pub scale  : f32,
+    /// `font` is the font face to render this text, which is returned by `UIRenderer::add_font`.
+    pub font   : FontID,
     /// `align` the align method for this text.
     pub align  : TextHAlign,
     /// `color` is color value of this text.
     pub color  : VkColor,
     /// `location` is the starting position of the first character.
     pub location: vk::Offset2D,
+    /// `outline` draws a border around the characters, which requires a font in `GlyphMode::DistanceField`.
+    pub outline: Option<TextOutline>,
+    /// `shadow` draws a copy of the text behind it.
+    pub shadow: Option<TextShadow>,
 
     pub r#type: TextType,
 }
 
+/// The border around the characters of a text.
+#[derive(Debug, Clone, Copy)]
+pub struct TextOutline {
+    /// `width` is the width of border in pixels of screen, which is limited by the spread of font face.
+    pub width: f32,
+    /// `color` is the color of border.
+    pub color: VkColor,
+}
+
+/// The drop shadow of a text.
+#[derive(Debug, Clone, Copy)]
+pub struct TextShadow {
+    /// `offset` is the offset of shadow from the text in pixels of screen.
+    pub offset: vk::Offset2D,
+    /// `softness` is the width of the blurred edge of shadow in pixels of screen, which only works for the font in `GlyphMode::DistanceField`.
+    pub softness: f32,
+    /// `color` is the color of shadow.
+    pub color: VkColor,
+}
+
 pub enum TextType {
     /// Render static text to screen. The text can not change after first set.
     Static,
     /// Render text that is dynamically changed in runtime.
     ///
-    /// `capacity` is the maximum length of the text to rendering.
+    /// `capacity` is the maximum character count of the text to rendering.
     ///
     /// Use `change_text` method to set the content of text in runtime.
     Dynamic { capacity: usize },
 }
 
+/// Iterate the characters of a text, where `None` is returned for the padding after the end of a dynamic text.
+#[derive(Clone)]
 pub struct TextIter<'a> {
-    content: &'a [u8],
-    current: usize,
-    len: usize,
-    capacity: usize,
+    chars: Chars<'a>,
+    /// the number of characters to be returned, including the padding.
+    remain: usize,
 }
 
 impl<'a> Iterator for TextIter<'a> {
@@ -191,18 +197,12 @@ impl<'a> Iterator for TextIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
 
-        let result = if self.current < self.len {
-            Some(self.content[self.current] as char)
-        } else {
-            if self.current < self.capacity {
-                None
-            } else {
-                return None
-            }
-        };
+        if self.remain == 0 {
+            return None
+        }
 
-        self.current += 1;
-        Some(result)
+        self.remain -= 1;
+        Some(self.chars.next())
     }
 }
 
@@ -210,21 +210,19 @@ impl TextInfo {
 
     fn iter(&self) -> TextIter {
 
-        let char_bytes = self.content.as_bytes();
-        let bytes_length = char_bytes.len();
+        TextIter {
+            chars: self.content.chars(),
+            remain: self.character_count(),
+        }
+    }
 
-        let mut iter = TextIter {
-            content: char_bytes,
-            current: 0,
-            len: bytes_length,
-            capacity: 0,
-        };
+    /// The number of characters to render, where the dynamic text is padded or truncated to its capacity.
+    fn character_count(&self) -> usize {
 
-        iter.capacity = match self.r#type {
-            | TextType::Static => bytes_length,
+        match self.r#type {
+            | TextType::Static => self.content.chars().count(),
             | TextType::Dynamic { capacity } => capacity,
-        };
-        iter
+        }
     }
 }
 
@@ -237,65 +235,182 @@ pub enum TextHAlign {
 
 impl TextPool {
 
-    pub fn new(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<TextPool> {
+    pub fn new(device: &mut VkDevice, dimension: vk::Extent2D, frame_in_flight: usize) -> VkResult<TextPool> {
 
-        let attributes = TextAttrStorage::new(device)?;
+        let vertex_capacity = INITIAL_CHARACTER_CAPACITY * VERTEX_PER_CHARACTER;
+        let attributes = (0..frame_in_flight.max(1))
+            .map(|_| TextAttrStorage::new(device, vertex_capacity))
+            .collect::<VkResult<Vec<_>>>()?;
 
         let font_bytes = include_bytes!("../../../assets/fonts/Roboto-Regular.ttf");
-        let glyphs = GlyphImages::from_font(device, font_bytes)?;
+        let default_face = FontFace::load(FontSource::Slice(font_bytes), DEFAULT_FONT_SCALE, GlyphMode::Bitmap)?;
+
+        let mut glyphs = GlyphAtlas::new(device)?;
+        glyphs.add_face(default_face);
+        // the printable ascii characters are likely to be used, so rasterize them in advance.
+        glyphs.rebuild((32..127_u8).map(|ch| (DEFAULT_FONT, char::from(ch))));
+        glyphs.flush(device)?;
 
         let result = TextPool {
             texts: Vec::new(),
             aspect_ratio: dimension.width as f32 / dimension.height as f32,
-            attributes, glyphs, dimension,
+            attributes, vertex_capacity, glyphs, dimension,
         };
         Ok(result)
     }
 
+    pub fn add_font(&mut self, source: FontSource, size: f32, mode: GlyphMode) -> VkResult<FontID> {
+
+        let face = FontFace::load(source, size, mode)?;
+        Ok(self.glyphs.add_face(face))
+    }
+
+    pub fn font_metrics(&self, font: FontID, scale: f32) -> VkResult<FontMetrics> {
+
+        let face = self.glyphs.face(font)
+            .ok_or_else(|| VkError::custom(format!("Font {} has not been added.", font)))?;
+
+        // the text is scaled with the width of screen.
+        let pixel_size = scale * DISPLAY_SCALE_FIX * self.dimension.width as f32;
+        Ok(face.metrics(pixel_size))
+    }
+
     pub fn add_text(&mut self, mut text: TextInfo) -> VkResult<TextID> {
 
-        if self.texts.len() < MAXIMUM_SENTENCE_COUNT {
-            if text.content.len() <= MAXIMUM_SENTENCE_TEXT_COUNT {
+        let font_size = self.glyphs.face(text.font)
+            .ok_or_else(|| VkError::custom(format!("Font {} has not been added.", text.font)))?
+            .size();
+        text.scale *= DISPLAY_SCALE_FIX / font_size;
+
+        let entry = TextEntry { info: text, vertices: Vec::new() };
+        let new_text_id = match self.texts.iter().position(Option::is_none) {
+            | Some(free_slot) => {
+                self.texts[free_slot] = Some(entry);
+                free_slot
+            },
+            | None => {
+                self.texts.push(Some(entry));
+                self.texts.len() - 1
+            },
+        };
+
+        // update the text that is newly added.
+        self.update_texts(new_text_id);
+
+        Ok(new_text_id)
+    }
+
+    /// Nothing happens if `update_text` has been removed.
+    pub fn change_text(&mut self, content: String, update_text: TextID) {
 
-                text.scale *= DISPLAY_SCALE_FIX / FONT_SCALE;
+        if let Some(Some(entry)) = self.texts.get_mut(update_text) {
+            entry.info.content = content;
+            self.update_texts(update_text);
+        }
+    }
 
-                let new_text_id = self.texts.len();
-                self.texts.push(text);
-                // update the text that is newly added.
-                self.update_texts(new_text_id);
+    /// Remove the text from pool, whose id may be reused by the texts added later.
+    ///
+    /// Nothing happens if `remove_text` has already been removed.
+    pub fn remove_text(&mut self, remove_text: TextID) {
 
-                Ok(new_text_id)
-            } else {
-                Err(VkError::custom(format!("Each sentence can't contain more that {} character.", MAXIMUM_SENTENCE_TEXT_COUNT)))
+        if let Some(slot) = self.texts.get_mut(remove_text) {
+            if slot.take().is_some() {
+                self.mark_outdated();
             }
+        }
+    }
+
+    fn update_texts(&mut self, update_text: TextID) {
+
+        let text = &self.texts[update_text].as_ref().unwrap().info;
+        let font = text.font;
+
+        if self.glyphs.cache(text.iter().flatten().map(|ch| (font, ch))) {
+
+            let vertices = self.generate_attributes(text);
+            self.texts[update_text].as_mut().unwrap().vertices = vertices;
         } else {
-            Err(VkError::custom(format!("The text pool can't contain more than {} sentence.", MAXIMUM_SENTENCE_COUNT)))
+            // the atlas is full, so evict the glyphs not used by any text, which also moves the remaining glyphs.
+            self.glyphs.rebuild(self.texts.iter().flatten().flat_map(|entry| {
+                let font = entry.info.font;
+                entry.info.iter().flatten().map(move |ch| (font, ch))
+            }));
+
+            self.regenerate_all();
         }
+
+        self.mark_outdated();
     }
 
-    pub fn change_text(&mut self, content: String, update_text: TextID) {
+    fn regenerate_all(&mut self) {
 
-        self.texts[update_text].content = content;
-        self.update_texts(update_text);
+        for text_id in 0..self.texts.len() {
+            if let Some(entry) = self.texts[text_id].as_ref() {
+                let vertices = self.generate_attributes(&entry.info);
+                self.texts[text_id].as_mut().unwrap().vertices = vertices;
+            }
+        }
     }
 
-    fn update_texts(&self, update_text: TextID) {
+    fn mark_outdated(&mut self) {
 
-        // calculate vertices attributes of rendering texts.
-        let mut char_vertices = Vec::with_capacity(self.texts.len() * MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER);
+        for storage in self.attributes.iter_mut() {
+            storage.is_outdated = true;
+        }
+    }
 
-        let text = &self.texts[update_text];
+    fn generate_attributes(&self, text: &TextInfo) -> Vec<CharacterVertex> {
+
+        // calculate vertices attributes of rendering texts.
+        let mut char_vertices = Vec::with_capacity(text.character_count() * VERTEX_PER_CHARACTER);
+
+        let atlas_dimension = self.glyphs.dimension();
+
+        let fill_color: [f32; 4] = text.color.into();
+        let (spread, is_distance_field) = self.glyphs.face(text.font)
+            .map(|face| (face.spread(), face.mode == GlyphMode::DistanceField))
+            .unwrap_or((0.0, false));
+        let (outline_width, outline_color) = match text.outline {
+            | Some(outline) if is_distance_field => (self.field_distance(text, spread, outline.width), outline.color.into()),
+            // the outline color is blended with the fill color by shader, so use the fill color if there is no outline.
+            | _ => (0.0, fill_color),
+        };
+        let params = [if is_distance_field { 1.0 } else { 0.0 }, outline_width, 0.0, 0.0];
 
         let mut origin_x = text.location.x as f32 / self.dimension.width as f32;
         let origin_y = text.location.y as f32 / self.dimension.height as f32;
+        let mut last_character = None;
 
         for ch in text.iter() {
 
             // use ' '(space) character instead if all the characters of current text has been rendered, but not yet reached its capacity.
             let character_id = ch.unwrap_or(' ');
 
-            let glyph_layout = self.glyphs.layouts.get(&character_id)
-                .expect(&format!("Find invalid character: {}({}).", character_id, character_id as u8));
+            if let (Some(last), Some(current)) = (last_character, ch) {
+                origin_x += self.glyphs.kerning(text.font, last, current) * text.scale;
+            }
+            last_character = ch;
+
+            let glyph_layout = match self.glyphs.layout(text.font, character_id) {
+                | Some(layout) => layout,
+                | None => {
+                    // the glyph is left out of the full atlas, so draw nothing for it.
+                    let empty_vertex = CharacterVertex { pos: [0.0; 2], uv: [0.0; 2], color: [0.0; 4], outline_color: [0.0; 4], params: [0.0; 4] };
+                    char_vertices.extend_from_slice(&[empty_vertex; VERTEX_PER_CHARACTER]);
+                    continue
+                },
+            };
+
+            let (min_uv, max_uv) = match glyph_layout.region {
+                | Some(region) => {
+                    let (atlas_width, atlas_height) = (atlas_dimension.width as f32, atlas_dimension.height as f32);
+                    ([region.min.x as f32 / atlas_width, region.min.y as f32 / atlas_height],
+                     [region.max.x as f32 / atlas_width, region.max.y as f32 / atlas_height])
+                },
+                // set the same uv for min and max position, so that nothing will be render for space.
+                | None => ([0.0; 2], [0.0; 2]),
+            };
 
             let x_offset     = glyph_layout.bounding_box.min.x    * text.scale;
             let y_offset     = glyph_layout.bounding_box.min.y    * text.scale * self.aspect_ratio;
@@ -313,29 +428,23 @@ impl TextPool {
 
             let top_left = CharacterVertex {
                 pos: [min_x, min_y],
-                uv: glyph_layout.min_uv,
-                color: text.color.into(),
+                uv: min_uv,
+                color: fill_color, outline_color, params,
             };
             let bottom_left = CharacterVertex {
                 pos: [min_x, max_y],
-                uv: [
-                    glyph_layout.min_uv[0],
-                    glyph_layout.max_uv[1],
-                ],
-                color: text.color.into(),
+                uv: [min_uv[0], max_uv[1]],
+                color: fill_color, outline_color, params,
             };
             let bottom_right = CharacterVertex {
                 pos: [max_x, max_y],
-                uv: glyph_layout.max_uv,
-                color: text.color.into(),
+                uv: max_uv,
+                color: fill_color, outline_color, params,
             };
             let top_right = CharacterVertex {
                 pos: [max_x, min_y],
-                uv: [
-                    glyph_layout.max_uv[0],
-                    glyph_layout.min_uv[1],
-                ],
-                color: text.color.into(),
+                uv: [max_uv[0], min_uv[1]],
+                color: fill_color, outline_color, params,
             };
 
             char_vertices.extend_from_slice(&[
@@ -368,219 +477,109 @@ impl TextPool {
             },
         }
 
-        // upload vertices attributes to memory.
-        unsafe {
-            let target_ptr = (self.attributes.data_ptr as vkptr<CharacterVertex>)
-                .offset((MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER * update_text) as isize);
-            target_ptr.copy_from(char_vertices.as_ptr(), char_vertices.len());
+        if let Some(shadow) = text.shadow {
+
+            // draw the shadow before the text, so that the text covers it.
+            let shadow_color: [f32; 4] = shadow.color.into();
+            let softness = if is_distance_field { self.field_distance(text, spread, shadow.softness) } else { 0.0 };
+            let offset_x = shadow.offset.x as f32 / self.dimension.width  as f32 * 2.0;
+            let offset_y = shadow.offset.y as f32 / self.dimension.height as f32 * 2.0;
+
+            let mut shadow_vertices: Vec<CharacterVertex> = char_vertices.iter().map(|vertex| {
+                CharacterVertex {
+                    pos: [vertex.pos[0] + offset_x, vertex.pos[1] + offset_y],
+                    uv: vertex.uv,
+                    color: shadow_color,
+                    outline_color: shadow_color,
+                    params: [vertex.params[0], vertex.params[1], softness, 0.0],
+                }
+            }).collect();
+
+            shadow_vertices.extend(char_vertices);
+            char_vertices = shadow_vertices;
         }
+
+        char_vertices
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
+    /// Convert the distance in pixels of screen to the distance in the value of distance field, which is limited by the spread of font face.
+    fn field_distance(&self, text: &TextInfo, spread: f32, pixels: f32) -> f32 {
 
-        recorder.bind_vertex_buffers(0, &[self.attributes.buffer], &[0]);
+        // `text.scale` has been divided by the font size, so it maps the pixels in atlas to the range [0.0, 1.0] of screen width.
+        let atlas_pixels = pixels / (text.scale * self.dimension.width as f32);
+        // the value of distance field changes by 0.5 within the spread.
+        (atlas_pixels / (2.0 * spread)).max(0.0).min(0.5)
+    }
 
-        let mut first_vertex = 0;
-        for text in self.texts.iter() {
+    /// Write the vertices of all texts to the buffer of `frame_index` if they have changed.
+    ///
+    /// This method must be called when GPU is not reading the buffer of `frame_index`.
+    /// Return true if the buffers have been reallocated, in which case the commands recorded by `record_command` are invalid.
+    pub fn update(&mut self, device: &VkDevice, frame_index: usize) -> VkResult<bool> {
 
-            let character_count = match text.r#type {
-                | TextType::Static => text.content.len(),
-                | TextType::Dynamic { capacity } => capacity,
-            };
-            let render_vertex_count = (character_count * VERTEX_PER_CHARACTER) as vkuint;
-            recorder.draw(render_vertex_count, 1, first_vertex, 0);
-            first_vertex += (MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER) as vkuint;
-        }
-    }
+        let vertex_count: usize = self.texts.iter().flatten().map(|entry| entry.vertices.len()).sum();
 
-    pub fn swapchain_reload(&mut self) {
+        let is_reallocated = vertex_count > self.vertex_capacity;
+        if is_reallocated {
 
-        for i in 0..self.texts.len() {
-            self.update_texts(i);
+            // the buffers of other frames may still be in use, and all of them are reallocated to the same capacity.
+            unsafe {
+                device.logic.handle.device_wait_idle()
+                    .map_err(|_| VkError::device("Device Wait Idle"))?;
+            }
+
+            let new_capacity = vertex_count.next_power_of_two();
+            for storage in self.attributes.iter_mut() {
+                let old_storage = ::std::mem::replace(storage, TextAttrStorage::new(device, new_capacity)?);
+                old_storage.discard(device);
+            }
+            self.vertex_capacity = new_capacity;
         }
-    }
 
-    pub fn glyphs_ref(&self) -> &GlyphImages {
-        &self.glyphs
+        let frame_slot = frame_index % self.attributes.len();
+        let storage = &mut self.attributes[frame_slot];
+        if storage.is_outdated {
+            storage.write(self.texts.iter().flatten(), vertex_count);
+        }
+
+        Ok(is_reallocated)
     }
 
-    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+    /// Draw all the texts with the buffer of `frame_index`, where the vertex count is read from buffer,
+    /// so the recorded commands are still valid after the texts are added, changed or removed.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, frame_index: usize) {
 
-        self.attributes.discard(device);
-        self.glyphs.discard(device)
-    }
-}
+        let storage = &self.attributes[frame_index % self.attributes.len()];
 
-fn generate_ascii_glyphs_bytes(font_bytes: &[u8], font_scale: f32) -> VkResult<(GlyphLayouts, Vec<u8>, vk::Extent2D)> {
-
-    use rusttype::{Font, Scale, PositionedGlyph, point};
-
-    /// the ascii character range that render to sampled glyph.
-    const ASCII_RANGE: Range<u8> = (33..127_u8);
-
-    let font = Font::from_bytes(font_bytes)
-        .map_err(|e| VkError::custom(e.to_string()))?;
-    let ascii_bytes: Vec<u8> = ASCII_RANGE.collect();
-
-    let ascii_characters = unsafe { String::from_utf8_unchecked(ascii_bytes.clone()) };
-
-    let scale = Scale::uniform(font_scale);
-    let v_metrics = font.v_metrics(scale);
-
-    let glyphs_start_point = point(IMAGE_PADDING as f32, IMAGE_PADDING as f32 + v_metrics.ascent);
-    let glyphs: Vec<PositionedGlyph> = font.layout(&ascii_characters, scale, glyphs_start_point)
-        .collect();
-    let glyphs_height = (v_metrics.ascent - v_metrics.descent).ceil() as usize;
-    let glyphs_width = {
-        let min_x = glyphs.first()
-            .map(|g| g.pixel_bounding_box().unwrap().min.x)
-            .unwrap();
-        let max_x = glyphs.last()
-            .map(|g| g.pixel_bounding_box().unwrap().max.x)
-            .unwrap();
-        (max_x - min_x) as usize
-    };
-
-    let image_width  = (2 * IMAGE_PADDING) + glyphs_width;
-    let image_height = (2 * IMAGE_PADDING) + glyphs_height;
-    let bytes_per_pixel = 1; // only store the alpha value.
-
-    // fill image data with empty bytes.
-    let mut image_bytes = vec![0_u8; image_width * image_height * bytes_per_pixel];
-
-    let mut glyph_layouts = GlyphLayouts::new();
-
-    // fill color to image data.
-    for (glyph, character) in glyphs.iter().zip(ascii_bytes.into_iter()) {
-
-        if let Some(bounding_box) = glyph.pixel_bounding_box() {
-            // Draw the glyph into the image per-pixel by using the draw closure.
-            glyph.draw(|x, y, v| {
-
-                let x = x + bounding_box.min.x as u32;
-                let y = y + bounding_box.min.y as u32;
-                let pos = (x + y * image_width as u32) as usize * bytes_per_pixel;
-
-                image_bytes[pos] = (v * 255.0) as u8;
-            });
-
-            let min_uv = [
-                bounding_box.min.x as f32 / image_width  as f32,
-                bounding_box.min.y as f32 / image_height as f32,
-            ];
-            let max_uv = [
-                bounding_box.max.x as f32 / image_width  as f32,
-                bounding_box.max.y as f32 / image_height as f32,
-            ];
-
-            let glyph_unpositioned = glyph.unpositioned();
-            let glyph_layout = GlyphLayout {
-                min_uv, max_uv,
-                h_metrics: glyph_unpositioned.h_metrics(),
-                bounding_box: fix_bounding_box_positive(glyph_unpositioned.exact_bounding_box().unwrap(), &v_metrics),
-            };
-            glyph_layouts.insert(character as CharacterID, glyph_layout);
-        }
+        recorder.bind_vertex_buffers(0, &[storage.buffer], &[VERTEX_OFFSET]);
+        recorder.draw_indirect(storage.buffer, 0, 1, ::std::mem::size_of::<vk::DrawIndirectCommand>() as vkuint);
     }
 
-    // set the layout of space the same with 't', since space does not have a bounding box.
-    let mut space_layout = glyph_layouts.get(&'t').unwrap().clone();
-    // set the same uv for min and max position, so that nothing will be render for space.
-    space_layout.max_uv = space_layout.min_uv;
-    glyph_layouts.insert(' ', space_layout);
-
+    pub fn swapchain_reload(&mut self, dimension: vk::Extent2D) {
 
-    let dimension = vk::Extent2D {
-        width : image_width  as vkuint,
-        height: image_height as vkuint,
-    };
-    Ok((glyph_layouts, image_bytes, dimension))
-}
+        self.dimension = dimension;
+        self.aspect_ratio = dimension.width as f32 / dimension.height as f32;
 
-fn allocate_glyph_image(device: &mut VkDevice, image_bytes: Vec<u8>, image_dimension: vk::Extent2D) -> VkResult<VmaImage> {
-
-    // create vk::Image to store glyphs data.
-    let glyphs_image = {
-
-        let glyphs_image_ci = ImageCI::new_2d(vk::Format::R8_UNORM, image_dimension)
-            .usages(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST);
-        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
-        let image_allocation = device.vma.create_image(glyphs_image_ci.as_ref(), allocation_ci.as_ref())
-            .map_err(VkErrorKind::Vma)?;
-        VmaImage::from(image_allocation)
-    };
-
-    // create staging buffer and map image data to it.
-    let staging_buffer = {
-
-        let estimate_buffer_size = (image_bytes.len() as vkbytes) * (::std::mem::size_of::<u8>() as vkbytes);
-        let staging_ci = BufferCI::new(estimate_buffer_size)
-            .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-        let staging_allocation = device.vma.create_buffer(staging_ci.as_ref(), allocation_ci.as_ref())
-            .map_err(VkErrorKind::Vma)?;
-
-        let data_ptr = device.vma.map_memory(&staging_allocation.1)
-            .map_err(VkErrorKind::Vma)? as vkptr<u8>;
-
-        unsafe { data_ptr.copy_from_nonoverlapping(image_bytes.as_ptr(), image_bytes.len()); }
-
-        device.vma.unmap_memory(&staging_allocation.1)
-            .map_err(VkErrorKind::Vma)?;
-
-        VmaBuffer::from(staging_allocation)
-    };
-
-    // transfer image data from staging buffer to destination image.
-    let recorder = device.get_transfer_recorder();
-
-    let copy_region = vk::BufferImageCopy {
-        buffer_offset: 0,
-        buffer_row_length  : 0,
-        buffer_image_height: 0,
-        image_subresource: vk::ImageSubresourceLayers {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            mip_level: 0,
-            base_array_layer: 0, layer_count: 1,
-        },
-        image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-        image_extent: vk::Extent3D { width: image_dimension.width, height: image_dimension.height, depth: 1 },
-    };
-
-    let image_range = vk::ImageSubresourceRange {
-        aspect_mask: vk::ImageAspectFlags::COLOR,
-        base_mip_level  : 0, level_count: 1,
-        base_array_layer: 0, layer_count: 1,
-    };
-    let copy_dst_barrier = ImageBarrierCI::new(glyphs_image.handle, image_range)
-        .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
-        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
-    let shader_read_barrier = ImageBarrierCI::new(glyphs_image.handle, image_range)
-        .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
-        .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
-
-    recorder.begin_record()?
-        .image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[copy_dst_barrier.into()])
-        .copy_buf2img(staging_buffer.handle, glyphs_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy_region])
-        .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[shader_read_barrier.into()])
-        .end_record()?;
-
-    device.flush_transfer(recorder)?;
-
-    // clean useless resources.
-    device.vma_discard(staging_buffer)?;
-
-    Ok(glyphs_image)
-}
+        self.regenerate_all();
+        self.mark_outdated();
+    }
 
+    /// Upload the glyphs rasterized since last call, and return true if the atlas image has been recreated.
+    pub fn flush_glyphs(&mut self, device: &mut VkDevice) -> VkResult<bool> {
+        self.glyphs.flush(device)
+    }
 
-// TODO: Fix and remove this magic function.
-fn fix_bounding_box_positive(mut rect: Rect<f32>, v_metrics: &VMetrics) -> Rect<f32> {
+    pub fn glyphs_ref(&self) -> &GlyphAtlas {
+        &self.glyphs
+    }
 
-    rect.min.y += v_metrics.ascent as f32;
-    rect.max.y += v_metrics.ascent as f32;
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-    rect
+        for storage in self.attributes {
+            storage.discard(device);
+        }
+        self.glyphs.discard(device)
+    }
 }
 
 pub fn input_descriptions() -> VertexInputSCI {
@@ -609,4 +608,16 @@ pub fn input_descriptions() -> VertexInputSCI {
             format  : vk::Format::R32G32B32A32_SFLOAT,
             offset  : offset_of!(CharacterVertex, color) as _,
         })
+        .add_attribute(vk::VertexInputAttributeDescription {
+            location: 3,
+            binding : 0,
+            format  : vk::Format::R32G32B32A32_SFLOAT,
+            offset  : offset_of!(CharacterVertex, outline_color) as _,
+        })
+        .add_attribute(vk::VertexInputAttributeDescription {
+            location: 4,
+            binding : 0,
+            format  : vk::Format::R32G32B32A32_SFLOAT,
+            offset  : offset_of!(CharacterVertex, params) as _,
+        })
 }
@@ -5,9 +5,13 @@
 layout (location = 0) in vec2 inPos;
 layout (location = 1) in vec2 inUV;
 layout (location = 2) in vec4 inColor;
+layout (location = 3) in vec4 inOutlineColor;
+layout (location = 4) in vec4 inParams;
 
 layout (location = 0) out vec2 outUV;
 layout (location = 1) out vec4 outColor;
+layout (location = 2) out vec4 outOutlineColor;
+layout (location = 3) out vec4 outParams;
 
 void main() {
 
@@ -15,4 +19,6 @@ void main() {
 
     outUV = inUV;
     outColor = inColor;
+    outOutlineColor = inOutlineColor;
+    outParams = inParams;
 }
@@ -40,6 +40,8 @@ impl ProcPipeline {
     pub fn launch(mut self, mut app: impl RenderWorkflow) -> VkResult<()> {
 
         app.init(&self.vulkan.device)?;
+        // make sure all the resources uploaded during initialization are ready before rendering.
+        self.vulkan.device.wait_uploads()?;
 
         self.main_loop(&mut app)?;
 
@@ -48,9 +50,7 @@ impl ProcPipeline {
         app.deinit(&mut self.vulkan.device)?;
         // and then free vulkan context resource.
         self.syncs.discard(&self.vulkan.device);
-        self.vulkan.discard();
-
-        Ok(())
+        self.vulkan.discard()
     }
 
     fn main_loop(&mut self, app: &mut impl RenderWorkflow) -> VkResult<()> {
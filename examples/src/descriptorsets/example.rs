@@ -12,7 +12,7 @@ use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::gltf::VkglTFModel;
-use vkbase::texture::Texture2D;
+use vkbase::texture::{Texture2D, TextureLoadOptions};
 use vkbase::context::VulkanContext;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkbytes, vkptr, Vec3F, Mat4F};
@@ -104,6 +104,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms(delta_time)?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -158,7 +163,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
             device.vma_discard(cube.uniform_buffer)?;
             cube.texture.discard_by(device)?;
         }
-        device.vma_discard(self.model)?;
+        self.model.discard_by(device)?;
         self.backend.discard_by(device)
     }
 }
@@ -202,12 +207,20 @@ impl VulkanExample {
                     descriptor_set : self.cubes[j].descriptor_set,
                     pipeline_layout: self.pipelines.layout,
                     material_stage : None,
+                    material_set   : None,
+                    material_pipelines: None,
+                    view_position  : None,
+                    frame_index    : i,
+                    skin_set       : None,
+                    morph_set      : None,
+                    topology       : None,
+                    instance_set   : None,
                 };
 
                 self.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -249,15 +262,20 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, SceneSelection, GltfSource, MeshOptimizeFlags};
 
     let model_info = GltfModelInfo {
-        path: Path::new(CUBE_MODEL_PATH),
+        source: GltfSource::File(Path::new(CUBE_MODEL_PATH)),
+        scene: SceneSelection::Default,
         // specify model's vertices layout.
         // in cube.vert.glsl:
         // layout (location = 0) in vec3 inPos;
         // layout (location = 1) in vec2 inUV;
-        attribute: AttributeFlags::POSITION | AttributeFlags::TEXCOORD_0,
+        attribute: (AttributeFlags::POSITION | AttributeFlags::TEXCOORD_0).into(),
+        unroll_strips: false,
+        generate_normals: false,
+        generate_tangents: false,
+        optimization: MeshOptimizeFlags::NONE,
         // specify model's node attachment layout.
         // in cube.vert.glsl
         // layout (set = 0, binding = 1) uniform DynNode {
@@ -265,6 +283,9 @@ pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
         // } dyn_node;
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        // the nodes of this model are never changed at runtime.
+        frame_in_flight: 1,
+        cache: None,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -322,7 +343,7 @@ fn prepare_uniform(device: &mut VkDevice, camera: &FlightCamera) -> VkResult<Arr
             // the descriptor_set member will be set in setup_descriptor() method.
             descriptor_set: vk::DescriptorSet::null(),
             uniform_buffer: ubo_buffer,
-            texture : Texture2D::load_ktx(device, Path::new(CUBE_TEXTURE_PATHS[i]), vk::Format::R8G8B8A8_UNORM)?,
+            texture : Texture2D::load_ktx(device, Path::new(CUBE_TEXTURE_PATHS[i]), vk::Format::R8G8B8A8_UNORM, TextureLoadOptions::default())?,
             rotation: 0.0,
         };
         cubes.push(cube);
@@ -92,6 +92,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms(device, delta_time)?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -195,7 +200,7 @@ impl VulkanExample {
                     .draw_indexed(INDEX_DATA.len() as vkuint, 1, 0, 0, 0);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
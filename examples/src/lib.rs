@@ -8,7 +8,7 @@ use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::sync::SemaphoreCI;
 use vkbase::ci::image::{ImageCI, ImageViewCI};
 use vkbase::ci::vma::{VmaImage, VmaAllocationCI};
-use vkbase::ui::{UIRenderer, TextInfo, TextID, TextType, TextHAlign};
+use vkbase::ui::{UIRenderer, TextInfo, TextID, TextType, TextHAlign, DEFAULT_FONT};
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::utils::color::VkColor;
@@ -127,27 +127,36 @@ impl VkExampleBackend {
         let title_text = TextInfo {
             content: String::from(title),
             scale: 12.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 0 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Static,
         };
 
         let device_text = TextInfo {
             content: device.phy.device_name.clone(),
             scale: 12.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 40 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Static,
         };
 
         let fps_text = TextInfo {
             content: String::from("FPS: 00.00"),
             scale: 12.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 80 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Dynamic { capacity: 15 },
         };
 
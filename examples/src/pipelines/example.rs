@@ -11,7 +11,7 @@ use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
 use vkbase::gltf::VkglTFModel;
-use vkbase::ui::{TextInfo, TextType, TextHAlign};
+use vkbase::ui::{TextInfo, TextType, TextHAlign, DEFAULT_FONT};
 use vkbase::context::VulkanContext;
 use vkbase::utils::color::VkColor;
 use vkbase::{FlightCamera, FrameAction};
@@ -103,9 +103,12 @@ impl vkbase::RenderWorkflow for VulkanExample {
         let phong_text = TextInfo {
             content: String::from("Phong Shading Pipeline"),
             scale: 16.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6, y: screen_height / 8 * 7 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Static,
         };
         self.backend.ui_renderer.add_text(phong_text)?;
@@ -113,9 +116,12 @@ impl vkbase::RenderWorkflow for VulkanExample {
         let toon_text = TextInfo {
             content: String::from("Toon Shading Pipeline"),
             scale: 16.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6 * 3, y: screen_height / 8 * 7 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Static,
         };
         self.backend.ui_renderer.add_text(toon_text)?;
@@ -123,9 +129,12 @@ impl vkbase::RenderWorkflow for VulkanExample {
         let wireframe_text = TextInfo {
             content: String::from("Wireframe Pipeline"),
             scale: 16.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6 * 5 , y: screen_height / 8 * 7 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Static,
         };
         self.backend.ui_renderer.add_text(wireframe_text)?;
@@ -141,6 +150,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
             self.update_uniforms()?;
         }
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -198,7 +212,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.pipelines.layout);
 
         device.vma_discard(self.uniform_buffer)?;
-        device.vma_discard(self.model)?;
+        self.model.discard_by(device)?;
         self.backend.discard_by(device)
     }
 }
@@ -221,6 +235,14 @@ impl VulkanExample {
                 descriptor_set : self.descriptors.set,
                 pipeline_layout: self.pipelines.layout,
                 material_stage : Some(vk::ShaderStageFlags::VERTEX),
+                material_set   : None,
+                material_pipelines: None,
+                view_position  : None,
+                frame_index    : i,
+                skin_set       : None,
+                morph_set      : None,
+                topology       : None,
+                instance_set   : None,
             };
 
             let mut viewport = vk::Viewport {
@@ -270,7 +292,7 @@ impl VulkanExample {
                 }
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -297,13 +319,21 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, SceneSelection, GltfSource, MeshOptimizeFlags};
 
     let model_info = GltfModelInfo {
-        path: Path::new(MODEL_PATH),
-        attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL, // specify model's vertices layout.
+        source: GltfSource::File(Path::new(MODEL_PATH)),
+        scene: SceneSelection::Default,
+        attribute: (AttributeFlags::POSITION | AttributeFlags::NORMAL).into(), // specify model's vertices layout.
+        unroll_strips: false,
+        generate_normals: false,
+        generate_tangents: false,
+        optimization: MeshOptimizeFlags::NONE,
         node: NodeAttachmentFlags::TRANSFORM_MATRIX, // specify model's node attachment layout.
         transform: None,
+        // the nodes of this model are never changed at runtime.
+        frame_in_flight: 1,
+        cache: None,
     };
 
     let model = load_gltf(device, model_info)?;
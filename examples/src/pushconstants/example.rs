@@ -109,6 +109,9 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update(delta_time);
 
+        // upload the texts changed since last frame, the command buffer is recorded again below anyway.
+        self.backend.ui_renderer.update(device, image_index)?;
+
         // Refresh the push constant data for current command buffer.
         self.rebuild_command(device, image_index)?;
 
@@ -165,7 +168,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.pipelines.layout);
 
         device.vma_discard(self.ubo_buffer)?;
-        device.vma_discard(self.model)?;
+        self.model.discard_by(device)?;
         self.backend.discard_by(device)
     }
 }
@@ -248,11 +251,19 @@ impl VulkanExample {
             descriptor_set : self.descriptors.set,
             pipeline_layout: self.pipelines.layout,
             material_stage : None,
+            material_set   : None,
+            material_pipelines: None,
+            view_position  : None,
+            frame_index    : command_index,
+            skin_set       : None,
+            morph_set      : None,
+            topology       : None,
+            instance_set   : None,
         };
 
         self.model.record_command(&recorder, &render_params);
 
-        self.backend.ui_renderer.record_command(&recorder);
+        self.backend.ui_renderer.record_command(&recorder, command_index);
 
         recorder
             .end_render_pass()
@@ -282,15 +293,20 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, SceneSelection, GltfSource, MeshOptimizeFlags};
 
     let model_info = GltfModelInfo {
-        path: Path::new(MODEL_PATH),
+        source: GltfSource::File(Path::new(MODEL_PATH)),
+        scene: SceneSelection::Default,
         // specify model's vertices layout.
         // in light.vert.glsl:
         // layout (location = 0) in vec3 inPos;
         // layout (location = 1) in vec3 inNormal;
-        attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL,
+        attribute: (AttributeFlags::POSITION | AttributeFlags::NORMAL).into(),
+        unroll_strips: false,
+        generate_normals: false,
+        generate_tangents: false,
+        optimization: MeshOptimizeFlags::NONE,
         // specify model's node attachment layout.
         // in light.vert.glsl
         // layout (set = 0, binding = 1) uniform DynNode {
@@ -298,6 +314,9 @@ pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
         // } dyn_node;
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        // the nodes of this model are never changed at runtime.
+        frame_in_flight: 1,
+        cache: None,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -11,7 +11,7 @@ use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
 use vkbase::gltf::VkglTFModel;
-use vkbase::texture::Texture2D;
+use vkbase::texture::{Texture2D, TextureLoadOptions};
 use vkbase::context::VulkanContext;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkbytes, vkuint, vkfloat, vkptr, Vec3F, Vec4F, Mat4F};
@@ -75,7 +75,7 @@ impl VulkanExample {
         let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
 
         let model = prepare_model(device)?;
-        let color_map = Texture2D::load_ktx(device, Path::new(TEXTURE_PATH), vk::Format::R8G8B8A8_UNORM)?;
+        let color_map = Texture2D::load_ktx(device, Path::new(TEXTURE_PATH), vk::Format::R8G8B8A8_UNORM, TextureLoadOptions::default())?;
         let ubo_buffer = prepare_uniform(device)?;
         let descriptors = setup_descriptor(device, &ubo_buffer, &model, &color_map)?;
 
@@ -105,6 +105,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms()?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -162,7 +167,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.pipelines.layout);
 
         device.vma_discard(self.ubo_buffer)?;
-        device.vma_discard(self.model)?;
+        self.model.discard_by(device)?;
 
         self.color_map.discard_by(device)?;
         self.backend.discard_by(device)
@@ -187,6 +192,14 @@ impl VulkanExample {
                 descriptor_set : self.descriptors.set,
                 pipeline_layout: self.pipelines.layout,
                 material_stage : Some(vk::ShaderStageFlags::VERTEX),
+                material_set   : None,
+                material_pipelines: None,
+                view_position  : None,
+                frame_index    : i,
+                skin_set       : None,
+                morph_set      : None,
+                topology       : None,
+                instance_set   : None,
             };
 
             let mut viewport = vk::Viewport {
@@ -230,7 +243,7 @@ impl VulkanExample {
                 self.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -260,15 +273,23 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, SceneSelection, GltfSource, MeshOptimizeFlags};
 
     let model_info = GltfModelInfo {
-        path: Path::new(MODEL_PATH),
+        source: GltfSource::File(Path::new(MODEL_PATH)),
+        scene: SceneSelection::Default,
         // specify model's vertices layout.
-        attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL | AttributeFlags::TEXCOORD_0,
+        attribute: (AttributeFlags::POSITION | AttributeFlags::NORMAL | AttributeFlags::TEXCOORD_0).into(),
+        unroll_strips: false,
+        generate_normals: false,
+        generate_tangents: false,
+        optimization: MeshOptimizeFlags::NONE,
         // specify model's node attachment layout.
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        // the nodes of this model are never changed at runtime.
+        frame_in_flight: 1,
+        cache: None,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -8,7 +8,7 @@ use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::vma::VmaBuffer;
 use vkbase::utils::color::VkColor;
-use vkbase::ui::{TextInfo, TextType, TextHAlign};
+use vkbase::ui::{TextInfo, TextType, TextHAlign, DEFAULT_FONT};
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkuint, vkptr, Vec3F, Vec4F};
 use vkbase::VkResult;
@@ -85,9 +85,12 @@ impl vkbase::RenderWorkflow for VulkanExample {
         let lod_text = TextInfo {
             content: format!("Lod bias: {:1.2} (numpad +/- to change)", self.ubo_data.lod_bias),
             scale: 14.0,
+            font: DEFAULT_FONT,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 140 },
+            outline: None,
+            shadow: None,
             r#type: TextType::Dynamic { capacity: 40 },
         };
         self.lod_text_id = self.backend.ui_renderer.add_text(lod_text)?;
@@ -101,6 +104,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms()?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -211,7 +219,7 @@ impl VulkanExample {
                 .bind_index_buffer(self.indices.handle, vk::IndexType::UINT32, 0)
                 .draw_indexed(INDEX_DATA.len() as vkuint, 1, 0, 0, 0);
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
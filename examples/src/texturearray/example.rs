@@ -7,12 +7,13 @@ use std::path::Path;
 use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::vma::VmaBuffer;
+use vkbase::texture::Texture2DArray;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkuint, vkptr, Vec3F};
 use vkbase::VkResult;
 
 use vkexamples::VkExampleBackend;
-use crate::data::{INDEX_DATA, Vertex, UboVS, TextureArray};
+use crate::data::{INDEX_DATA, Vertex, UboVS, load_texture_array};
 use crate::data::UboMatrices;
 
 const SHADER_VERTEX_PATH  : &'static str = "examples/src/texturearray/instancing.vert.glsl";
@@ -29,7 +30,7 @@ pub struct VulkanExample {
     ubo_buffer: VmaBuffer,
     ubo_data: UboVS,
 
-    texture: TextureArray,
+    texture: Texture2DArray,
 
     pipelines: PipelineStaff,
     descriptors: DescriptorStaff,
@@ -55,7 +56,7 @@ impl VulkanExample {
         let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
 
         let (vertices, indices) = super::data::generate_quad(device)?;
-        let texture = TextureArray::load(device)?;
+        let texture = load_texture_array(device)?;
         let (ubo_buffer, ubo_data) = UboVS::prepare_buffer(device, &camera, &texture)?;
 
         let descriptors = setup_descriptor(device, &ubo_buffer, &texture)?;
@@ -87,6 +88,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms()?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -183,7 +189,7 @@ impl VulkanExample {
                 .bind_index_buffer(self.indices.handle, vk::IndexType::UINT32, 0)
                 .draw_indexed(INDEX_DATA.len() as vkuint, self.texture.layer_count, 0, 0, 0);
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
@@ -216,7 +222,7 @@ struct DescriptorStaff {
     layout : vk::DescriptorSetLayout,
 }
 
-fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &TextureArray) -> VkResult<DescriptorStaff> {
+fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &Texture2DArray) -> VkResult<DescriptorStaff> {
 
     use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
     use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
@@ -273,11 +279,7 @@ fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &Texture
 
     // Setup a descriptor image info for the current texture to be used as a combined image sampler.
     let sampler_write = DescriptorImageSetWI::new(descriptor_set, 1, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .add_image(vk::DescriptorImageInfo {
-            sampler      : texture.sampler,
-            image_view   : texture.view,
-            image_layout : texture.layout,
-        });
+        .add_image(texture.descriptor);
 
     DescriptorSetsUpdateCI::new()
         .add_write(&ubo_write)
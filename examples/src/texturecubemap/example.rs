@@ -84,6 +84,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.update_uniforms(delta_time)?;
 
+        // upload the texts changed since last frame, and record the commands again if the ui resources have been recreated.
+        if self.backend.ui_renderer.update(device, image_index)? {
+            self.record_commands(device, self.backend.dimension)?;
+        }
+
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
             .add_command(self.backend.commands[image_index])
@@ -178,12 +183,20 @@ impl VulkanExample {
                     descriptor_set : self.skybox.descriptor_set,
                     pipeline_layout: self.pipelines.layout,
                     material_stage : None,
+                    material_set   : None,
+                    material_pipelines: None,
+                    view_position  : None,
+                    frame_index    : i,
+                    skin_set       : None,
+                    morph_set      : None,
+                    topology       : None,
+                    instance_set   : None,
                 };
 
                 self.skybox.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.ui_renderer.record_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -306,7 +319,7 @@ fn setup_descriptor(device: &VkDevice, skybox: &mut Skybox) -> VkResult<Descript
         .add_buffer(skybox.model.nodes.node_descriptor());
     // Binding 2: Object texture.
     let sampler_write_info = DescriptorImageSetWI::new(skybox.descriptor_set, 2, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .add_image(skybox.texture.descriptor());
+        .add_image(skybox.texture.descriptor);
 
     DescriptorSetsUpdateCI::new()
         .add_write(&ubo_write_info)